@@ -0,0 +1,195 @@
+//! The `#[lasr_program]` attribute, which implements `LasrProgram` for a
+//! type from the `#[op]` annotated functions of one of its `impl` blocks.
+//!
+//! ```ignore
+//! use lasr_rust::lasrctl::builders::lasr_program::{lasr_program, LasrProgram};
+//!
+//! pub struct Counter;
+//!
+//! #[lasr_program(main)]
+//! impl Counter {
+//!     /// Called for transactions with `"op": "increment"`, with their
+//!     /// `transactionInputs` deserialized into `IncrementInputs`.
+//!     #[op("increment")]
+//!     fn increment(inputs: &Inputs, args: IncrementInputs) -> anyhow::Result<Outputs> {
+//!         // ...
+//!     }
+//!
+//!     /// `#[op]` without a name routes the function's own name, and a
+//!     /// function taking only `&Inputs` receives the raw inputs.
+//!     #[op]
+//!     fn reset(inputs: &Inputs) -> anyhow::Result<Outputs> {
+//!         // ...
+//!     }
+//! }
+//! ```
+//!
+//! Options, given as `#[lasr_program(option, ...)]`:
+//! - `main` generates a `main` that runs the program against stdin.
+//! - `no_defaults` starts from an empty program instead of the default
+//!   `approve`, `create`, `update`, `mint` and `burn` strategies.
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+    parse_macro_input, punctuated::Punctuated, spanned::Spanned, Attribute, FnArg, ImplItem,
+    ItemImpl, LitStr, Meta, Token,
+};
+
+#[proc_macro_attribute]
+pub fn lasr_program(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let options = parse_macro_input!(attr with Punctuated::<Meta, Token![,]>::parse_terminated);
+    let item_impl = parse_macro_input!(item as ItemImpl);
+    expand(options, item_impl)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+struct Options {
+    main: bool,
+    no_defaults: bool,
+}
+
+fn parse_options(options: Punctuated<Meta, Token![,]>) -> syn::Result<Options> {
+    let mut parsed = Options {
+        main: false,
+        no_defaults: false,
+    };
+    for option in options {
+        match &option {
+            Meta::Path(path) if path.is_ident("main") => parsed.main = true,
+            Meta::Path(path) if path.is_ident("no_defaults") => parsed.no_defaults = true,
+            _ => {
+                return Err(syn::Error::new(
+                    option.span(),
+                    "unknown lasr_program option, expected `main` or `no_defaults`",
+                ))
+            }
+        }
+    }
+    Ok(parsed)
+}
+
+/// The op routed to a function, and whether it takes typed `transactionInputs`.
+struct OpMethod {
+    op: LitStr,
+    ident: syn::Ident,
+    typed: bool,
+}
+
+/// Removes the `#[op]` attribute from `attrs`, returning the op it names.
+fn take_op(attrs: &mut Vec<Attribute>, ident: &syn::Ident) -> syn::Result<Option<LitStr>> {
+    let Some(index) = attrs.iter().position(|attr| attr.path().is_ident("op")) else {
+        return Ok(None);
+    };
+    let attr = attrs.remove(index);
+    if attrs.iter().any(|attr| attr.path().is_ident("op")) {
+        return Err(syn::Error::new(
+            attr.span(),
+            "a function can only be routed one op",
+        ));
+    }
+    match &attr.meta {
+        Meta::Path(_) => Ok(Some(LitStr::new(&ident.to_string(), ident.span()))),
+        Meta::List(_) => attr.parse_args::<LitStr>().map(Some),
+        Meta::NameValue(_) => Err(syn::Error::new(
+            attr.span(),
+            "expected `#[op]` or `#[op(\"name\")]`",
+        )),
+    }
+}
+
+fn expand(
+    options: Punctuated<Meta, Token![,]>,
+    mut item_impl: ItemImpl,
+) -> syn::Result<TokenStream2> {
+    let options = parse_options(options)?;
+    if let Some((_, trait_path, _)) = &item_impl.trait_ {
+        return Err(syn::Error::new(
+            trait_path.span(),
+            "#[lasr_program] goes on an inherent impl block, not a trait impl",
+        ));
+    }
+
+    let mut methods = Vec::new();
+    for item in &mut item_impl.items {
+        let ImplItem::Fn(function) = item else {
+            continue;
+        };
+        let ident = function.sig.ident.clone();
+        let Some(op) = take_op(&mut function.attrs, &ident)? else {
+            continue;
+        };
+        let inputs = &function.sig.inputs;
+        if let Some(FnArg::Receiver(receiver)) = inputs.first() {
+            return Err(syn::Error::new(
+                receiver.span(),
+                "op functions are associated functions, taking `inputs: &Inputs` and optionally a typed `transactionInputs` argument",
+            ));
+        }
+        let typed = match inputs.len() {
+            1 => false,
+            2 => true,
+            _ => {
+                return Err(syn::Error::new(
+                    function.sig.span(),
+                    "op functions take `inputs: &Inputs` and optionally a typed `transactionInputs` argument",
+                ))
+            }
+        };
+        if methods
+            .iter()
+            .any(|method: &OpMethod| method.op.value() == op.value())
+        {
+            return Err(syn::Error::new(
+                op.span(),
+                format!("op '{}' is routed more than once", op.value()),
+            ));
+        }
+        methods.push(OpMethod { op, ident, typed });
+    }
+
+    let self_ty = &item_impl.self_ty;
+    let (impl_generics, _, where_clause) = item_impl.generics.split_for_impl();
+    let builders = quote!(::lasr_rust::lasrctl::builders);
+    let private = quote!(#builders::lasr_program::__private);
+
+    let registrations = methods.iter().map(|OpMethod { op, ident, typed }| {
+        if *typed {
+            quote!(program.register_method(#op, <#self_ty>::#ident);)
+        } else {
+            quote!(program.register_raw_method(#op, <#self_ty>::#ident);)
+        }
+    });
+    let program = options.no_defaults.then(|| {
+        quote! {
+            fn program() -> #builders::program::Program<#private::Inputs> {
+                let mut program = #builders::program::Program::empty();
+                <Self as #builders::lasr_program::LasrProgram>::register(&mut program);
+                program
+            }
+        }
+    });
+    let main = options.main.then(|| {
+        quote! {
+            #[allow(dead_code)]
+            fn main() -> #private::anyhow::Result<()> {
+                <#self_ty as #builders::lasr_program::LasrProgram>::run()
+            }
+        }
+    });
+
+    Ok(quote! {
+        #item_impl
+
+        impl #impl_generics #builders::lasr_program::LasrProgram for #self_ty #where_clause {
+            fn register(program: &mut #builders::program::Program<#private::Inputs>) {
+                #(#registrations)*
+            }
+
+            #program
+        }
+
+        #main
+    })
+}
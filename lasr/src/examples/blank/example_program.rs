@@ -1,23 +1,24 @@
-use crate::lasrctl::builders::program::{CreateTransactionInputs, Program};
-use anyhow::{bail, Ok};
+use crate::lasrctl::builders::{
+    lasr_program::{lasr_program, LasrProgram},
+    program::CreateTransactionInputs,
+};
+use anyhow::Ok;
 use lasr_types::*;
 use std::collections::BTreeMap;
 
-pub struct BlankProgram {
-    _program: Program<Inputs>,
-}
+/// A program with the default method strategies, plus `hello`.
+///
+/// `#[lasr_program(main)]` routes each `#[op]` function to the transactions
+/// with that op, and generates the `main` that runs the program on stdin.
+pub struct BlankProgram;
 
+#[lasr_program(main)]
 impl BlankProgram {
-    pub fn new(_inputs: Inputs) -> Program<Inputs> {
-        Program::new()
-    }
-
-    pub fn hello(inputs: Inputs) -> Result<String, anyhow::Error> {
-        let transaction = &inputs.transaction;
-        let txn_inputs: CreateTransactionInputs =
-            serde_json::from_str(&transaction.inputs().as_str())
-                .map_err(|e| anyhow::anyhow!("failed to deserialize txInputs: {e}"))?;
-
+    #[op("hello")]
+    pub fn hello(
+        inputs: &Inputs,
+        txn_inputs: CreateTransactionInputs,
+    ) -> Result<Outputs, anyhow::Error> {
         let name = serde_json::to_string(&format!("{:?} World", txn_inputs.name))
             .map_err(|e| anyhow::anyhow!("failed to serialize txn metadata: {e:?}"))?;
         let current_time = std::time::SystemTime::now();
@@ -41,32 +42,17 @@ impl BlankProgram {
             .build();
 
         let outputs: Outputs = OutputsBuilder::new()
-            .inputs(inputs)
+            .inputs(inputs.clone())
             .add_instruction(lasr_types::Instruction::Update(update_instruction))
             .build()
             .map_err(|e| anyhow::anyhow!("failed to build computeOutputs: {e:?}"))?;
 
-        let obj = serde_json::to_string_pretty(&outputs).unwrap();
-
-        Ok(obj)
+        Ok(outputs)
     }
 
     pub fn start(inputs: Inputs) -> Result<String, anyhow::Error> {
-        let blank = Program::new();
-        let outputs = blank
+        BlankProgram::program()
             .execute_method(&inputs)
-            .map_err(|e| anyhow::anyhow!("failed to update program: {e:?}"))?;
-
-        Ok(outputs)
-    }
-}
-
-#[allow(dead_code)]
-/// A minimalistic main function for a Rust LASR program.
-/// A Program that takes in lasr_type::Inputs, handles the call based on the program method, and produces necessary lasr_types::Outputs to be processed by protocol
-fn main() -> anyhow::Result<()> {
-    if let Err(e) = Program::run() {
-        bail!("Error occured while running program: {}", e.to_string())
+            .map_err(|e| anyhow::anyhow!("failed to update program: {e:?}"))
     }
-    Ok(())
 }
@@ -1,11 +1,12 @@
-//! A minimalistic main function for a Rust LASR program.
-//! A Program that takes in lasr_type::Inputs, handles the call based on the program method, and produces necessary lasr_types::Outputs to be processed by protocol
-use crate::lasrctl::builders::program::Program;
+//! A faucet LASR program.
+//! Supports `addProgram` to register (and fund) a program's token with the faucet, and `faucet` to claim
+//! a fixed amount of a supported token, at most once per address per `addressTimeoutMinutes`.
+use crate::lasrctl::builders::faucet::FaucetProgram;
 use anyhow::bail;
 
 #[allow(dead_code)]
 fn main() -> anyhow::Result<()> {
-    if let Err(e) = Program::run() {
+    if let Err(e) = FaucetProgram::new().run_stdin() {
         bail!("Error occured while running program: {}", e.to_string())
     }
     Ok(())
@@ -1,11 +1,12 @@
-//! A minimalistic main function for a Rust LASR program.
-//! A Program that takes in lasr_type::Inputs, handles the call based on the program method, and produces necessary lasr_types::Outputs to be processed by protocol
-use crate::lasrctl::builders::program::Program;
+//! A non-fungible token LASR program.
+//! `create` distributes a pool of sequential token IDs to the program, and `mint` sells the next IDs from
+//! that pool to the caller at the program's `price`, paid in its `paymentProgramAddress`.
+use crate::lasrctl::builders::non_fungible::NonFungibleProgram;
 use anyhow::bail;
 
 #[allow(dead_code)]
 fn main() -> anyhow::Result<()> {
-    if let Err(e) = Program::run() {
+    if let Err(e) = NonFungibleProgram::new().run_stdin() {
         bail!("Error occured while running program: {}", e.to_string())
     }
     Ok(())
@@ -0,0 +1,263 @@
+//! A typed, read-only view of the account state passed to a program call.
+//!
+//! `Inputs::account_info` holds the caller's token holdings alongside the
+//! program account's metadata, data and linked programs, as raw maps of
+//! strings. `AccountState` parses them on access, so a strategy can ask for
+//! the caller's balance or a `U256` setting instead of digging through maps
+//! and hex strings.
+use lasr_types::*;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use super::{
+    metadata::{migrate_metadata, ProgramMetadata},
+    program::{parse_address, parse_u256, program_setting},
+};
+
+/// A value stored as a string in account metadata or data.
+pub trait StateValue: Sized {
+    fn parse_state(value: &str) -> anyhow::Result<Self>;
+}
+
+impl StateValue for String {
+    fn parse_state(value: &str) -> anyhow::Result<Self> {
+        Ok(value.to_string())
+    }
+}
+
+/// Hex (`0x` prefixed) or decimal.
+impl StateValue for U256 {
+    fn parse_state(value: &str) -> anyhow::Result<Self> {
+        parse_u256(value)
+    }
+}
+
+impl StateValue for Address {
+    fn parse_state(value: &str) -> anyhow::Result<Self> {
+        parse_address(value)
+    }
+}
+
+impl StateValue for u64 {
+    fn parse_state(value: &str) -> anyhow::Result<Self> {
+        value
+            .trim()
+            .parse()
+            .map_err(|e| anyhow::anyhow!("invalid integer '{value}': {e:?}"))
+    }
+}
+
+impl StateValue for bool {
+    fn parse_state(value: &str) -> anyhow::Result<Self> {
+        value
+            .trim()
+            .parse()
+            .map_err(|e| anyhow::anyhow!("invalid boolean '{value}': {e:?}"))
+    }
+}
+
+/// A value stored as a JSON document, e.g. `data_get::<Json<TokenMetadata>>(key)`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Json<T>(pub T);
+
+impl<T: DeserializeOwned> StateValue for Json<T> {
+    fn parse_state(value: &str) -> anyhow::Result<Self> {
+        serde_json::from_str(value)
+            .map(Json)
+            .map_err(|e| anyhow::anyhow!("invalid JSON '{value}': {e:?}"))
+    }
+}
+
+/// Parses the `kind` entry `key` of the program account, if present.
+fn parse_entry<T: StateValue>(
+    kind: &str,
+    key: &str,
+    value: Option<&String>,
+) -> anyhow::Result<Option<T>> {
+    value
+        .map(|value| T::parse_state(value))
+        .transpose()
+        .map_err(|e| anyhow::anyhow!("program {kind} '{key}' is malformed: {e:#}"))
+}
+
+/// A read-only view of the `accountInfo` of `Inputs`, from the point of view
+/// of the transaction's sender and the called program.
+pub struct AccountState<'a> {
+    inputs: &'a Inputs,
+}
+
+impl<'a> AccountState<'a> {
+    pub fn new(inputs: &'a Inputs) -> Self {
+        Self { inputs }
+    }
+
+    /// The transaction's sender.
+    pub fn caller(&self) -> Address {
+        self.inputs.transaction.from()
+    }
+
+    /// The program being called.
+    pub fn program_id(&self) -> Address {
+        self.inputs.transaction.program_id()
+    }
+
+    /// The owner of the called program's account.
+    pub fn owner(&self) -> anyhow::Result<Address> {
+        let account = serde_json::to_value(&self.inputs.account_info)?;
+        match account.get("ownerAddress").and_then(Value::as_str) {
+            Some(owner) => parse_address(owner),
+            None => anyhow::bail!("accountInfo has no ownerAddress"),
+        }
+    }
+
+    /// Whether the transaction was sent by the program's owner.
+    pub fn caller_is_owner(&self) -> anyhow::Result<bool> {
+        Ok(self.owner()? == self.caller())
+    }
+
+    /// The caller's holdings of `program`'s token, if it holds any.
+    pub fn token(&self, program: &Address) -> Option<&Token> {
+        self.inputs.account_info.programs().get(program)
+    }
+
+    /// The caller's balance of `program`'s token.
+    pub fn balance_of(&self, program: &Address) -> U256 {
+        self.token(program)
+            .map(|token| token.balance())
+            .unwrap_or_default()
+    }
+
+    /// The token IDs of `program` held by the caller.
+    pub fn token_ids_of(&self, program: &Address) -> Vec<U256> {
+        self.token(program)
+            .map(|token| token.token_ids().to_vec())
+            .unwrap_or_default()
+    }
+
+    /// The token IDs of the called program the caller has approved `spender`
+    /// to transfer.
+    pub fn approvals_for(&self, spender: &Address) -> anyhow::Result<Vec<U256>> {
+        let Some(approved) = self.token_entry("approvals", spender)? else {
+            return Ok(Vec::new());
+        };
+        approved
+            .as_array()
+            .ok_or_else(|| anyhow::anyhow!("approvals for {spender:?} are not a list"))?
+            .iter()
+            .map(|token_id| match token_id.as_str() {
+                Some(token_id) => parse_u256(token_id),
+                None => anyhow::bail!("approved token ID {token_id} is not a string"),
+            })
+            .collect()
+    }
+
+    /// The amount of the called program's token the caller has allowed
+    /// `spender` to transfer.
+    pub fn allowance_for(&self, spender: &Address) -> anyhow::Result<U256> {
+        match self.token_entry("allowance", spender)? {
+            Some(Value::String(amount)) => parse_u256(&amount),
+            Some(other) => anyhow::bail!("allowance for {spender:?} is not a string: {other}"),
+            None => Ok(U256::zero()),
+        }
+    }
+
+    /// Looks up `spender` in the `field` map of the caller's token entry for
+    /// the called program.
+    fn token_entry(&self, field: &str, spender: &Address) -> anyhow::Result<Option<Value>> {
+        let Some(token) = self.token(&self.program_id()) else {
+            return Ok(None);
+        };
+        let token = serde_json::to_value(token)?;
+        let Some(entries) = token.get(field).and_then(Value::as_object) else {
+            return Ok(None);
+        };
+        for (address, value) in entries {
+            if parse_address(address)? == *spender {
+                return Ok(Some(value.clone()));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Parses the program account's metadata entry `key`.
+    pub fn metadata<T: StateValue>(&self, key: &str) -> anyhow::Result<Option<T>> {
+        let metadata = self.inputs.account_info.program_account_metadata();
+        parse_entry("metadata", key, metadata.inner().get(key))
+    }
+
+    /// The program account's metadata, including metadata written by earlier
+    /// versions under a single `metadata` key.
+    pub fn program_metadata(&self) -> anyhow::Result<ProgramMetadata> {
+        migrate_metadata(self.inputs.account_info.program_account_metadata().inner())
+    }
+
+    /// Parses the program account's data entry `key`.
+    pub fn data_get<T: StateValue>(&self, key: &str) -> anyhow::Result<Option<T>> {
+        let data = self.inputs.account_info.program_account_data();
+        parse_entry("data", key, data.inner().get(key))
+    }
+
+    /// Parses the program setting `key`, looked up like `program_setting`.
+    pub fn setting<T: StateValue>(&self, key: &str) -> anyhow::Result<Option<T>> {
+        parse_entry("setting", key, program_setting(self.inputs, key).as_ref())
+    }
+}
+
+#[test]
+fn test_account_state() -> Result<(), anyhow::Error> {
+    let mut inputs: serde_json::Value = serde_json::from_str(include_str!(
+        "../../examples/fungible/example-program-inputs/fungible-approve.json"
+    ))?;
+    let program = "0x100444c7d04a842d19bc3ee63cb7b96682ff3f43";
+    let spender = "0x482830d7655fb8465a43844fc1530a7713781b49";
+    inputs["accountInfo"]["programs"][program] = serde_json::json!({
+        "programId": program,
+        "ownerId": program,
+        "balance": "0x0000000000000000000000000000000000000000000000000000000000000064",
+        "metadata": {},
+        "tokenIds": [],
+        "allowance": {
+            "0x482830d7655fb8465a43844fc1530a7713781b49":
+                "0x0000000000000000000000000000000000000000000000000000000000000005"
+        },
+        "approvals": {
+            "0x482830d7655fb8465a43844fc1530a7713781b49":
+                ["0x0000000000000000000000000000000000000000000000000000000000000001"]
+        },
+        "data": {},
+        "status": "free"
+    });
+    inputs["accountInfo"]["programAccountData"]["config"] = r#"{"paused":true}"#.into();
+    let inputs: Inputs = serde_json::from_value(inputs)?;
+    let state = AccountState::new(&inputs);
+    let program = parse_address(program)?;
+    let spender = parse_address(spender)?;
+
+    assert_eq!(state.balance_of(&program), U256::from(100));
+    assert_eq!(state.balance_of(&spender), U256::zero());
+    assert_eq!(state.approvals_for(&spender)?, vec![U256::one()]);
+    assert_eq!(state.allowance_for(&spender)?, U256::from(5));
+    assert_eq!(state.owner()?, spender);
+    assert!(!state.caller_is_owner()?);
+
+    assert_eq!(
+        state.metadata::<U256>("totalSupply")?,
+        Some(parse_u256("1000000000000000000000000")?)
+    );
+    assert_eq!(state.metadata::<String>("symbol")?.as_deref(), Some("LOVE"));
+    assert_eq!(state.metadata::<U256>("missing")?, None);
+    assert!(state.metadata::<U256>("symbol").is_err());
+    assert_eq!(
+        state.program_metadata()?.name.as_deref(),
+        Some("Anotha One")
+    );
+
+    #[derive(serde::Deserialize)]
+    struct Config {
+        paused: bool,
+    }
+    let config = state.data_get::<Json<Config>>("config")?;
+    assert!(config.map(|Json(config)| config.paused).unwrap_or_default());
+
+    Ok(())
+}
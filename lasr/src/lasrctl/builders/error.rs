@@ -0,0 +1,170 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// The class of failure that caused a LASR program to reject a call.
+///
+/// Each class maps to a distinct process exit code, so callers that only see
+/// the exit status of a program binary can still tell why it was rejected.
+/// The codes start at 10 to stay clear of 1, which Rust exits with when
+/// `main` returns an `Err`, and 101, which it exits with on a panic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ProgramErrorKind {
+    /// An unexpected failure while building or serializing the outputs.
+    Internal,
+    /// The program's stdin could not be read or parsed as `lasr_types::Inputs`.
+    InvalidInputs,
+    /// The transaction's `transactionInputs` were malformed for the called op.
+    InvalidTransactionInputs,
+    /// No method strategy is registered for the called op.
+    UnknownMethod,
+    /// The call was well-formed but violates the program's rules.
+    Rejected,
+}
+
+impl ProgramErrorKind {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ProgramErrorKind::Internal => 10,
+            ProgramErrorKind::InvalidInputs => 11,
+            ProgramErrorKind::InvalidTransactionInputs => 12,
+            ProgramErrorKind::UnknownMethod => 13,
+            ProgramErrorKind::Rejected => 14,
+        }
+    }
+
+    pub fn from_exit_code(code: i32) -> Option<Self> {
+        match code {
+            10 => Some(ProgramErrorKind::Internal),
+            11 => Some(ProgramErrorKind::InvalidInputs),
+            12 => Some(ProgramErrorKind::InvalidTransactionInputs),
+            13 => Some(ProgramErrorKind::UnknownMethod),
+            14 => Some(ProgramErrorKind::Rejected),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ProgramErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self {
+            ProgramErrorKind::Internal => "internal",
+            ProgramErrorKind::InvalidInputs => "invalid inputs",
+            ProgramErrorKind::InvalidTransactionInputs => "invalid transaction inputs",
+            ProgramErrorKind::UnknownMethod => "unknown method",
+            ProgramErrorKind::Rejected => "rejected",
+        };
+        write!(f, "{kind}")
+    }
+}
+
+/// An error returned by a LASR program, written to stdout as
+/// `{"error": {"kind": ..., "code": ..., "message": ...}}` when a call fails.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProgramError {
+    pub kind: ProgramErrorKind,
+    pub code: i32,
+    pub message: String,
+}
+
+impl ProgramError {
+    pub fn new(kind: ProgramErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            code: kind.exit_code(),
+            message: message.into(),
+        }
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        Self::new(ProgramErrorKind::Internal, message)
+    }
+
+    pub fn invalid_inputs(message: impl Into<String>) -> Self {
+        Self::new(ProgramErrorKind::InvalidInputs, message)
+    }
+
+    pub fn invalid_transaction_inputs(message: impl Into<String>) -> Self {
+        Self::new(ProgramErrorKind::InvalidTransactionInputs, message)
+    }
+
+    pub fn unknown_method(op: &str) -> Self {
+        Self::new(
+            ProgramErrorKind::UnknownMethod,
+            format!("Invalid method strategy: no method registered for op '{op}'"),
+        )
+    }
+
+    pub fn rejected(message: impl Into<String>) -> Self {
+        Self::new(ProgramErrorKind::Rejected, message)
+    }
+
+    /// Serializes the error into the JSON envelope programs print on failure.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(&ProgramErrorOutput {
+            error: self.clone(),
+        })
+        .unwrap_or_else(|_| {
+            format!(
+                "{{\"error\":{{\"kind\":\"internal\",\"code\":10,\"message\":{:?}}}}}",
+                self.message
+            )
+        })
+    }
+
+    /// Attempts to parse the error envelope from a program's stdout.
+    pub fn from_json(json: &str) -> Option<Self> {
+        serde_json::from_str::<ProgramErrorOutput>(json.trim())
+            .ok()
+            .map(|output| output.error)
+    }
+}
+
+impl fmt::Display for ProgramError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} (exit code {}): {}",
+            self.kind, self.code, self.message
+        )
+    }
+}
+
+impl std::error::Error for ProgramError {}
+
+impl From<anyhow::Error> for ProgramError {
+    /// Recovers the `ProgramError` a strategy failed with, falling back to an
+    /// internal error when the failure was not classified.
+    fn from(err: anyhow::Error) -> Self {
+        let message = format!("{err:#}");
+        match err.downcast_ref::<ProgramError>() {
+            Some(program_error) => ProgramError::new(program_error.kind, message),
+            None => ProgramError::internal(message),
+        }
+    }
+}
+
+/// The JSON envelope written to stdout when a program rejects a call.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProgramErrorOutput {
+    pub error: ProgramError,
+}
+
+#[test]
+fn test_program_error_json_round_trip() {
+    let err: ProgramError = anyhow::Error::new(ProgramError::rejected("supply exhausted"))
+        .context("failed to mint")
+        .into();
+    assert_eq!(err.kind, ProgramErrorKind::Rejected);
+    assert_eq!(err.code, 14);
+    assert_eq!(
+        ProgramErrorKind::from_exit_code(err.code),
+        Some(ProgramErrorKind::Rejected)
+    );
+    assert_eq!(ProgramErrorKind::from_exit_code(1), None);
+    assert_eq!(err.message, "failed to mint: supply exhausted");
+
+    let json = err.to_json();
+    assert_eq!(ProgramError::from_json(&json), Some(err));
+}
@@ -0,0 +1,402 @@
+use std::collections::BTreeMap;
+
+use anyhow::Ok;
+use lasr_types::*;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    error::ProgramError,
+    program::{parse_address, parse_u256, Program},
+};
+
+/// The program data key under which the faucet stores its supported programs.
+pub const FAUCET_PROGRAMS_KEY: &str = "programs";
+
+/// The latest claim timestamp the faucet accepts, 3000-01-01T00:00:00Z.
+pub const MAX_CLAIM_TIMESTAMP: u64 = 32_503_680_000;
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(
+    rename(serialize = "transactionInputs", deserialize = "transactionInputs"),
+    rename_all = "camelCase"
+)]
+/// Structure of transactionInputs specifically for the addProgram method strategy.
+pub struct AddProgramTransactionInputs {
+    pub program_address: String,
+    pub faucet_amount: String,
+    pub address_timeout_minutes: String,
+    pub amount_to_add: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(
+    rename(serialize = "transactionInputs", deserialize = "transactionInputs"),
+    rename_all = "camelCase"
+)]
+/// Structure of transactionInputs specifically for the faucet method strategy.
+///
+/// `timestamp` is the claim time in seconds since the unix epoch, set by the
+/// client building the call. Programs are not given a clock the protocol
+/// controls, and a signature only proves who sent the time, not that it is
+/// real. The faucet therefore rate limits the signer, requires each of its
+/// claims to be no earlier than its last, and rejects timestamps past
+/// `MAX_CLAIM_TIMESTAMP`, so a made up time can only lock out its own signer.
+pub struct FaucetTransactionInputs {
+    pub to: String,
+    #[serde(alias = "programToSend")]
+    pub program_address: String,
+    pub timestamp: String,
+}
+
+/// How much of a program's token the faucet hands out per claim, and how long
+/// an address has to wait between claims.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FaucetConfig {
+    pub faucet_amount: String,
+    pub address_timeout_minutes: String,
+}
+
+/// A program supported by the faucet, along with the last time (in seconds
+/// since the unix epoch) each signer claimed from it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FaucetRecord {
+    pub config: FaucetConfig,
+    /// Last claim time by lowercase signer address. Stored under
+    /// `recipients`, which older versions keyed by recipient.
+    pub claimants: BTreeMap<String, u64>,
+}
+
+/// The JSON encoded form of a `FaucetRecord`, as stored in program data.
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct EncodedFaucetRecord {
+    pipe_data: String,
+    recipients: String,
+}
+
+impl FaucetRecord {
+    pub fn new(config: FaucetConfig) -> Self {
+        Self {
+            config,
+            claimants: BTreeMap::new(),
+        }
+    }
+
+    pub fn faucet_amount(&self) -> anyhow::Result<U256> {
+        parse_u256(&self.config.faucet_amount)
+    }
+
+    pub fn timeout_secs(&self) -> anyhow::Result<u64> {
+        let minutes: u64 = self
+            .config
+            .address_timeout_minutes
+            .trim()
+            .parse()
+            .map_err(|e| {
+                anyhow::anyhow!(
+                    "invalid addressTimeoutMinutes '{}': {e:?}",
+                    self.config.address_timeout_minutes
+                )
+            })?;
+        Ok(minutes.saturating_mul(60))
+    }
+
+    /// Records a claim by `claimant` at `now`, rejecting it if the claimant
+    /// already claimed within the configured timeout. `now` must not precede
+    /// the claimant's last claim, nor be past `MAX_CLAIM_TIMESTAMP`.
+    pub fn claim(&mut self, claimant: &str, now: u64) -> anyhow::Result<()> {
+        let claimant = claimant.to_lowercase();
+        let timeout = self.timeout_secs()?;
+        if now > MAX_CLAIM_TIMESTAMP {
+            return Err(ProgramError::invalid_transaction_inputs(format!(
+                "timestamp {now} is in the future, the latest accepted is {MAX_CLAIM_TIMESTAMP}"
+            ))
+            .into());
+        }
+        if let Some(&last_claim) = self.claimants.get(&claimant) {
+            if now < last_claim {
+                return Err(ProgramError::invalid_transaction_inputs(format!(
+                    "timestamp {now} precedes {claimant}'s last claim at {last_claim}"
+                ))
+                .into());
+            }
+            let next_claim = last_claim.checked_add(timeout).ok_or_else(|| {
+                ProgramError::rejected(format!(
+                    "{claimant}'s last claim at {last_claim} is too late to claim again"
+                ))
+            })?;
+            if now < next_claim {
+                return Err(ProgramError::rejected(format!(
+                    "{claimant} must wait another {} seconds before claiming again",
+                    next_claim - now
+                ))
+                .into());
+            }
+        }
+        self.claimants.insert(claimant, now);
+        Ok(())
+    }
+
+    fn decode(encoded: &str) -> anyhow::Result<Self> {
+        let record: EncodedFaucetRecord = serde_json::from_str(encoded)?;
+        Ok(Self {
+            config: serde_json::from_str(&record.pipe_data)?,
+            claimants: serde_json::from_str(&record.recipients)?,
+        })
+    }
+
+    fn encode(&self) -> anyhow::Result<String> {
+        Ok(serde_json::to_string(&EncodedFaucetRecord {
+            pipe_data: serde_json::to_string(&self.config)?,
+            recipients: serde_json::to_string(&self.claimants)?,
+        })?)
+    }
+}
+
+/// Decodes the faucet's supported programs from program data, keyed by
+/// lowercase program address.
+pub fn faucet_records(inputs: &Inputs) -> anyhow::Result<BTreeMap<String, FaucetRecord>> {
+    let Some(programs) = inputs
+        .account_info
+        .program_account_data()
+        .inner()
+        .get(FAUCET_PROGRAMS_KEY)
+        .cloned()
+    else {
+        return Ok(BTreeMap::new());
+    };
+
+    let encoded: BTreeMap<String, String> = serde_json::from_str(&programs)
+        .map_err(|e| anyhow::anyhow!("failed to decode faucet programs: {e:?}"))?;
+    encoded
+        .into_iter()
+        .map(|(program, record)| {
+            let record = FaucetRecord::decode(&record).map_err(|e| {
+                anyhow::anyhow!("failed to decode faucet record for {program}: {e:?}")
+            })?;
+            Ok((program.to_lowercase(), record))
+        })
+        .collect()
+}
+
+fn faucet_records_update(
+    records: &BTreeMap<String, FaucetRecord>,
+) -> anyhow::Result<ProgramUpdateField> {
+    let encoded = records
+        .iter()
+        .map(|(program, record)| Ok((program.clone(), record.encode()?)))
+        .collect::<anyhow::Result<BTreeMap<String, String>>>()?;
+
+    ProgramUpdateFieldBuilder::new()
+        .field(ProgramField::Data)
+        .value(ProgramFieldValue::Data(DataValue::Insert(
+            FAUCET_PROGRAMS_KEY.to_string(),
+            serde_json::to_string(&encoded)?,
+        )))
+        .build()
+        .map_err(|e| anyhow::anyhow!("failed to build program update field: {e:?}"))
+}
+
+fn program_update_instruction(update_field: ProgramUpdateField) -> anyhow::Result<Instruction> {
+    Ok(lasr_types::Instruction::Update(
+        UpdateInstructionBuilder::new()
+            .add_update(TokenOrProgramUpdate::ProgramUpdate(
+                ProgramUpdateBuilder::new()
+                    .account(AddressOrNamespace::This)
+                    .add_update(update_field)
+                    .build()
+                    .map_err(|e| anyhow::anyhow!("failed to build program update: {e:?}"))?,
+            ))
+            .build(),
+    ))
+}
+
+/// A program that hands out a fixed amount of each supported program's token,
+/// at most once per address per timeout.
+pub struct FaucetProgram;
+
+impl FaucetProgram {
+    /// Creates a program with the default method strategies, plus
+    /// `addProgram` and `faucet`.
+    pub fn new() -> Program<Inputs> {
+        let mut program = Program::new();
+        program
+            .register_method("addProgram", FaucetProgram::add_program)
+            .register_method("faucet", FaucetProgram::faucet);
+        program
+    }
+
+    /// Adds (or reconfigures) a program supported by the faucet, optionally
+    /// funding the faucet with `amountToAdd` of the program's token.
+    pub fn add_program(
+        inputs: &Inputs,
+        txn_inputs: AddProgramTransactionInputs,
+    ) -> anyhow::Result<Outputs> {
+        let invalid = |e: anyhow::Error| ProgramError::invalid_transaction_inputs(format!("{e:#}"));
+        let program_address = parse_address(&txn_inputs.program_address).map_err(invalid)?;
+        let config = FaucetConfig {
+            faucet_amount: txn_inputs.faucet_amount,
+            address_timeout_minutes: txn_inputs.address_timeout_minutes,
+        };
+        let amount_to_add = txn_inputs
+            .amount_to_add
+            .map(|amount| parse_u256(&amount))
+            .transpose()
+            .map_err(invalid)?
+            .unwrap_or_default();
+
+        let mut records = faucet_records(inputs)?;
+        let key = txn_inputs.program_address.trim().to_lowercase();
+        let record = match records.remove(&key) {
+            Some(mut record) => {
+                record.config = config;
+                record
+            }
+            None => FaucetRecord::new(config),
+        };
+        if record.faucet_amount().map_err(invalid)?.is_zero() {
+            return Err(ProgramError::invalid_transaction_inputs(
+                "faucetAmount must be greater than zero",
+            )
+            .into());
+        }
+        record.timeout_secs().map_err(invalid)?;
+        records.insert(key, record);
+
+        let mut outputs = OutputsBuilder::new()
+            .inputs(inputs.clone())
+            .add_instruction(program_update_instruction(faucet_records_update(
+                &records,
+            )?)?);
+        if !amount_to_add.is_zero() {
+            let funding = TransferInstructionBuilder::new()
+                .token(program_address)
+                .from(AddressOrNamespace::Address(inputs.transaction.from()))
+                .to(AddressOrNamespace::This)
+                .amount(amount_to_add)
+                .build()
+                .map_err(|e| anyhow::anyhow!("failed to build funding transfer: {e:?}"))?;
+            outputs = outputs.add_instruction(lasr_types::Instruction::Transfer(funding));
+        }
+
+        outputs
+            .build()
+            .map_err(|e| anyhow::anyhow!("failed to build computeOutputs: {e:?}"))
+    }
+
+    /// Sends the configured `faucetAmount` of a supported program's token to
+    /// `to`, provided the transaction's signer has not claimed it within the
+    /// program's timeout of the call's `timestamp`.
+    pub fn faucet(inputs: &Inputs, txn_inputs: FaucetTransactionInputs) -> anyhow::Result<Outputs> {
+        let invalid = |e: anyhow::Error| ProgramError::invalid_transaction_inputs(format!("{e:#}"));
+        let program_address = parse_address(&txn_inputs.program_address).map_err(invalid)?;
+        let recipient = parse_address(&txn_inputs.to).map_err(invalid)?;
+        let now: u64 = txn_inputs.timestamp.trim().parse().map_err(|e| {
+            ProgramError::invalid_transaction_inputs(format!(
+                "invalid timestamp '{}': {e:?}",
+                txn_inputs.timestamp
+            ))
+        })?;
+
+        let mut records = faucet_records(inputs)?;
+        let key = txn_inputs.program_address.trim().to_lowercase();
+        let record = records.get_mut(&key).ok_or_else(|| {
+            ProgramError::rejected(format!(
+                "program {} is not supported by this faucet",
+                txn_inputs.program_address
+            ))
+        })?;
+        record.claim(&inputs.transaction.from().to_full_string(), now)?;
+        let faucet_amount = record.faucet_amount()?;
+
+        let transfer = TransferInstructionBuilder::new()
+            .token(program_address)
+            .from(AddressOrNamespace::This)
+            .to(AddressOrNamespace::Address(recipient))
+            .amount(faucet_amount)
+            .build()
+            .map_err(|e| anyhow::anyhow!("failed to build faucet transfer: {e:?}"))?;
+
+        OutputsBuilder::new()
+            .inputs(inputs.clone())
+            .add_instruction(lasr_types::Instruction::Transfer(transfer))
+            .add_instruction(program_update_instruction(faucet_records_update(
+                &records,
+            )?)?)
+            .build()
+            .map_err(|e| anyhow::anyhow!("failed to build computeOutputs: {e:?}"))
+    }
+}
+
+#[test]
+fn test_faucet_claims_are_rate_limited() -> Result<(), anyhow::Error> {
+    let mut record = FaucetRecord::new(FaucetConfig {
+        faucet_amount: "100".to_string(),
+        address_timeout_minutes: "10".to_string(),
+    });
+    record.claim("0xABC", 1_000)?;
+    assert!(record.claim("0xabc", 1_000 + 599).is_err());
+    record.claim("0xabc", 1_000 + 600)?;
+    assert!(record.claim("0xabc", 1_000).is_err());
+    assert!(record.claim("0xdef", u64::MAX).is_err());
+    record.claim("0xdef", MAX_CLAIM_TIMESTAMP)?;
+    // A claim at the horizon only locks out its own signer.
+    assert!(record.claim("0xdef", MAX_CLAIM_TIMESTAMP).is_err());
+    record.claim("0x123", 1_000)?;
+
+    let decoded = FaucetRecord::decode(&record.encode()?)?;
+    assert_eq!(decoded, record);
+
+    Ok(())
+}
+
+#[test]
+fn test_faucet() -> Result<(), anyhow::Error> {
+    use super::error::ProgramErrorKind;
+
+    let program = FaucetProgram::new();
+
+    let template_str =
+        include_str!("../../examples/faucet/example-program-inputs/faucet-faucet-2.json");
+    let outputs = program.execute(&serde_json::from_str(template_str)?)?;
+    assert_eq!(outputs.instructions().len(), 2);
+
+    let template_str =
+        include_str!("../../examples/faucet/example-program-inputs/faucet-faucet-fail.json");
+    let err = program
+        .execute(&serde_json::from_str(template_str)?)
+        .unwrap_err();
+    let err = ProgramError::from(err);
+    assert_eq!(err.kind, ProgramErrorKind::Rejected);
+    assert!(err.message.contains("must wait another 540 seconds"));
+
+    for (template_str, expected) in [
+        (
+            include_str!(
+                "../../examples/faucet/example-program-inputs/faucet-faucet-past-fail.json"
+            ),
+            "precedes",
+        ),
+        (
+            include_str!(
+                "../../examples/faucet/example-program-inputs/faucet-faucet-future-fail.json"
+            ),
+            "in the future",
+        ),
+    ] {
+        let err = program
+            .execute(&serde_json::from_str(template_str)?)
+            .unwrap_err();
+        let err = ProgramError::from(err);
+        assert_eq!(err.kind, ProgramErrorKind::InvalidTransactionInputs);
+        assert!(err.message.contains(expected), "{}", err.message);
+    }
+
+    let template_str =
+        include_str!("../../examples/faucet/example-program-inputs/faucet-add-first-program.json");
+    let outputs = program.execute(&serde_json::from_str(template_str)?)?;
+    assert_eq!(outputs.instructions().len(), 2);
+
+    Ok(())
+}
@@ -0,0 +1,97 @@
+//! Programs written as typed, op-annotated functions instead of hand-wired
+//! method strategies.
+//!
+//! `#[lasr_program]` goes on an `impl` block and routes each function marked
+//! `#[op("name")]` to transactions with that `op`. Functions taking
+//! `(&Inputs, T)` receive the `transactionInputs` deserialized into `T`, and
+//! malformed inputs are rejected as invalid transaction inputs before they
+//! are called; functions taking only `&Inputs` receive the raw inputs. With
+//! `#[lasr_program(main)]` the stdin-reading `main` is generated as well.
+pub use lasr_derive::lasr_program;
+use lasr_types::Inputs;
+
+use super::program::Program;
+
+/// A LASR program whose op routing is generated by `#[lasr_program]`.
+pub trait LasrProgram {
+    /// Registers the program's ops on `program`.
+    fn register(program: &mut Program<Inputs>);
+
+    /// The program with its ops registered over the default method strategies.
+    fn program() -> Program<Inputs> {
+        let mut program = Program::new();
+        Self::register(&mut program);
+        program
+    }
+
+    /// Runs the program against `Inputs` read from stdin, printing the
+    /// `Outputs` to stdout.
+    fn run() -> anyhow::Result<()> {
+        Self::program().run_stdin()
+    }
+}
+
+/// Items the code generated by `#[lasr_program]` refers to.
+#[doc(hidden)]
+pub mod __private {
+    pub use anyhow;
+    pub use lasr_types::Inputs;
+}
+
+#[test]
+fn test_lasr_program_routing() -> Result<(), anyhow::Error> {
+    use super::error::{ProgramError, ProgramErrorKind};
+    use crate::examples::blank::example_program::BlankProgram;
+    use lasr_types::Outputs;
+
+    let fixture: serde_json::Value = serde_json::from_str(include_str!(
+        "../../examples/blank/example-program-inputs/blank-create.json"
+    ))?;
+    let with_op = |op: &str, transaction_inputs: Option<&str>| {
+        let mut inputs = fixture.clone();
+        inputs["op"] = op.into();
+        inputs["transaction"]["op"] = op.into();
+        if let Some(transaction_inputs) = transaction_inputs {
+            inputs["transaction"]["transactionInputs"] = transaction_inputs.into();
+        }
+        serde_json::from_value::<Inputs>(inputs)
+    };
+
+    let program = BlankProgram::program();
+    assert!(program.has_method("hello") && program.has_method("create"));
+    assert_eq!(
+        program
+            .execute(&with_op("hello", None)?)?
+            .instructions()
+            .len(),
+        1
+    );
+    let err: ProgramError = program
+        .execute(&with_op("hello", Some("not json"))?)
+        .expect_err("malformed transactionInputs")
+        .into();
+    assert_eq!(err.kind, ProgramErrorKind::InvalidTransactionInputs);
+
+    #[allow(dead_code)]
+    struct Echo;
+
+    #[lasr_program(no_defaults)]
+    impl Echo {
+        #[op]
+        fn echo(inputs: &Inputs) -> anyhow::Result<Outputs> {
+            lasr_types::OutputsBuilder::new()
+                .inputs(inputs.clone())
+                .build()
+                .map_err(|e| anyhow::anyhow!("failed to build computeOutputs: {e:?}"))
+        }
+    }
+
+    let program = Echo::program();
+    assert_eq!(program.methods(), vec!["echo"]);
+    assert!(program
+        .execute(&with_op("echo", None)?)?
+        .instructions()
+        .is_empty());
+
+    Ok(())
+}
@@ -0,0 +1,246 @@
+//! The keys a program account's metadata is stored under.
+//!
+//! `create` and `update` write each metadata field under its own key, listed
+//! in [`METADATA_SCHEMA`]. Programs created by earlier versions instead hold
+//! a single `metadata` key: either a JSON `(symbol, name, totalSupply)` tuple
+//! string written by `create`, or a JSON object written by `update`.
+//! [`migrate_metadata`] reads all three shapes.
+use std::collections::BTreeMap;
+
+use lasr_types::*;
+use serde::{Deserialize, Serialize};
+
+/// The single key legacy programs stored all of their metadata under.
+pub const LEGACY_METADATA_KEY: &str = "metadata";
+
+/// A documented program metadata key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MetadataKey {
+    pub key: &'static str,
+    pub description: &'static str,
+}
+
+/// Every key `ProgramMetadata` is stored under, in the order they are written.
+pub const METADATA_SCHEMA: &[MetadataKey] = &[
+    MetadataKey {
+        key: "name",
+        description: "Human readable name of the program's token",
+    },
+    MetadataKey {
+        key: "symbol",
+        description: "Ticker symbol of the program's token",
+    },
+    MetadataKey {
+        key: "totalSupply",
+        description: "Maximum number of tokens that may ever exist, in 0x hex",
+    },
+    MetadataKey {
+        key: "initializedSupply",
+        description: "Number of tokens distributed when the program was created, in 0x hex; equals totalSupply for non-fungible programs",
+    },
+    MetadataKey {
+        key: "imgUrl",
+        description: "URL of the token's image",
+    },
+    MetadataKey {
+        key: "collection",
+        description: "Name of the collection the program's tokens belong to",
+    },
+    MetadataKey {
+        key: "paymentProgramAddress",
+        description: "Address of the program whose tokens pay for mints",
+    },
+    MetadataKey {
+        key: "price",
+        description: "Price of a single token in the smallest unit of the payment program's token, as a decimal; non-fungible mints round the cost up",
+    },
+];
+
+/// A program account's metadata, one field per key of [`METADATA_SCHEMA`].
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProgramMetadata {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_supply: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub initialized_supply: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub img_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub collection: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payment_program_address: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price: Option<String>,
+}
+
+impl ProgramMetadata {
+    /// The metadata entries of the fields that are set, in schema order.
+    pub fn entries(&self) -> Vec<(&'static str, String)> {
+        let fields = [
+            &self.name,
+            &self.symbol,
+            &self.total_supply,
+            &self.initialized_supply,
+            &self.img_url,
+            &self.collection,
+            &self.payment_program_address,
+            &self.price,
+        ];
+        METADATA_SCHEMA
+            .iter()
+            .zip(fields)
+            .filter_map(|(schema, value)| value.clone().map(|value| (schema.key, value)))
+            .collect()
+    }
+
+    /// Reads the schema's keys from metadata `entries`, ignoring any others.
+    pub fn from_entries<'a>(entries: impl IntoIterator<Item = (&'a String, &'a String)>) -> Self {
+        let entries: BTreeMap<&str, &String> = entries
+            .into_iter()
+            .map(|(key, value)| (key.as_str(), value))
+            .collect();
+        let get = |key: &str| entries.get(key).map(|value| value.to_string());
+        Self {
+            name: get("name"),
+            symbol: get("symbol"),
+            total_supply: get("totalSupply"),
+            initialized_supply: get("initializedSupply"),
+            img_url: get("imgUrl"),
+            collection: get("collection"),
+            payment_program_address: get("paymentProgramAddress"),
+            price: get("price"),
+        }
+    }
+
+    /// Fills every field that isn't set from `fallback`.
+    pub fn or(self, fallback: Self) -> Self {
+        Self {
+            name: self.name.or(fallback.name),
+            symbol: self.symbol.or(fallback.symbol),
+            total_supply: self.total_supply.or(fallback.total_supply),
+            initialized_supply: self.initialized_supply.or(fallback.initialized_supply),
+            img_url: self.img_url.or(fallback.img_url),
+            collection: self.collection.or(fallback.collection),
+            payment_program_address: self
+                .payment_program_address
+                .or(fallback.payment_program_address),
+            price: self.price.or(fallback.price),
+        }
+    }
+
+    /// Program update fields inserting each field that is set under its own key.
+    pub fn update_fields(&self) -> anyhow::Result<Vec<ProgramUpdateField>> {
+        self.entries()
+            .into_iter()
+            .map(|(key, value)| {
+                ProgramUpdateFieldBuilder::new()
+                    .field(ProgramField::Metadata)
+                    .value(ProgramFieldValue::Metadata(MetadataValue::Insert(
+                        key.to_string(),
+                        value,
+                    )))
+                    .build()
+                    .map_err(|e| anyhow::anyhow!("failed to build program update field: {e:?}"))
+            })
+            .collect()
+    }
+}
+
+/// Parses the value of a legacy `metadata` key: either the JSON
+/// `(symbol, name, totalSupply)` tuple written by `create`, or the JSON
+/// object written by `update`. Legacy `create` parsed the tuple's
+/// `totalSupply` as hex, so it is returned with a `0x` prefix.
+pub fn parse_legacy_metadata(value: &str) -> anyhow::Result<ProgramMetadata> {
+    if let Ok((symbol, name, total_supply)) =
+        serde_json::from_str::<(Option<String>, Option<String>, Option<String>)>(value)
+    {
+        return Ok(ProgramMetadata {
+            name,
+            symbol,
+            total_supply: total_supply
+                .filter(|total_supply| !total_supply.is_empty())
+                .map(|total_supply| match total_supply.starts_with("0x") {
+                    true => total_supply,
+                    false => format!("0x{total_supply}"),
+                }),
+            ..ProgramMetadata::default()
+        });
+    }
+    serde_json::from_str(value)
+        .map_err(|e| anyhow::anyhow!("unrecognized legacy metadata '{value}': {e:?}"))
+}
+
+/// Reads a program's metadata from its `entries`, whichever version wrote it.
+/// Keys written individually take precedence over those in a legacy
+/// `metadata` value.
+pub fn migrate_metadata<'a>(
+    entries: impl IntoIterator<Item = (&'a String, &'a String)>,
+) -> anyhow::Result<ProgramMetadata> {
+    let mut legacy = None;
+    let metadata = ProgramMetadata::from_entries(entries.into_iter().inspect(|(key, value)| {
+        if key.as_str() == LEGACY_METADATA_KEY {
+            legacy = Some(value.to_string());
+        }
+    }));
+    match legacy {
+        Some(legacy) => Ok(metadata.or(parse_legacy_metadata(&legacy)?)),
+        None => Ok(metadata),
+    }
+}
+
+/// Looks up `key` in a legacy `metadata` value, if it holds one.
+pub fn legacy_metadata_entry(legacy: &str, key: &str) -> Option<String> {
+    parse_legacy_metadata(legacy)
+        .ok()?
+        .entries()
+        .into_iter()
+        .find_map(|(entry, value)| (entry == key).then_some(value))
+}
+
+#[test]
+fn test_migrate_metadata() -> Result<(), anyhow::Error> {
+    let entries = |pairs: &[(&str, &str)]| -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    };
+
+    let created = entries(&[("metadata", r#"["HLLO","HelloToken","1000"]"#)]);
+    assert_eq!(
+        migrate_metadata(&created)?,
+        ProgramMetadata {
+            name: Some("HelloToken".to_string()),
+            symbol: Some("HLLO".to_string()),
+            total_supply: Some("0x1000".to_string()),
+            ..ProgramMetadata::default()
+        }
+    );
+
+    // Keys written individually win over the legacy value.
+    let updated = entries(&[
+        ("metadata", r#"{"symbol":"SNICKERS","totalSupply":"5"}"#),
+        ("totalSupply", "1000"),
+        ("imgUrl", "https://example.com/hllo.png"),
+    ]);
+    let metadata = migrate_metadata(&updated)?;
+    assert_eq!(
+        metadata.entries(),
+        vec![
+            ("symbol", "SNICKERS".to_string()),
+            ("totalSupply", "1000".to_string()),
+            ("imgUrl", "https://example.com/hllo.png".to_string()),
+        ]
+    );
+    assert_eq!(
+        legacy_metadata_entry(r#"["HLLO",null,"1000"]"#, "totalSupply").as_deref(),
+        Some("0x1000")
+    );
+    assert!(migrate_metadata(&entries(&[("metadata", "not json")])).is_err());
+    Ok(())
+}
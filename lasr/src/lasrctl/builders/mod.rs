@@ -1 +1,7 @@
+pub mod account;
+pub mod error;
+pub mod faucet;
+pub mod lasr_program;
+pub mod metadata;
+pub mod non_fungible;
 pub mod program;
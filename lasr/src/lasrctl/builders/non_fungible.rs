@@ -0,0 +1,532 @@
+use std::collections::BTreeMap;
+
+use anyhow::Ok;
+use lasr_types::*;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    account::AccountState,
+    error::ProgramError,
+    metadata::ProgramMetadata,
+    program::{
+        cost_at_price, parse_address, parse_supply, parse_u256, program_setting,
+        CreateTransactionInputs, Program,
+    },
+};
+use crate::scripts::consts::ETH_PROGRAM_ADDRESS;
+
+/// The largest pool of token IDs a single `create` call may distribute.
+pub const MAX_INITIAL_TOKEN_IDS: u64 = 10_000;
+/// The most tokens a single `mint` call may hand out.
+pub const MAX_MINT_QUANTITY: u64 = 100;
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(
+    rename(serialize = "transactionInputs", deserialize = "transactionInputs"),
+    rename_all = "camelCase"
+)]
+/// Structure of transactionInputs specifically for the non-fungible mint method strategy.
+pub struct MintTransactionInputs {
+    pub quantity: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(
+    rename(serialize = "transactionInputs", deserialize = "transactionInputs"),
+    rename_all = "camelCase"
+)]
+/// Structure of transactionInputs specifically for the non-fungible burn method strategy.
+pub struct BurnTransactionInputs {
+    pub token_ids: Vec<String>,
+}
+
+/// Metadata stored in program data for each minted token, under
+/// `token_metadata_key(token_id)`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenMetadata {
+    pub img_url: Option<String>,
+    pub collection: Option<String>,
+}
+
+/// The program data key holding the `TokenMetadata` of `token_id`.
+pub fn token_metadata_key(token_id: U256) -> String {
+    format!("token:{token_id}")
+}
+
+fn program_update_instruction(updates: Vec<ProgramUpdateField>) -> anyhow::Result<Instruction> {
+    Ok(lasr_types::Instruction::Update(
+        UpdateInstructionBuilder::new()
+            .add_update(TokenOrProgramUpdate::ProgramUpdate(
+                ProgramUpdateBuilder::new()
+                    .account(AddressOrNamespace::This)
+                    .extend_updates(updates)
+                    .build()
+                    .map_err(|e| anyhow::anyhow!("failed to build program update: {e:?}"))?,
+            ))
+            .build(),
+    ))
+}
+
+fn program_field_update(
+    field: ProgramField,
+    value: ProgramFieldValue,
+) -> anyhow::Result<ProgramUpdateField> {
+    ProgramUpdateFieldBuilder::new()
+        .field(field)
+        .value(value)
+        .build()
+        .map_err(|e| anyhow::anyhow!("failed to build program update field: {e:?}"))
+}
+
+/// A program for non-fungible tokens, where each token is identified by a
+/// sequentially assigned token ID.
+///
+/// `create` distributes a pool of `initializedSupply` token IDs to the program
+/// itself, which must be the whole `totalSupply`, and `mint` sells the next
+/// `quantity` IDs from that pool to the caller at `price` per token, in the
+/// smallest unit of the program's `paymentProgramAddress` token.
+pub struct NonFungibleProgram;
+
+impl NonFungibleProgram {
+    /// Creates a program with the default method strategies, with `create`,
+    /// `mint` and `burn` replaced by their non-fungible counterparts.
+    pub fn new() -> Program<Inputs> {
+        let mut program = Program::new();
+        program
+            .register_method("create", NonFungibleProgram::create)
+            .register_method("mint", NonFungibleProgram::mint)
+            .register_method("burn", NonFungibleProgram::burn);
+        program
+    }
+
+    pub fn create(inputs: &Inputs, txn_inputs: CreateTransactionInputs) -> anyhow::Result<Outputs> {
+        let invalid = |e: anyhow::Error| ProgramError::invalid_transaction_inputs(format!("{e:#}"));
+        let required = |field: &str, value: Option<String>| {
+            value.ok_or_else(|| {
+                ProgramError::invalid_transaction_inputs(format!(
+                    "non-fungible create requires '{field}'"
+                ))
+            })
+        };
+
+        let name = required("name", txn_inputs.name)?;
+        let symbol = required("symbol", txn_inputs.symbol)?;
+        let total_supply = required("totalSupply", txn_inputs.total_supply)?;
+        let initialized_supply = required("initializedSupply", txn_inputs.initialized_supply)?;
+        let img_url = required("imgUrl", txn_inputs.img_url)?;
+        let price = required("price", txn_inputs.price)?;
+        let payment_program = txn_inputs
+            .payment_program_address
+            .unwrap_or_else(|| ETH_PROGRAM_ADDRESS.to_string());
+
+        // Read like the fungible `create`, so the same inputs mean the same supply.
+        let total = parse_supply("totalSupply", Some(&total_supply))?;
+        let initialized = parse_supply("initializedSupply", Some(&initialized_supply))?;
+        parse_address(&payment_program).map_err(invalid)?;
+        cost_at_price(U256::one(), &price).map_err(invalid)?;
+        // Every token ID is created up front and minted out of the program's
+        // pool, so IDs past initializedSupply could never be minted.
+        if total.is_zero() || initialized != total {
+            return Err(ProgramError::invalid_transaction_inputs(format!(
+                "initializedSupply {initialized} must equal a non-zero totalSupply {total}"
+            ))
+            .into());
+        }
+        if initialized > U256::from(MAX_INITIAL_TOKEN_IDS) {
+            return Err(ProgramError::invalid_transaction_inputs(format!(
+                "initializedSupply {initialized} exceeds the maximum of {MAX_INITIAL_TOKEN_IDS} token IDs per create"
+            ))
+            .into());
+        }
+
+        let distribution = (0..initialized.as_u64())
+            .fold(
+                TokenDistributionBuilder::new()
+                    .program_id(AddressOrNamespace::This)
+                    .to(AddressOrNamespace::This)
+                    .amount(initialized),
+                |distribution, token_id| distribution.add_token_id(U256::from(token_id)),
+            )
+            .build()
+            .map_err(|e| anyhow::anyhow!("failed to build token distribution: {e:?}"))?;
+
+        let create_instruction = CreateInstructionBuilder::new()
+            .program_owner(inputs.transaction.from())
+            .initialized_supply(initialized)
+            .total_supply(total)
+            .program_id(AddressOrNamespace::This)
+            .program_namespace(AddressOrNamespace::This)
+            .add_token_distribution(distribution)
+            .build()
+            .map_err(|e| anyhow::anyhow!("failed to build create instructions: {e:?}"))?;
+
+        let mut updates = ProgramMetadata {
+            name: Some(name),
+            symbol: Some(symbol),
+            total_supply: Some(format!("0x{total:x}")),
+            initialized_supply: Some(format!("0x{initialized:x}")),
+            ..ProgramMetadata::default()
+        }
+        .update_fields()?;
+
+        let mut data = BTreeMap::from([
+            ("type".to_string(), "non-fungible".to_string()),
+            ("currentSupply".to_string(), "0".to_string()),
+            ("nextTokenId".to_string(), "0".to_string()),
+            ("imgUrl".to_string(), img_url),
+            ("price".to_string(), price),
+            ("paymentProgramAddress".to_string(), payment_program),
+        ]);
+        if let Some(collection) = txn_inputs.collection {
+            data.insert("collection".to_string(), collection);
+        }
+        updates.push(program_field_update(
+            ProgramField::Data,
+            ProgramFieldValue::Data(DataValue::Extend(data)),
+        )?);
+
+        OutputsBuilder::new()
+            .inputs(inputs.clone())
+            .add_instruction(lasr_types::Instruction::Create(create_instruction))
+            .add_instruction(program_update_instruction(updates)?)
+            .build()
+            .map_err(|e| anyhow::anyhow!("failed to build computeOutputs: {e:?}"))
+    }
+
+    /// Transfers the next `quantity` token IDs from the program's pool to the
+    /// caller, charging `price` per token in the payment program.
+    pub fn mint(inputs: &Inputs, txn_inputs: MintTransactionInputs) -> anyhow::Result<Outputs> {
+        let transaction = &inputs.transaction;
+        let caller = transaction.from();
+        let program_id = transaction.program_id();
+
+        let quantity: u64 = txn_inputs.quantity.trim().parse().map_err(|e| {
+            ProgramError::invalid_transaction_inputs(format!(
+                "invalid quantity '{}': {e:?}",
+                txn_inputs.quantity
+            ))
+        })?;
+        if quantity == 0 || quantity > MAX_MINT_QUANTITY {
+            return Err(ProgramError::invalid_transaction_inputs(format!(
+                "quantity must be between 1 and {MAX_MINT_QUANTITY}, got {quantity}"
+            ))
+            .into());
+        }
+
+        let pool = program_setting(inputs, "initializedSupply").ok_or_else(|| {
+            ProgramError::rejected(format!(
+                "program {} does not define an initializedSupply",
+                program_id.to_full_string()
+            ))
+        })?;
+        let pool = parse_u256(&pool)?;
+        let current_supply = program_setting(inputs, "currentSupply")
+            .map(|supply| parse_u256(&supply))
+            .transpose()?
+            .unwrap_or_default();
+        // Burns lower currentSupply, so IDs come from their own counter to
+        // avoid handing out an ID that is still held. Programs created before
+        // nextTokenId existed never burned, so currentSupply is a safe default.
+        let next_token_id = program_setting(inputs, "nextTokenId")
+            .map(|token_id| parse_u256(&token_id))
+            .transpose()?
+            .unwrap_or(current_supply);
+        let overflow = || {
+            ProgramError::invalid_transaction_inputs(format!(
+                "minting {quantity} tokens overflows token ID {next_token_id}"
+            ))
+        };
+        let last_token_id = next_token_id
+            .checked_add(U256::from(quantity))
+            .ok_or_else(overflow)?;
+        let next_supply = current_supply
+            .checked_add(U256::from(quantity))
+            .ok_or_else(overflow)?;
+        if last_token_id > pool {
+            return Err(ProgramError::rejected(format!(
+                "cannot mint {quantity} tokens, only {} of {pool} remain",
+                pool.saturating_sub(next_token_id)
+            ))
+            .into());
+        }
+
+        let price = program_setting(inputs, "price").unwrap_or_else(|| "0".to_string());
+        let cost = cost_at_price(U256::from(quantity), &price)?;
+        let payment_program = parse_address(
+            &program_setting(inputs, "paymentProgramAddress")
+                .unwrap_or_else(|| ETH_PROGRAM_ADDRESS.to_string()),
+        )?;
+        let payment_balance = AccountState::new(inputs).balance_of(&payment_program);
+        if payment_balance < cost {
+            return Err(ProgramError::rejected(format!(
+                "insufficient balance in payment program {}: {payment_balance} < {cost}",
+                payment_program.to_full_string()
+            ))
+            .into());
+        }
+
+        let token_ids = (0..quantity)
+            .map(|offset| next_token_id.checked_add(U256::from(offset)))
+            .collect::<Option<Vec<U256>>>()
+            .ok_or_else(overflow)?;
+        let token_transfer = token_ids
+            .iter()
+            .fold(
+                TransferInstructionBuilder::new()
+                    .token(program_id)
+                    .from(AddressOrNamespace::This)
+                    .to(AddressOrNamespace::Address(caller))
+                    .amount(U256::from(quantity)),
+                |transfer, token_id| transfer.add_token_id(*token_id),
+            )
+            .build()
+            .map_err(|e| anyhow::anyhow!("failed to build token transfer: {e:?}"))?;
+
+        let token_metadata = serde_json::to_string(&TokenMetadata {
+            img_url: program_setting(inputs, "imgUrl"),
+            collection: program_setting(inputs, "collection"),
+        })?;
+        let mut data: BTreeMap<String, String> = token_ids
+            .iter()
+            .map(|token_id| (token_metadata_key(*token_id), token_metadata.clone()))
+            .collect();
+        data.insert("currentSupply".to_string(), next_supply.to_string());
+        data.insert("nextTokenId".to_string(), last_token_id.to_string());
+        let data_update = program_field_update(
+            ProgramField::Data,
+            ProgramFieldValue::Data(DataValue::Extend(data)),
+        )?;
+
+        let mut outputs = OutputsBuilder::new().inputs(inputs.clone());
+        if !cost.is_zero() {
+            let payment = TransferInstructionBuilder::new()
+                .token(payment_program)
+                .from(AddressOrNamespace::Address(caller))
+                .to(AddressOrNamespace::This)
+                .amount(cost)
+                .build()
+                .map_err(|e| anyhow::anyhow!("failed to build payment transfer: {e:?}"))?;
+            outputs = outputs.add_instruction(lasr_types::Instruction::Transfer(payment));
+        }
+
+        outputs
+            .add_instruction(lasr_types::Instruction::Transfer(token_transfer))
+            .add_instruction(program_update_instruction(vec![data_update])?)
+            .build()
+            .map_err(|e| anyhow::anyhow!("failed to build computeOutputs: {e:?}"))
+    }
+
+    /// Burns the given token IDs held by the caller and lowers `currentSupply`.
+    pub fn burn(inputs: &Inputs, txn_inputs: BurnTransactionInputs) -> anyhow::Result<Outputs> {
+        let transaction = &inputs.transaction;
+        let caller = transaction.from();
+        let program_id = transaction.program_id();
+
+        let token_ids = txn_inputs
+            .token_ids
+            .iter()
+            .map(|token_id| parse_u256(token_id))
+            .collect::<anyhow::Result<Vec<U256>>>()
+            .map_err(|e| ProgramError::invalid_transaction_inputs(format!("{e:#}")))?;
+        if token_ids.is_empty() {
+            return Err(
+                ProgramError::invalid_transaction_inputs("tokenIds must not be empty").into(),
+            );
+        }
+        let state = AccountState::new(inputs);
+        let Some(token) = state.token(&program_id) else {
+            return Err(ProgramError::rejected(format!(
+                "cannot burn, caller holds none of {}",
+                program_id.to_full_string()
+            ))
+            .into());
+        };
+        let held = token.token_ids();
+        if let Some(missing) = token_ids.iter().find(|token_id| !held.contains(token_id)) {
+            return Err(
+                ProgramError::rejected(format!("caller does not hold token ID {missing}")).into(),
+            );
+        }
+        let current_supply = program_setting(inputs, "currentSupply")
+            .map(|supply| parse_u256(&supply))
+            .transpose()?
+            .unwrap_or_default();
+        let next_supply = current_supply
+            .checked_sub(U256::from(token_ids.len() as u64))
+            .ok_or_else(|| {
+                ProgramError::rejected(format!(
+                    "cannot burn {} tokens, more than the currentSupply {current_supply}",
+                    token_ids.len()
+                ))
+            })?;
+
+        let burn_instruction = token_ids
+            .iter()
+            .fold(
+                BurnInstructionBuilder::new()
+                    .caller(caller)
+                    .program_id(AddressOrNamespace::This)
+                    .token(program_id)
+                    .from(AddressOrNamespace::Address(caller))
+                    .amount(U256::from(token_ids.len() as u64)),
+                |burn, token_id| burn.add_token_id(*token_id),
+            )
+            .build()
+            .map_err(|e| anyhow::anyhow!("failed to build burn instruction: {e:?}"))?;
+        let supply_update = program_field_update(
+            ProgramField::Data,
+            ProgramFieldValue::Data(DataValue::Insert(
+                "currentSupply".to_string(),
+                next_supply.to_string(),
+            )),
+        )?;
+
+        OutputsBuilder::new()
+            .inputs(inputs.clone())
+            .add_instruction(lasr_types::Instruction::Burn(burn_instruction))
+            .add_instruction(program_update_instruction(vec![supply_update])?)
+            .build()
+            .map_err(|e| anyhow::anyhow!("failed to build computeOutputs: {e:?}"))
+    }
+}
+
+#[test]
+fn test_non_fungible_create() -> Result<(), anyhow::Error> {
+    use super::error::ProgramErrorKind;
+
+    let program = NonFungibleProgram::new();
+
+    let template_str =
+        include_str!("../../examples/non_fungible/example-program-inputs/non-fungible-create.json");
+    let outputs = program.execute(&serde_json::from_str(template_str)?)?;
+    assert_eq!(outputs.instructions().len(), 2);
+    // Supplies are read as hex and stored in the schema's 0x hex form.
+    let outputs = serde_json::to_string(&outputs)?;
+    assert!(outputs.contains(r#"["totalSupply","0x100"]"#));
+    assert!(outputs.contains(r#"["initializedSupply","0x100"]"#));
+
+    // Token IDs past initializedSupply would never exist to be minted.
+    let mut compute_inputs: serde_json::Value = serde_json::from_str(template_str)?;
+    compute_inputs["transaction"]["transactionInputs"] = serde_json::json!({
+        "name": "Rare Candy", "symbol": "RARECANDY", "imgUrl": "https://example.com/candy.png",
+        "price": "1", "initializedSupply": "10", "totalSupply": "100"
+    })
+    .to_string()
+    .into();
+    let err = program
+        .execute(&serde_json::from_value(compute_inputs)?)
+        .unwrap_err();
+    assert_eq!(
+        ProgramError::from(err).kind,
+        ProgramErrorKind::InvalidTransactionInputs
+    );
+
+    let template_str = include_str!(
+        "../../examples/non_fungible/example-program-inputs/non-fungible-create-fail.json"
+    );
+    let err = program
+        .execute(&serde_json::from_str(template_str)?)
+        .unwrap_err();
+    assert_eq!(
+        ProgramError::from(err).kind,
+        ProgramErrorKind::InvalidTransactionInputs
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_non_fungible_mint_and_burn() -> Result<(), anyhow::Error> {
+    use super::error::ProgramErrorKind;
+
+    let program = NonFungibleProgram::new();
+
+    let template_str =
+        include_str!("../../examples/non_fungible/example-program-inputs/non-fungible-mint.json");
+    let outputs = program.execute(&serde_json::from_str(template_str)?)?;
+    assert_eq!(outputs.instructions().len(), 3);
+    let minted = serde_json::to_value(outputs)?["instructions"][1]["transfer"]["ids"]
+        .as_array()
+        .map(|ids| {
+            ids.iter()
+                .map(|id| parse_u256(id.as_str().unwrap_or_default()))
+                .collect::<anyhow::Result<Vec<U256>>>()
+        })
+        .transpose()?;
+    assert_eq!(minted, Some(vec![U256::zero(), U256::one()]));
+    // price is in the payment token's smallest unit, charged per token.
+    let payment = serde_json::to_value(program.execute(&serde_json::from_str(template_str)?)?)?
+        ["instructions"][0]["transfer"]["amount"]
+        .as_str()
+        .map(parse_u256)
+        .transpose()?;
+    assert_eq!(payment, Some(U256::from(200_000_000_000_000_000u64)));
+
+    let mint_err = |edit: &dyn Fn(&mut serde_json::Value)| -> anyhow::Result<ProgramErrorKind> {
+        let mut compute_inputs: serde_json::Value = serde_json::from_str(template_str)?;
+        edit(&mut compute_inputs);
+        let err = program
+            .execute(&serde_json::from_value(compute_inputs)?)
+            .unwrap_err();
+        Ok(ProgramError::from(err).kind)
+    };
+    // The caller holds none of the payment program's tokens.
+    assert_eq!(
+        mint_err(&|compute_inputs| {
+            compute_inputs["accountInfo"]["programs"]
+                ["0x57234c52617e7ca8edc5577ebe3eb38d53a77607"]["balance"] =
+                "0x0000000000000000000000000000000000000000000000000000000000000000".into();
+        })?,
+        ProgramErrorKind::Rejected
+    );
+    // Minting past the largest token ID overflows currentSupply.
+    assert_eq!(
+        mint_err(&|compute_inputs| {
+            compute_inputs["accountInfo"]["programAccountData"]["currentSupply"] =
+                format!("{:#x}", U256::MAX).into();
+        })?,
+        ProgramErrorKind::InvalidTransactionInputs
+    );
+
+    let template_str = include_str!(
+        "../../examples/non_fungible/example-program-inputs/non-fungible-mint-fail.json"
+    );
+    let err = program
+        .execute(&serde_json::from_str(template_str)?)
+        .unwrap_err();
+    assert_eq!(
+        ProgramError::from(err).kind,
+        ProgramErrorKind::InvalidTransactionInputs
+    );
+
+    let template_str =
+        include_str!("../../examples/non_fungible/example-program-inputs/non-fungible-burn.json");
+    let outputs = program.execute(&serde_json::from_str(template_str)?)?;
+    assert_eq!(outputs.instructions().len(), 2);
+    assert!(serde_json::to_string(&outputs)?.contains(r#"["currentSupply","1"]"#));
+
+    let burn_err = |edit: &dyn Fn(&mut serde_json::Value)| -> anyhow::Result<ProgramError> {
+        let mut compute_inputs: serde_json::Value = serde_json::from_str(template_str)?;
+        edit(&mut compute_inputs);
+        let err = program
+            .execute(&serde_json::from_value(compute_inputs)?)
+            .unwrap_err();
+        Ok(ProgramError::from(err))
+    };
+    // The caller has no entry for the program, so holds no token IDs.
+    let err = burn_err(&|compute_inputs| {
+        compute_inputs["accountInfo"]["programs"] = serde_json::json!({});
+    })?;
+    assert_eq!(err.kind, ProgramErrorKind::Rejected);
+    assert!(err.message.contains("holds none"));
+    // currentSupply cannot drop below zero.
+    let err = burn_err(&|compute_inputs| {
+        compute_inputs["accountInfo"]["programs"]["0x100444c7D04A842D19bc3eE63cB7b96682FF3f43"]
+            ["data"]["currentSupply"] = "0".into();
+    })?;
+    assert_eq!(err.kind, ProgramErrorKind::Rejected);
+    assert!(err.message.contains("currentSupply"));
+
+    Ok(())
+}
@@ -1,88 +1,218 @@
-use std::{collections::HashMap, io::Read, str::FromStr};
+use std::{
+    collections::HashMap,
+    io::{Read, Write},
+};
 
-use anyhow::Ok;
+use anyhow::{Context, Ok};
 use lasr_types::*;
-use serde::{Deserialize, Serialize};
-
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use super::{
+    account::AccountState,
+    error::ProgramError,
+    metadata::{legacy_metadata_entry, ProgramMetadata, LEGACY_METADATA_KEY},
+};
+use crate::scripts::consts::ETH_PROGRAM_ADDRESS;
+
+/// A LASR program, represented as a registry of method strategies keyed by
+/// the `op` of the incoming transaction.
+///
+/// `Program::new` registers the default `approve`, `create`, `update`, `mint`
+/// and `burn` strategies. Any of them can be overridden by registering a method under the
+/// same op, or removed entirely with `Program::remove_method`.
+///
+/// Strategies report failures by returning an error; returning a
+/// [`ProgramError`] (directly or as the source of an `anyhow::Error`) selects
+/// the error class reported to the caller, otherwise it is treated as internal.
 pub struct Program<Inputs> {
-    method_strategies: HashMap<String, Box<dyn Fn(&Inputs) -> String>>,
+    method_strategies: HashMap<String, Box<dyn Fn(&Inputs) -> anyhow::Result<Outputs>>>,
+}
+
+impl Default for Program<Inputs> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Program<Inputs> {
     pub fn new() -> Self {
-        let mut program = Program {
-            method_strategies: HashMap::new(),
-        };
+        let mut program = Program::empty();
 
         // Register default methods
-        program.register_contract_method("approve".to_string(), Box::new(Program::approve));
-        program.register_contract_method("create".to_string(), Box::new(Program::create));
-        program.register_contract_method("update".to_string(), Box::new(Program::update));
+        program
+            .register_raw_method("approve", Program::approve)
+            .register_raw_method("create", Program::create)
+            .register_raw_method("update", Program::update)
+            .register_raw_method("mint", Program::mint)
+            .register_raw_method("burn", Program::burn);
 
         program
     }
 
-    fn register_contract_method(
-        &mut self,
-        operation: String,
-        method: Box<dyn Fn(&Inputs) -> String>,
-    ) {
-        self.method_strategies.insert(operation, method);
+    /// Creates a program without any registered method strategies.
+    pub fn empty() -> Self {
+        Program {
+            method_strategies: HashMap::new(),
+        }
     }
 
-    fn approve(inputs: &Inputs) -> String {
-        approve_program(inputs.clone())
-            .map_err(|e| anyhow::anyhow!("failed to approve lasr program: {e:?}"))
-            .unwrap()
+    /// Registers a method strategy for `operation` whose handler receives the
+    /// transaction's `transactionInputs` deserialized into `T`.
+    ///
+    /// An empty `transactionInputs` string is treated as an empty JSON object.
+    /// Registering an operation that already exists replaces its strategy.
+    pub fn register_method<T, F>(&mut self, operation: impl Into<String>, method: F) -> &mut Self
+    where
+        T: DeserializeOwned + 'static,
+        F: Fn(&Inputs, T) -> anyhow::Result<Outputs> + 'static,
+    {
+        let operation = operation.into();
+        let op = operation.clone();
+        self.register_raw_method(operation, move |inputs: &Inputs| {
+            let txn_inputs: T = parse_transaction_inputs(inputs)
+                .with_context(|| format!("failed to deserialize transactionInputs for '{op}'"))?;
+            method(inputs, txn_inputs)
+        })
     }
 
-    fn create(inputs: &Inputs) -> String {
-        create_program(inputs.clone())
-            .map_err(|e| anyhow::anyhow!("failed to create lasr program: {e:?}"))
-            .unwrap()
+    /// Registers a method strategy for `operation` whose handler receives the
+    /// raw `Inputs`, leaving `transactionInputs` for the handler to interpret.
+    ///
+    /// Registering an operation that already exists replaces its strategy.
+    pub fn register_raw_method<F>(&mut self, operation: impl Into<String>, method: F) -> &mut Self
+    where
+        F: Fn(&Inputs) -> anyhow::Result<Outputs> + 'static,
+    {
+        self.method_strategies
+            .insert(operation.into(), Box::new(method));
+        self
     }
 
-    fn update(inputs: &Inputs) -> String {
-        update_program(inputs.clone())
-            .map_err(|e| anyhow::anyhow!("failed to update lasr program: {e:?}"))
-            .unwrap()
+    /// Removes the method strategy registered for `operation`, returning
+    /// whether one was registered.
+    pub fn remove_method(&mut self, operation: &str) -> bool {
+        self.method_strategies.remove(operation).is_some()
     }
 
-    pub fn execute_method(&self, inputs: &Inputs) -> Result<String, anyhow::Error> {
+    /// Returns `true` if a method strategy is registered for `operation`.
+    pub fn has_method(&self, operation: &str) -> bool {
+        self.method_strategies.contains_key(operation)
+    }
+
+    /// The operations this program can handle, in alphabetical order.
+    pub fn methods(&self) -> Vec<&str> {
+        let mut methods: Vec<&str> = self.method_strategies.keys().map(String::as_str).collect();
+        methods.sort_unstable();
+        methods
+    }
+
+    fn approve(inputs: &Inputs) -> anyhow::Result<Outputs> {
+        approve_program(inputs.clone()).context("failed to approve lasr program")
+    }
+
+    fn create(inputs: &Inputs) -> anyhow::Result<Outputs> {
+        create_program(inputs.clone()).context("failed to create lasr program")
+    }
+
+    fn update(inputs: &Inputs) -> anyhow::Result<Outputs> {
+        update_program(inputs.clone()).context("failed to update lasr program")
+    }
+
+    fn mint(inputs: &Inputs) -> anyhow::Result<Outputs> {
+        mint_program(inputs.clone()).context("failed to mint lasr program tokens")
+    }
+
+    fn burn(inputs: &Inputs) -> anyhow::Result<Outputs> {
+        burn_program(inputs.clone()).context("failed to burn lasr program tokens")
+    }
+
+    /// Executes the strategy registered for the inputs' op, returning the
+    /// resulting `Outputs`.
+    pub fn execute(&self, inputs: &Inputs) -> Result<Outputs, anyhow::Error> {
         match self.method_strategies.get(&inputs.op) {
-            Some(strategy) => Ok(strategy(inputs)),
-            None => Err(anyhow::anyhow!("Invalid method strategy")),
+            Some(strategy) => strategy(inputs),
+            None => Err(ProgramError::unknown_method(&inputs.op).into()),
         }
     }
 
+    /// Executes the strategy registered for the inputs' op, returning the
+    /// resulting `Outputs` as pretty-printed JSON.
+    pub fn execute_method(&self, inputs: &Inputs) -> Result<String, anyhow::Error> {
+        let outputs = self.execute(inputs)?;
+        serde_json::to_string_pretty(&outputs).map_err(|e| {
+            ProgramError::internal(format!("failed to serialize computeOutputs: {e:?}")).into()
+        })
+    }
+
     pub fn start(&self, compute_inputs: &Inputs) -> Result<String, anyhow::Error> {
         self.execute_method(compute_inputs)
     }
 
+    /// Runs a program with the default method strategies against `Inputs`
+    /// read from stdin, printing the `Outputs` to stdout.
     pub fn run() -> anyhow::Result<()> {
+        Program::new().run_stdin()
+    }
+
+    /// Runs this program against `Inputs` read from stdin, printing the
+    /// `Outputs` to stdout.
+    ///
+    /// If the call fails, the error is printed to stdout as a `ProgramError`
+    /// JSON envelope and the process exits with the error class' exit code.
+    pub fn run_stdin(&self) -> anyhow::Result<()> {
         let mut input = String::new();
-        std::io::stdin()
+        let result = std::io::stdin()
             .read_to_string(&mut input)
-            .map_err(|e| anyhow::anyhow!("error while reading stdin to string: {e:?}"))?;
+            .map_err(|e| {
+                ProgramError::invalid_inputs(format!("error while reading stdin to string: {e:?}"))
+            })
+            .and_then(|_| self.respond(&input));
+
+        match result {
+            std::result::Result::Ok(outputs) => {
+                println!("{}", outputs);
+                Ok(())
+            }
+            Err(err) => {
+                println!("{}", err.to_json());
+                let _ = std::io::stdout().flush();
+                std::process::exit(err.code)
+            }
+        }
+    }
 
-        let parsed_data: Inputs = serde_json::from_str(&input).map_err(|e| {
-            anyhow::anyhow!("error serializing stdin inputs to lasr_type Inputs: {e:?}")
+    /// Executes a JSON encoded `Inputs` payload, returning the pretty-printed
+    /// `Outputs` or the classified error the program would exit with.
+    pub fn respond(&self, input: &str) -> Result<String, ProgramError> {
+        let parsed_data: Inputs = serde_json::from_str(input).map_err(|e| {
+            ProgramError::invalid_inputs(format!(
+                "error serializing stdin inputs to lasr_type Inputs: {e:?}"
+            ))
         })?;
-        let program = Program::new();
-        let result = program
-            .start(&parsed_data)
-            .map_err(|e| anyhow::anyhow!("error while starting LASR program: {e:?}"))?;
 
-        println!("{}", result);
-        Ok(())
+        self.start(&parsed_data).map_err(ProgramError::from)
     }
 }
 
+/// Deserializes the transaction's `transactionInputs` into `T`, treating an
+/// empty string as an empty JSON object.
+pub fn parse_transaction_inputs<T: DeserializeOwned>(inputs: &Inputs) -> anyhow::Result<T> {
+    let txn_inputs = inputs.transaction.inputs();
+    let txn_inputs = match txn_inputs.trim() {
+        "" => "{}",
+        trimmed => trimmed,
+    };
+    serde_json::from_str(txn_inputs)
+        .map_err(|e| ProgramError::invalid_transaction_inputs(format!("{e}: {txn_inputs}")).into())
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub enum MethodStrategy {
     Approve,
     Create,
     Update,
+    Mint,
+    Burn,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -109,20 +239,217 @@ pub struct CreateTransactionInputs {
 /// Structure of transactionInputs specifically for the update method strategy.
 pub struct UpdateTransactionInputs {
     data: Option<HashMap<String, String>>,
-    metadata: Option<Metadata>,
+    metadata: Option<ProgramMetadata>,
     linked_programs: Option<Vec<Address>>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-#[serde(rename_all = "camelCase")]
-pub struct Metadata {
-    symbol: Option<String>,
-    name: Option<String>,
-    total_supply: Option<String>,
-    initialized_supply: Option<String>,
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(
+    rename(serialize = "transactionInputs", deserialize = "transactionInputs"),
+    rename_all = "camelCase"
+)]
+/// Structure of transactionInputs specifically for the burn method strategy.
+/// When `amount` is omitted, the transaction's value is burned.
+pub struct BurnTransactionInputs {
+    pub amount: Option<String>,
+}
+
+/// Parses a `U256` from either a `0x` prefixed hex string or a decimal string.
+pub fn parse_u256(value: &str) -> anyhow::Result<U256> {
+    let value = value.trim();
+    match value.strip_prefix("0x") {
+        Some(hex) => U256::from_str_radix(hex, 16)
+            .map_err(|e| anyhow::anyhow!("invalid hex value '{value}': {e:?}")),
+        None => U256::from_dec_str(value)
+            .map_err(|e| anyhow::anyhow!("invalid decimal value '{value}': {e:?}")),
+    }
+}
+
+/// Parses a `0x` prefixed hex string into an `Address`.
+pub fn parse_address(address: &str) -> anyhow::Result<Address> {
+    serde_json::from_value(serde_json::Value::String(address.trim().to_string()))
+        .map_err(|e| anyhow::anyhow!("invalid address '{address}': {e:?}"))
+}
+
+/// Parses a non-negative decimal such as `"0.1"` into a numerator and a
+/// power of ten denominator.
+fn parse_decimal(decimal: &str) -> anyhow::Result<(U256, U256)> {
+    let decimal = decimal.trim();
+    let (whole, fraction) = decimal.split_once('.').unwrap_or((decimal, ""));
+    let is_digits = |s: &str| s.chars().all(|c| c.is_ascii_digit());
+    if (whole.is_empty() && fraction.is_empty())
+        || !is_digits(whole)
+        || !is_digits(fraction)
+        || fraction.len() > 77
+    {
+        anyhow::bail!("invalid decimal '{decimal}'");
+    }
+
+    let numerator = U256::from_dec_str(&format!("{whole}{fraction}"))
+        .map_err(|e| anyhow::anyhow!("invalid decimal '{decimal}': {e:?}"))?;
+    Ok((numerator, U256::exp10(fraction.len())))
+}
+
+/// Multiplies `amount` by a non-negative decimal `rate` such as `"0.1"`,
+/// truncating any fractional remainder.
+pub fn scale_by_rate(amount: U256, rate: &str) -> anyhow::Result<U256> {
+    let (numerator, denominator) =
+        parse_decimal(rate).map_err(|e| anyhow::anyhow!("invalid rate: {e:#}"))?;
+    amount
+        .checked_mul(numerator)
+        .map(|scaled| scaled / denominator)
+        .ok_or_else(|| anyhow::anyhow!("overflow while scaling {amount} by rate {rate}"))
 }
 
-pub fn approve_program(inputs: Inputs) -> Result<String, anyhow::Error> {
+/// Multiplies `quantity` by a non-negative decimal `price` such as `"0.5"`,
+/// rounding up so a fractional price is never undercharged.
+pub fn cost_at_price(quantity: U256, price: &str) -> anyhow::Result<U256> {
+    let (numerator, denominator) =
+        parse_decimal(price).map_err(|e| anyhow::anyhow!("invalid price: {e:#}"))?;
+    let scaled = quantity
+        .checked_mul(numerator)
+        .ok_or_else(|| anyhow::anyhow!("overflow while pricing {quantity} at {price}"))?;
+    let remainder = if (scaled % denominator).is_zero() {
+        U256::zero()
+    } else {
+        U256::one()
+    };
+    Ok(scaled / denominator + remainder)
+}
+
+/// Divides `amount` by a positive decimal `price` such as `"0.5"`,
+/// truncating any fractional remainder.
+pub fn divide_by_price(amount: U256, price: &str) -> anyhow::Result<U256> {
+    let (numerator, denominator) =
+        parse_decimal(price).map_err(|e| anyhow::anyhow!("invalid price: {e:#}"))?;
+    if numerator.is_zero() {
+        anyhow::bail!("price '{price}' must be greater than zero");
+    }
+    amount
+        .checked_mul(denominator)
+        .map(|scaled| scaled / numerator)
+        .ok_or_else(|| anyhow::anyhow!("overflow while dividing {amount} by price {price}"))
+}
+
+/// Whether a `conversionRate` (tokens per unit of payment) and a `price`
+/// (units of payment per token) describe the same exchange, i.e. whether
+/// `rate * price == 1`.
+fn rate_matches_price(rate: &str, price: &str) -> anyhow::Result<bool> {
+    let (rate_numerator, rate_denominator) =
+        parse_decimal(rate).map_err(|e| anyhow::anyhow!("invalid rate: {e:#}"))?;
+    let (price_numerator, price_denominator) =
+        parse_decimal(price).map_err(|e| anyhow::anyhow!("invalid price: {e:#}"))?;
+    Ok(rate_numerator
+        .checked_mul(price_numerator)
+        .zip(rate_denominator.checked_mul(price_denominator))
+        .is_some_and(|(product, unit)| product == unit))
+}
+
+/// Looks up a program setting such as `totalSupply` or `conversionRate`,
+/// preferring the program account's own data and metadata over the copies
+/// held on the caller's token entry for the program. Metadata written by
+/// earlier versions under a single `metadata` key is read too.
+pub fn program_setting(inputs: &Inputs, key: &str) -> Option<String> {
+    let account = &inputs.account_info;
+    let program_id = inputs.transaction.program_id();
+    let metadata = account.program_account_metadata();
+    account
+        .program_account_data()
+        .inner()
+        .get(key)
+        .or_else(|| metadata.inner().get(key))
+        .cloned()
+        .or_else(|| {
+            metadata
+                .inner()
+                .get(LEGACY_METADATA_KEY)
+                .and_then(|legacy| legacy_metadata_entry(legacy, key))
+        })
+        .or_else(|| {
+            account.programs().get(&program_id).and_then(|token| {
+                token
+                    .data()
+                    .inner()
+                    .get(key)
+                    .cloned()
+                    .or_else(|| token.metadata().inner().get(key).cloned())
+            })
+        })
+}
+
+/// Builds an update instruction inserting `key` into the program's data.
+fn program_data_update(key: &str, value: String) -> anyhow::Result<UpdateInstruction> {
+    let update_field = ProgramUpdateFieldBuilder::new()
+        .field(ProgramField::Data)
+        .value(ProgramFieldValue::Data(DataValue::Insert(
+            key.to_string(),
+            value,
+        )))
+        .build()
+        .map_err(|e| anyhow::anyhow!("failed to build program update field: {e:?}"))?;
+
+    Ok(UpdateInstructionBuilder::new()
+        .add_update(TokenOrProgramUpdate::ProgramUpdate(
+            ProgramUpdateBuilder::new()
+                .account(AddressOrNamespace::This)
+                .add_update(update_field)
+                .build()
+                .map_err(|e| anyhow::anyhow!("failed to build program update: {e:?}"))?,
+        ))
+        .build())
+}
+
+/// Parses a supply from `transactionInputs`. Supplies are hex, with or
+/// without a `0x` prefix, as `create` has always read them: `"1000"` is 4096.
+pub fn parse_supply(field: &str, supply: Option<&str>) -> anyhow::Result<U256> {
+    let supply = supply.unwrap_or_default().trim();
+    let digits = supply.strip_prefix("0x").unwrap_or(supply);
+    U256::from_str_radix(digits, 16).map_err(|e| {
+        ProgramError::invalid_transaction_inputs(format!("invalid {field} '{supply}': {e:?}"))
+            .into()
+    })
+}
+
+/// Approves spenders to transfer the caller's tokens of the program.
+///
+/// `transactionInputs` is a list of `[spender, [tokenId, ...]]` pairs. Approving
+/// the caller itself is rejected, as is approving token IDs the caller's
+/// holdings show it does not hold, or only token IDs that are already approved.
+pub fn approve_program(inputs: Inputs) -> Result<Outputs, anyhow::Error> {
+    let approvals: Vec<(String, Vec<String>)> = parse_transaction_inputs(&inputs)?;
+    let state = AccountState::new(&inputs);
+    let invalid = |e: anyhow::Error| ProgramError::invalid_transaction_inputs(format!("{e:#}"));
+    let held = state
+        .token(&state.program_id())
+        .map(|token| token.token_ids().to_vec());
+
+    for (spender, token_ids) in &approvals {
+        let spender = parse_address(spender).map_err(invalid)?;
+        if spender == state.caller() {
+            return Err(ProgramError::rejected("the caller cannot approve itself").into());
+        }
+        let token_ids = token_ids
+            .iter()
+            .map(|token_id| parse_u256(token_id).map_err(invalid))
+            .collect::<Result<Vec<_>, _>>()?;
+        if let Some(missing) = held
+            .as_ref()
+            .and_then(|held| token_ids.iter().find(|token_id| !held.contains(token_id)))
+        {
+            return Err(
+                ProgramError::rejected(format!("caller does not hold token ID {missing}")).into(),
+            );
+        }
+        let approved = state.approvals_for(&spender)?;
+        if !token_ids.is_empty() && token_ids.iter().all(|token_id| approved.contains(token_id)) {
+            return Err(ProgramError::rejected(format!(
+                "{} is already approved for every requested token ID",
+                spender.to_full_string()
+            ))
+            .into());
+        }
+    }
+
     let transaction = &inputs.transaction;
     let txn_inputs = transaction.inputs();
     let program_id = transaction.program_id();
@@ -136,9 +463,10 @@ pub fn approve_program(inputs: Inputs) -> Result<String, anyhow::Error> {
         .build()
         .map_err(|e| anyhow::anyhow!("failed to build update_field: {e:?}"))?;
 
+    // The caller's holdings of the program's token carry the approvals.
     let token_update = TokenUpdateBuilder::new()
-        .token(AddressOrNamespace::Address(caller))
-        .account(program_address)
+        .account(AddressOrNamespace::Address(caller))
+        .token(program_address)
         .add_update(update)
         .build()
         .map_err(|e| anyhow::anyhow!("failed to build TokenUpdate for {caller}: {e:?}"))?;
@@ -155,45 +483,46 @@ pub fn approve_program(inputs: Inputs) -> Result<String, anyhow::Error> {
         .build()
         .map_err(|e| anyhow::anyhow!("failed to build computeOutputs: {e:?}"))?;
 
-    let obj = serde_json::to_string_pretty(&outputs).unwrap();
-
-    Ok(obj)
+    Ok(outputs)
 }
 
-pub fn create_program(inputs: Inputs) -> Result<String, anyhow::Error> {
+pub fn create_program(inputs: Inputs) -> Result<Outputs, anyhow::Error> {
     let transaction = &inputs.transaction;
     let from = transaction.from();
-    let txn_inputs: CreateTransactionInputs = serde_json::from_str(transaction.inputs().as_str())?;
-
-    let total_supply = txn_inputs.total_supply.unwrap_or_default();
-    let initialized_supply = txn_inputs.initialized_supply.unwrap_or_default();
+    let txn_inputs: CreateTransactionInputs = parse_transaction_inputs(&inputs)?;
 
-    let metadata_str =
-        serde_json::to_string(&(txn_inputs.symbol, txn_inputs.name, total_supply.clone()))
-            .map_err(|e| anyhow::anyhow!("failed to serialize txn metadata: {e:?}"))?;
-    let update_field_val =
-        ProgramFieldValue::Metadata(MetadataValue::Insert("metadata".to_string(), metadata_str));
+    let total_supply = parse_supply("totalSupply", txn_inputs.total_supply.as_deref())?;
+    let initialized_supply = parse_supply(
+        "initializedSupply",
+        txn_inputs.initialized_supply.as_deref(),
+    )?;
 
     let create_dist_instructions = CreateInstructionBuilder::new()
         .program_owner(from)
-        .initialized_supply(U256::from_str(&initialized_supply)?)
-        .total_supply(U256::from_str(&total_supply)?)
+        .initialized_supply(initialized_supply)
+        .total_supply(total_supply)
         .program_id(AddressOrNamespace::This)
         .program_namespace(AddressOrNamespace::This)
         .build()
         .map_err(|e| anyhow::anyhow!("failed to build create instructions: {e:?}"))?;
 
-    let program_metadata = ProgramUpdateFieldBuilder::new()
-        .field(ProgramField::Metadata)
-        .value(update_field_val)
-        .build()
-        .map_err(|e| anyhow::anyhow!("failed to build program update field: {e:?}"))?;
+    let program_metadata = ProgramMetadata {
+        name: txn_inputs.name,
+        symbol: txn_inputs.symbol,
+        total_supply: Some(format!("0x{total_supply:x}")),
+        initialized_supply: Some(format!("0x{initialized_supply:x}")),
+        img_url: txn_inputs.img_url,
+        collection: txn_inputs.collection,
+        payment_program_address: txn_inputs.payment_program_address,
+        price: txn_inputs.price,
+    }
+    .update_fields()?;
 
     let update_instruction = UpdateInstructionBuilder::new()
         .add_update(TokenOrProgramUpdate::ProgramUpdate(
             ProgramUpdateBuilder::new()
                 .account(AddressOrNamespace::This)
-                .add_update(program_metadata)
+                .extend_updates(program_metadata)
                 .build()
                 .map_err(|e| anyhow::anyhow!("failed to build program update: {e:?}"))?,
         ))
@@ -206,29 +535,16 @@ pub fn create_program(inputs: Inputs) -> Result<String, anyhow::Error> {
         .build()
         .map_err(|e| anyhow::anyhow!("failed to build computeOutputs: {e:?}"))?;
 
-    let obj = serde_json::to_string_pretty(&outputs).unwrap();
-
-    Ok(obj)
+    Ok(outputs)
 }
 
-pub fn update_program(inputs: Inputs) -> Result<String, anyhow::Error> {
-    let transaction = &inputs.transaction;
-    let txn_inputs: UpdateTransactionInputs = serde_json::from_str(transaction.inputs().as_str())?;
+pub fn update_program(inputs: Inputs) -> Result<Outputs, anyhow::Error> {
+    let txn_inputs: UpdateTransactionInputs = parse_transaction_inputs(&inputs)?;
 
     let mut program_updates = Vec::new();
 
     if let Some(metadata) = txn_inputs.metadata {
-        let metadata = serde_json::to_string(&metadata)?;
-        let update_field_val =
-            ProgramFieldValue::Metadata(MetadataValue::Insert("metadata".to_string(), metadata));
-
-        let update_field = ProgramUpdateFieldBuilder::new()
-            .field(ProgramField::Metadata)
-            .value(update_field_val)
-            .build()
-            .map_err(|e| anyhow::anyhow!("failed to build program update field: {e:?}"))?;
-
-        program_updates.push(update_field)
+        program_updates.extend(metadata.update_fields()?);
     };
 
     if let Some(data) = txn_inputs.data {
@@ -274,9 +590,178 @@ pub fn update_program(inputs: Inputs) -> Result<String, anyhow::Error> {
         .build()
         .map_err(|e| anyhow::anyhow!("failed to build computeOutputs: {e:?}"))?;
 
-    let obj = serde_json::to_string_pretty(&outputs).unwrap();
+    Ok(outputs)
+}
+
+/// Mints program tokens to the caller in exchange for the transaction value,
+/// paid in the program's `paymentProgramAddress` (ETH by default) and
+/// converted at its `price` per token, or else its `conversionRate` (1 by
+/// default). A program that sets both is rejected unless they agree.
+///
+/// The program's metadata must define `totalSupply`; minting is rejected if
+/// it would push the program's `currentSupply` past it. Until the first mint
+/// records it, `currentSupply` is the program's `initializedSupply`.
+pub fn mint_program(inputs: Inputs) -> Result<Outputs, anyhow::Error> {
+    let transaction = &inputs.transaction;
+    let caller = transaction.from();
+    let program_id = transaction.program_id();
+    let payment = transaction.value();
+
+    let state = AccountState::new(&inputs);
+
+    let total_supply: U256 = state.setting("totalSupply")?.ok_or_else(|| {
+        ProgramError::rejected(format!(
+            "program {} does not define a totalSupply",
+            program_id.to_full_string()
+        ))
+    })?;
+    let initialized_supply: U256 = state.setting("initializedSupply")?.unwrap_or_default();
+    if initialized_supply > total_supply {
+        return Err(ProgramError::rejected(format!(
+            "initializedSupply {initialized_supply} exceeds totalSupply {total_supply}"
+        ))
+        .into());
+    }
+    let current_supply: U256 = state
+        .setting("currentSupply")?
+        .unwrap_or(initialized_supply);
+
+    let price: Option<String> = state.setting("price")?;
+    let conversion_rate: Option<String> = state.setting("conversionRate")?;
+    let payment_program = match state.setting("paymentProgramAddress")? {
+        Some(payment_program) => payment_program,
+        None => parse_address(ETH_PROGRAM_ADDRESS)?,
+    };
 
-    Ok(obj)
+    if payment.is_zero() {
+        return Err(ProgramError::rejected("mint requires a non-zero transaction value").into());
+    }
+    let mint_amount = match (&price, &conversion_rate) {
+        (Some(price), Some(rate)) if !rate_matches_price(rate, price)? => {
+            return Err(ProgramError::rejected(format!(
+                "price {price} conflicts with conversionRate {rate}"
+            ))
+            .into());
+        }
+        (Some(price), _) => divide_by_price(payment, price)?,
+        (None, rate) => scale_by_rate(payment, rate.as_deref().unwrap_or("1"))?,
+    };
+    if mint_amount.is_zero() {
+        let exchange = match (&price, &conversion_rate) {
+            (Some(price), _) => format!("price {price}"),
+            (None, rate) => format!("conversionRate {}", rate.as_deref().unwrap_or("1")),
+        };
+        return Err(ProgramError::rejected(format!(
+            "a payment of {payment} at {exchange} mints no tokens"
+        ))
+        .into());
+    }
+    let minted_supply = current_supply
+        .checked_add(mint_amount)
+        .filter(|supply| *supply <= total_supply)
+        .ok_or_else(|| {
+            ProgramError::rejected(format!(
+                "minting {mint_amount} would exceed totalSupply {total_supply} (currentSupply {current_supply})"
+            ))
+        })?;
+
+    let payment_balance = state.balance_of(&payment_program);
+    if payment_balance < payment {
+        return Err(ProgramError::rejected(format!(
+            "insufficient balance in payment program {}: {payment_balance} < {payment}",
+            payment_program.to_full_string()
+        ))
+        .into());
+    }
+
+    let payment_transfer = TransferInstructionBuilder::new()
+        .token(payment_program)
+        .from(AddressOrNamespace::Address(caller))
+        .to(AddressOrNamespace::This)
+        .amount(payment)
+        .build()
+        .map_err(|e| anyhow::anyhow!("failed to build payment transfer: {e:?}"))?;
+
+    let mint_transfer = TransferInstructionBuilder::new()
+        .token(program_id)
+        .from(AddressOrNamespace::This)
+        .to(AddressOrNamespace::Address(caller))
+        .amount(mint_amount)
+        .build()
+        .map_err(|e| anyhow::anyhow!("failed to build mint transfer: {e:?}"))?;
+
+    let supply_update = program_data_update("currentSupply", minted_supply.to_string())?;
+
+    let outputs: Outputs = OutputsBuilder::new()
+        .inputs(inputs)
+        .add_instruction(lasr_types::Instruction::Transfer(payment_transfer))
+        .add_instruction(lasr_types::Instruction::Transfer(mint_transfer))
+        .add_instruction(lasr_types::Instruction::Update(supply_update))
+        .build()
+        .map_err(|e| anyhow::anyhow!("failed to build computeOutputs: {e:?}"))?;
+
+    Ok(outputs)
+}
+
+/// Burns program tokens held by the caller, decrementing the program's
+/// `currentSupply`. Until the first mint or burn records it, `currentSupply`
+/// is the program's `initializedSupply`.
+pub fn burn_program(inputs: Inputs) -> Result<Outputs, anyhow::Error> {
+    let txn_inputs: BurnTransactionInputs = parse_transaction_inputs(&inputs)?;
+    let transaction = &inputs.transaction;
+    let caller = transaction.from();
+    let program_id = transaction.program_id();
+
+    let amount = match txn_inputs.amount {
+        Some(amount) => parse_u256(&amount)
+            .map_err(|e| ProgramError::invalid_transaction_inputs(format!("{e:#}")))?,
+        None => transaction.value(),
+    };
+    if amount.is_zero() {
+        return Err(ProgramError::rejected("burn requires a non-zero amount").into());
+    }
+    let state = AccountState::new(&inputs);
+    let Some(token) = state.token(&program_id) else {
+        return Err(ProgramError::rejected(format!(
+            "cannot burn {amount}, caller holds none of {}",
+            program_id.to_full_string()
+        ))
+        .into());
+    };
+    let balance = token.balance();
+    if balance < amount {
+        return Err(ProgramError::rejected(format!(
+            "cannot burn {amount}, caller only holds {balance}"
+        ))
+        .into());
+    }
+
+    let current_supply: U256 = match state.setting("currentSupply")? {
+        Some(current_supply) => current_supply,
+        None => state.setting("initializedSupply")?.unwrap_or_default(),
+    };
+    let remaining_supply = current_supply.checked_sub(amount).ok_or_else(|| {
+        ProgramError::rejected(format!(
+            "cannot burn {amount}, more than the currentSupply {current_supply}"
+        ))
+    })?;
+    let supply_update = program_data_update("currentSupply", remaining_supply.to_string())?;
+
+    let burn_instruction = BurnInstructionBuilder::new()
+        .caller(caller)
+        .program_id(AddressOrNamespace::This)
+        .token(program_id)
+        .from(AddressOrNamespace::Address(caller))
+        .amount(amount)
+        .build()
+        .map_err(|e| anyhow::anyhow!("failed to build burn instruction: {e:?}"))?;
+
+    OutputsBuilder::new()
+        .inputs(inputs)
+        .add_instruction(lasr_types::Instruction::Burn(burn_instruction))
+        .add_instruction(lasr_types::Instruction::Update(supply_update))
+        .build()
+        .map_err(|e| anyhow::anyhow!("failed to build computeOutputs: {e:?}"))
 }
 
 #[cfg(test)]
@@ -300,6 +785,34 @@ async fn test_approval() -> Result<(), anyhow::Error> {
     Ok(())
 }
 
+#[test]
+fn test_approve_rejections() -> Result<(), anyhow::Error> {
+    use super::error::ProgramErrorKind;
+
+    let template_str =
+        include_str!("../../examples/fungible/example-program-inputs/fungible-approve.json");
+    let approve = |transaction_inputs: &str| -> anyhow::Result<ProgramErrorKind> {
+        let mut compute_inputs: serde_json::Value = serde_json::from_str(template_str)?;
+        compute_inputs["transaction"]["transactionInputs"] = transaction_inputs.into();
+        let err = Program::new()
+            .execute(&serde_json::from_value(compute_inputs)?)
+            .unwrap_err();
+        Ok(ProgramError::from(err).kind)
+    };
+
+    // The fixture's caller approving itself.
+    assert_eq!(
+        approve(r#"[["0x100444c7d04a842d19bc3ee63cb7b96682ff3f43",["0x1"]]]"#)?,
+        ProgramErrorKind::Rejected
+    );
+    assert_eq!(
+        approve(r#"[["not an address",["0x1"]]]"#)?,
+        ProgramErrorKind::InvalidTransactionInputs
+    );
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_create() -> Result<(), anyhow::Error> {
     let template_str =
@@ -339,3 +852,160 @@ async fn test_update() -> Result<(), anyhow::Error> {
 
     Ok(())
 }
+
+#[test]
+fn test_register_method() -> Result<(), anyhow::Error> {
+    #[derive(Deserialize)]
+    struct HelloInputs {
+        name: Option<String>,
+    }
+
+    let template_str =
+        include_str!("../../examples/blank/example-program-inputs/blank-create.json");
+
+    let mut compute_inputs: Inputs = serde_json::from_str(&template_str)
+        .map_err(|e| anyhow::anyhow!("failed to destructure json template: {e:?}"))?;
+    compute_inputs.op = "hello".to_string();
+
+    let mut program = Program::new();
+    assert!(program.execute(&compute_inputs).is_err());
+
+    program.register_method("hello", |inputs: &Inputs, txn_inputs: HelloInputs| {
+        assert_eq!(txn_inputs.name.as_deref(), Some("HelloToken"));
+        create_program(inputs.clone())
+    });
+    assert!(program.has_method("hello"));
+    assert!(program.execute(&compute_inputs).is_ok());
+
+    assert!(program.remove_method("create"));
+    assert_eq!(
+        program.methods(),
+        vec!["approve", "burn", "hello", "mint", "update"]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_respond_error_classes() -> Result<(), anyhow::Error> {
+    use super::error::ProgramErrorKind;
+
+    let program = Program::new();
+
+    let err = program.respond("not json").unwrap_err();
+    assert_eq!(err.kind, ProgramErrorKind::InvalidInputs);
+
+    let template_str =
+        include_str!("../../examples/blank/example-program-inputs/blank-create.json");
+    let mut compute_inputs: Inputs = serde_json::from_str(&template_str)?;
+    compute_inputs.op = "hello".to_string();
+    let err = program
+        .respond(&serde_json::to_string(&compute_inputs)?)
+        .unwrap_err();
+    assert_eq!(err.kind, ProgramErrorKind::UnknownMethod);
+    assert_eq!(err.code, 13);
+
+    Ok(())
+}
+
+#[test]
+fn test_mint() -> Result<(), anyhow::Error> {
+    let template_str =
+        include_str!("../../examples/fungible/example-program-inputs/fungible-mint.json");
+
+    let compute_inputs: Inputs = serde_json::from_str(&template_str)
+        .map_err(|e| anyhow::anyhow!("failed to destructure json template: {e:?}"))?;
+
+    let program = Program::new();
+    let outputs = program.execute(&compute_inputs)?;
+    assert_eq!(outputs.instructions().len(), 3);
+
+    let rejected = |template_str: &str| -> anyhow::Result<()> {
+        let compute_inputs: Inputs = serde_json::from_str(template_str)?;
+        let err = super::error::ProgramError::from(program.execute(&compute_inputs).unwrap_err());
+        assert_eq!(err.kind, super::error::ProgramErrorKind::Rejected);
+        Ok(())
+    };
+    rejected(include_str!(
+        "../../examples/fungible/example-program-inputs/fungible-mint-fail.json"
+    ))?;
+    // initializedSupply already equals totalSupply, so nothing is left to mint.
+    rejected(include_str!(
+        "../../examples/fungible/example-program-inputs/fungible-mint-exhausted-fail.json"
+    ))?;
+
+    // The fixture pays 1e18 of its payment token.
+    let token = "0x100444c7D04A842D19bc3eE63cB7b96682FF3f43";
+    let mint_at = |price: &str, conversion_rate: Option<&str>| -> anyhow::Result<Outputs> {
+        let mut compute_inputs: serde_json::Value = serde_json::from_str(template_str)?;
+        let data = compute_inputs["accountInfo"]["programs"][token]["data"]
+            .as_object_mut()
+            .expect("fixture has program data");
+        data.insert("price".to_string(), price.into());
+        match conversion_rate {
+            Some(rate) => data.insert("conversionRate".to_string(), rate.into()),
+            None => data.remove("conversionRate"),
+        };
+        program.execute(&serde_json::from_value(compute_inputs)?)
+    };
+    let minted = |outputs: Outputs| -> anyhow::Result<serde_json::Value> {
+        Ok(serde_json::to_value(outputs)?["instructions"][1]["transfer"]["amount"].clone())
+    };
+    let amount = |amount: u128| serde_json::json!(format!("0x{amount:064x}"));
+
+    assert_eq!(
+        minted(mint_at("10", Some("0.1"))?)?,
+        amount(100_000_000_000_000_000)
+    );
+    assert_eq!(
+        minted(mint_at("0.5", None)?)?,
+        amount(2_000_000_000_000_000_000)
+    );
+    let err = super::error::ProgramError::from(mint_at("1", Some("0.1")).unwrap_err());
+    assert_eq!(err.kind, super::error::ProgramErrorKind::Rejected);
+
+    Ok(())
+}
+
+#[test]
+fn test_burn() -> Result<(), anyhow::Error> {
+    let template_str =
+        include_str!("../../examples/fungible/example-program-inputs/fungible-burn.json");
+
+    let compute_inputs: Inputs = serde_json::from_str(&template_str)
+        .map_err(|e| anyhow::anyhow!("failed to destructure json template: {e:?}"))?;
+
+    let program = Program::new();
+    let outputs = program.execute(&compute_inputs)?;
+    assert_eq!(outputs.instructions().len(), 2);
+    // currentSupply falls back to initializedSupply, 0x3e8.
+    assert!(serde_json::to_string(&outputs)?.contains(r#"["currentSupply","999"]"#));
+
+    let template_str =
+        include_str!("../../examples/fungible/example-program-inputs/fungible-burn-fail.json");
+    assert!(program.respond(template_str).is_err());
+
+    let template_str =
+        include_str!("../../examples/fungible/example-program-inputs/fungible-burn.json");
+    let burn = |edit: &dyn Fn(&mut serde_json::Value)| -> anyhow::Result<ProgramError> {
+        let mut compute_inputs: serde_json::Value = serde_json::from_str(template_str)?;
+        edit(&mut compute_inputs);
+        let err = program
+            .execute(&serde_json::from_value(compute_inputs)?)
+            .unwrap_err();
+        Ok(ProgramError::from(err))
+    };
+    // A caller without a holding of the token burns nothing.
+    let err =
+        burn(&|compute_inputs| compute_inputs["accountInfo"]["programs"] = serde_json::json!({}))?;
+    assert_eq!(err.kind, super::error::ProgramErrorKind::Rejected);
+    assert!(err.message.contains("holds none"));
+    // Burning more than the currentSupply is rejected instead of saturating.
+    let err = burn(&|compute_inputs| {
+        compute_inputs["accountInfo"]["programAccountData"]["currentSupply"] = "0".into()
+    })?;
+    assert_eq!(err.kind, super::error::ProgramErrorKind::Rejected);
+    assert!(err.message.contains("currentSupply"));
+
+    Ok(())
+}
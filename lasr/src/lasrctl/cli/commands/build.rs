@@ -62,8 +62,7 @@ impl BuildArgs {
         let program = Program::new();
         let result = program
             .execute_method(&inputs)
-            .map_err(|e| e.to_string())
-            .unwrap();
+            .map_err(|e| anyhow::anyhow!("failed to execute LASR program method: {e:#}"))?;
 
         if !output_dir.exists() {
             println!("Generating new Program outputs at {output_dir:?}");
@@ -71,9 +70,8 @@ impl BuildArgs {
             let mut f = fs::File::options()
                 .create(true)
                 .write(true)
-                .open(output_dir.clone())
-                .unwrap();
-            writeln!(&mut f, "{json_output}").unwrap();
+                .open(output_dir.clone())?;
+            writeln!(&mut f, "{json_output}")?;
         } else {
             println!("Found existing LASR Program Outputs! In order to avoid accidental overwrites, please remove the `temp.json` file and try again.")
         }
@@ -1,5 +1,9 @@
+use std::path::PathBuf;
+
 use clap::Args;
 
+use crate::lasrctl::{builders::program::parse_u256, rpc::SigningClient};
+
 #[derive(Args, Debug)]
 pub struct CallArgs {
     /// Program address to be sent
@@ -11,13 +15,34 @@ pub struct CallArgs {
     tx_inputs: String,
     /// Value (in verse) to be sent to the program method
     value: String,
-    /// Desired network. Options: "stable" or "test"
+    /// Desired network. Options: "stable", "test", "local" or an RPC URL
     #[arg(short = 'x', default_value = "stable")]
     network: String,
-    /// Path to the keypair file
-    #[arg(default_value = "./.lasr/wallet/keypair.json")]
-    keypair_path: String,
-    /// Secret key for the wallet
-    #[arg(short = 'k')]
-    secret_key: String,
+    /// Name of the wallet keypair to use. Defaults to the wallet's default keypair
+    #[arg(long)]
+    wallet: Option<String>,
+    /// Path to a keypair file, overriding `--wallet`. Encrypted keystores are unlocked
+    /// with `LASR_KEYSTORE_PASSPHRASE`, or a passphrase prompt
+    #[arg(long)]
+    keypair_path: Option<PathBuf>,
+}
+
+impl CallArgs {
+    /// Signs a call of `op` with the wallet keypair and sends it to the network.
+    pub async fn lasr_call(&self) -> anyhow::Result<()> {
+        let client = SigningClient::from_wallet(
+            &self.network,
+            self.wallet.as_deref(),
+            self.keypair_path.as_deref(),
+        )?;
+        let value = parse_u256(&self.value)?;
+        let hash = client
+            .call(&self.program_address, &self.op, &self.tx_inputs, value)
+            .await?;
+        println!(
+            "called {} on {}: transaction {hash}",
+            self.op, self.program_address
+        );
+        Ok(())
+    }
 }
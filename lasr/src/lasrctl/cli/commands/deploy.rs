@@ -1,4 +1,11 @@
+use std::{fs, path::PathBuf};
+
+use anyhow::bail;
 use clap::Args;
+use lasr_types::U256;
+use serde_json::Value;
+
+use crate::lasrctl::rpc::SigningClient;
 
 #[derive(Args, Debug)]
 pub struct DeployArgs {
@@ -17,10 +24,10 @@ pub struct DeployArgs {
     /// Name for the program
     #[arg(short = 'p')]
     program_name: String,
-    /// Supply of the token to be sent to either the caller or the program
+    /// Supply of the token to be sent to either the caller or the program, in hex
     #[arg(default_value = "1")]
     init_supply: String,
-    /// Total supply of the token to be created
+    /// Total supply of the token to be created, in hex
     #[arg(short = 't', default_value = "1")]
     total_supply: String,
     /// Address for the initialized supply
@@ -29,14 +36,67 @@ pub struct DeployArgs {
     /// Additional inputs for the program
     #[arg(default_value = "{}")]
     tx_inputs: String,
-    /// Path to the keypair file
-    #[arg(default_value = "./.lasr/wallet/keypair.json")]
-    keypair_path: String,
-    /// Secret key for the wallet
-    #[arg(short = 'k')]
-    secret_key: String,
-    /// Network handle. Options: "stable" or "unstable"
+    /// Name of the wallet keypair to use. Defaults to the wallet's default keypair
+    #[arg(long)]
+    wallet: Option<String>,
+    /// Path to a keypair file, overriding `--wallet`. Encrypted keystores are unlocked
+    /// with `LASR_KEYSTORE_PASSPHRASE`, or a passphrase prompt
+    #[arg(long)]
+    keypair_path: Option<PathBuf>,
+    /// Content ID of the program's published package. Without it, the path of
+    /// the built program is sent as `binaryPath`, which is how
+    /// `lasr node --local` locates it
+    #[arg(long)]
+    content_id: Option<String>,
+    /// Network handle. Options: "stable", "unstable", "local" or an RPC URL
     #[arg(short = 'x', default_value = "stable")]
     network: String,
 }
 
+impl DeployArgs {
+    /// Registers the program with the network, then calls its `create` method
+    /// with the token's name, symbol and supplies.
+    pub async fn lasr_deploy(&self) -> anyhow::Result<()> {
+        let client = SigningClient::from_wallet(
+            &self.network,
+            self.wallet.as_deref(),
+            self.keypair_path.as_deref(),
+        )?;
+        let mut register_inputs = serde_json::json!({
+            "author": self.author,
+            "programName": self.program_name,
+        });
+        match &self.content_id {
+            Some(content_id) => register_inputs["contentId"] = Value::String(content_id.clone()),
+            None => {
+                let binary = fs::canonicalize(&self.build).map_err(|e| {
+                    anyhow::anyhow!("built program {:?} was not found: {e:?}", self.build)
+                })?;
+                register_inputs["binaryPath"] =
+                    Value::String(binary.to_string_lossy().into_owned());
+            }
+        }
+        let program = client.register_program(&register_inputs).await?;
+        println!("registered program {program}");
+
+        let mut create_inputs: Value = serde_json::from_str(&self.tx_inputs)
+            .map_err(|e| anyhow::anyhow!("tx_inputs are not valid JSON: {e:?}"))?;
+        let Some(fields) = create_inputs.as_object_mut() else {
+            bail!("tx_inputs must be a JSON object, got {}", self.tx_inputs);
+        };
+        for (field, value) in [
+            ("name", &self.name),
+            ("symbol", &self.symbol),
+            ("totalSupply", &self.total_supply),
+            ("initializedSupply", &self.init_supply),
+            ("to", &self.recipient_address),
+        ] {
+            fields.insert(field.to_string(), Value::String(value.clone()));
+        }
+        let hash = client
+            .call(&program, "create", &create_inputs.to_string(), U256::zero())
+            .await?;
+        println!("created {program} in transaction {hash}");
+        Ok(())
+    }
+}
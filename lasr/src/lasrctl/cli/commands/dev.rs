@@ -0,0 +1,357 @@
+use anyhow::bail;
+use clap::Args;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::{self, IsTerminal, Write},
+    path::{Path, PathBuf},
+    process::Command,
+    thread,
+    time::{Duration, Instant},
+};
+
+use super::{
+    scenario::run_steps,
+    test::{expected_path, fixtures, FixtureOutcome, TestArgs},
+};
+use crate::lasrctl::{scenario::Scenario, watch::Snapshot};
+
+/// The maximum number of differences shown per fixture on the dashboard.
+const MAX_DASHBOARD_DIFFS: usize = 5;
+/// The number of trailing lines of a failed build's output shown on the dashboard.
+const MAX_BUILD_ERROR_LINES: usize = 20;
+
+#[derive(Args, Debug)]
+pub struct DevArgs {
+    /// Filename of the built program. Ex: "target/release/example-program"
+    #[arg(short = 'b')]
+    build: PathBuf,
+    /// Path to the JSON input file or dir containing JSON files for testing
+    #[arg(short = 'i')]
+    input_json: PathBuf,
+    /// Scenario files to rerun alongside the fixtures
+    #[arg(short = 's', long = "scenario")]
+    scenarios: Vec<PathBuf>,
+    /// Source directory whose changes trigger a rebuild
+    #[arg(long, default_value = "src")]
+    src: PathBuf,
+    /// How often to check for changes, in milliseconds
+    #[arg(long, default_value_t = 500)]
+    interval_ms: u64,
+    /// Don't run `cargo build --release` on source changes, e.g. when another
+    /// process rebuilds the program
+    #[arg(long)]
+    no_build: bool,
+    /// Run a single build and test cycle, then exit
+    #[arg(long)]
+    once: bool,
+}
+
+/// What a set of file changes requires to be rerun.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Rerun {
+    pub rebuild: bool,
+    pub fixtures: BTreeSet<PathBuf>,
+    pub scenarios: BTreeSet<PathBuf>,
+}
+
+impl Rerun {
+    pub fn is_empty(&self) -> bool {
+        !self.rebuild && self.fixtures.is_empty() && self.scenarios.is_empty()
+    }
+}
+
+/// The latest result of everything `lasr dev` runs.
+#[derive(Debug, Default)]
+struct Dashboard {
+    build: Option<Result<Duration, String>>,
+    fixtures: BTreeMap<PathBuf, FixtureOutcome>,
+    scenarios: BTreeMap<PathBuf, Result<Vec<String>, Vec<String>>>,
+    errors: Vec<String>,
+}
+
+impl DevArgs {
+    /// Builds the program and runs every fixture and scenario, then watches the
+    /// source and fixture paths, rerunning whatever a change affects.
+    pub fn lasr_dev(&self) -> anyhow::Result<()> {
+        if !self.input_json.exists() {
+            bail!(
+                "{:?} does not exist, please provide a valid path and try again.",
+                self.input_json
+            );
+        }
+        let watched = self.watched_paths();
+        let mut snapshot = Snapshot::take(&watched);
+        let mut dashboard = Dashboard::default();
+        let mut rerun = Rerun {
+            rebuild: !self.no_build,
+            fixtures: fixtures(&self.input_json)?.into_iter().collect(),
+            scenarios: self.scenarios.iter().cloned().collect(),
+        };
+
+        loop {
+            self.run(&rerun, &mut dashboard)?;
+            self.render(&dashboard)?;
+            if self.once {
+                let failed = dashboard.failed();
+                if failed > 0 {
+                    bail!("{failed} fixtures or scenarios failed");
+                }
+                return Ok(());
+            }
+
+            rerun = loop {
+                thread::sleep(Duration::from_millis(self.interval_ms));
+                let current = Snapshot::take(&watched);
+                let mut changes = current.changes_since(&snapshot);
+                if changes.is_empty() {
+                    continue;
+                }
+                // Editors often write a file in several steps, so wait for the
+                // tree to settle before deciding what to rerun.
+                thread::sleep(Duration::from_millis(self.interval_ms));
+                let settled = Snapshot::take(&watched);
+                changes.extend(settled.changes_since(&current));
+                snapshot = settled;
+                let rerun = self.affected_by(&changes)?;
+                if !rerun.is_empty() {
+                    break rerun;
+                }
+            };
+        }
+    }
+
+    fn watched_paths(&self) -> Vec<PathBuf> {
+        let mut watched = vec![
+            self.src.clone(),
+            PathBuf::from("Cargo.toml"),
+            self.input_json.clone(),
+        ];
+        watched.extend(self.scenarios.iter().cloned());
+        watched
+    }
+
+    /// Decides what to rerun after `changes`: source changes rebuild and rerun
+    /// everything, fixture and golden file changes rerun just that fixture.
+    pub fn affected_by(&self, changes: &[PathBuf]) -> anyhow::Result<Rerun> {
+        let fixtures = if self.input_json.exists() {
+            fixtures(&self.input_json)?
+        } else {
+            Vec::new()
+        };
+        let mut rerun = Rerun::default();
+        for change in changes {
+            // Fixtures may live under the source directory, so they are
+            // matched first to avoid needless rebuilds.
+            if let Some(fixture) = fixtures
+                .iter()
+                .find(|fixture| *fixture == change || expected_path(fixture) == *change)
+            {
+                rerun.fixtures.insert(fixture.clone());
+            } else if self.scenarios.contains(change) {
+                rerun.scenarios.insert(change.clone());
+            } else if change.starts_with(&self.input_json) && !change.exists() {
+                // A removed fixture only needs to disappear from the dashboard.
+                rerun.fixtures.insert(change.clone());
+            } else if change.starts_with(&self.src) || change == Path::new("Cargo.toml") {
+                rerun.rebuild = !self.no_build;
+                rerun.fixtures.extend(fixtures.iter().cloned());
+                rerun.scenarios.extend(self.scenarios.iter().cloned());
+            }
+        }
+        Ok(rerun)
+    }
+
+    fn run(&self, rerun: &Rerun, dashboard: &mut Dashboard) -> anyhow::Result<()> {
+        dashboard.errors.clear();
+        if rerun.rebuild {
+            self.render_status("building...")?;
+            dashboard.build = Some(build_release());
+        }
+        if let Some(Err(_)) = dashboard.build {
+            // Keep the last results around rather than running a stale binary.
+            return Ok(());
+        }
+        if !self.build.exists() {
+            dashboard.errors.push(format!(
+                "{:?} does not exist, build the program or pass its path with -b",
+                self.build
+            ));
+            return Ok(());
+        }
+
+        let test_args = TestArgs::new(self.build.clone(), self.input_json.clone());
+        for fixture in &rerun.fixtures {
+            if !fixture.exists() {
+                dashboard.fixtures.remove(fixture);
+                continue;
+            }
+            self.render_status(&format!("running {}...", fixture.display()))?;
+            let outcome = test_args
+                .check_fixture(fixture, None)
+                .unwrap_or_else(|e| FixtureOutcome::Failed(vec![format!("{e:#}")]));
+            dashboard.fixtures.insert(fixture.clone(), outcome);
+        }
+        for path in &rerun.scenarios {
+            self.render_status(&format!("running scenario {}...", path.display()))?;
+            dashboard
+                .scenarios
+                .insert(path.clone(), run_scenario(&self.build, path));
+        }
+        Ok(())
+    }
+
+    /// Shows a one-line progress note while a cycle runs.
+    fn render_status(&self, status: &str) -> anyhow::Result<()> {
+        let mut stdout = io::stdout();
+        if stdout.is_terminal() {
+            write!(stdout, "\r\x1b[2K{status}")?;
+            stdout.flush()?;
+        }
+        Ok(())
+    }
+
+    fn render(&self, dashboard: &Dashboard) -> anyhow::Result<()> {
+        let mut stdout = io::stdout().lock();
+        if stdout.is_terminal() && !self.once {
+            // Clear the screen and move the cursor home.
+            write!(stdout, "\x1b[2J\x1b[H")?;
+        }
+        writeln!(
+            stdout,
+            "lasr dev: watching {}, {}",
+            self.src.display(),
+            self.input_json.display()
+        )?;
+        match &dashboard.build {
+            Some(Ok(elapsed)) => writeln!(stdout, "build ... ok ({:.1}s)", elapsed.as_secs_f64())?,
+            Some(Err(error)) => {
+                writeln!(stdout, "build ... FAILED")?;
+                let lines: Vec<&str> = error.lines().collect();
+                for line in &lines[lines.len().saturating_sub(MAX_BUILD_ERROR_LINES)..] {
+                    writeln!(stdout, "    {line}")?;
+                }
+            }
+            None => {}
+        }
+        for error in &dashboard.errors {
+            writeln!(stdout, "error: {error}")?;
+        }
+        writeln!(stdout)?;
+
+        for (fixture, outcome) in &dashboard.fixtures {
+            let name = fixture.display();
+            match outcome {
+                FixtureOutcome::Passed(_) | FixtureOutcome::Updated => {
+                    writeln!(stdout, "  ok    {name}")?
+                }
+                FixtureOutcome::Failed(reasons) => {
+                    writeln!(stdout, "  FAIL  {name}")?;
+                    write_reasons(&mut stdout, reasons)?;
+                }
+            }
+        }
+        for (scenario, result) in &dashboard.scenarios {
+            let name = scenario.display();
+            match result {
+                Ok(steps) => writeln!(stdout, "  ok    scenario {name} ({} steps)", steps.len())?,
+                Err(failures) => {
+                    writeln!(stdout, "  FAIL  scenario {name}")?;
+                    write_reasons(&mut stdout, failures)?;
+                }
+            }
+        }
+
+        let total = dashboard.fixtures.len() + dashboard.scenarios.len();
+        let failed = dashboard.failed();
+        writeln!(stdout, "\n{} passed; {failed} failed", total - failed)?;
+        if !self.once {
+            writeln!(stdout, "waiting for changes (Ctrl-C to exit)")?;
+        }
+        stdout.flush()?;
+        Ok(())
+    }
+}
+
+impl Dashboard {
+    fn failed(&self) -> usize {
+        let failed_fixtures = self
+            .fixtures
+            .values()
+            .filter(|outcome| matches!(outcome, FixtureOutcome::Failed(_)))
+            .count();
+        let failed_scenarios = self.scenarios.values().filter(|r| r.is_err()).count();
+        let failed_build = usize::from(matches!(self.build, Some(Err(_))));
+        failed_fixtures + failed_scenarios + failed_build + self.errors.len()
+    }
+}
+
+fn write_reasons(stdout: &mut impl Write, reasons: &[String]) -> io::Result<()> {
+    for reason in reasons.iter().take(MAX_DASHBOARD_DIFFS) {
+        writeln!(stdout, "        {reason}")?;
+    }
+    if reasons.len() > MAX_DASHBOARD_DIFFS {
+        writeln!(
+            stdout,
+            "        ... and {} more",
+            reasons.len() - MAX_DASHBOARD_DIFFS
+        )?;
+    }
+    Ok(())
+}
+
+/// Runs `cargo build --release`, returning how long it took or its error output.
+fn build_release() -> Result<Duration, String> {
+    let started = Instant::now();
+    match Command::new("cargo").args(["build", "--release"]).output() {
+        Ok(output) if output.status.success() => Ok(started.elapsed()),
+        Ok(output) => Err(String::from_utf8_lossy(&output.stderr).into_owned()),
+        Err(e) => Err(format!("failed to run cargo: {e:?}")),
+    }
+}
+
+/// Runs a scenario from a fresh ledger, returning the step names on success
+/// or the failures of each failed step.
+fn run_scenario(build: &Path, path: &Path) -> Result<Vec<String>, Vec<String>> {
+    let scenario = Scenario::load(path).map_err(|e| vec![format!("{e:#}")])?;
+    let run = run_steps(build, &scenario).map_err(|e| vec![format!("{e:#}")])?;
+    let failures = run.failures();
+    if failures.is_empty() {
+        Ok(run.reports.into_iter().map(|report| report.name).collect())
+    } else {
+        Err(failures)
+    }
+}
+
+#[test]
+fn test_affected_by() -> Result<(), anyhow::Error> {
+    let inputs = PathBuf::from("src/examples/fungible/example-program-inputs");
+    let dev_args = DevArgs {
+        build: PathBuf::from("target/release/fungible"),
+        input_json: inputs.clone(),
+        scenarios: vec![PathBuf::from("scenarios/flow.json")],
+        src: PathBuf::from("src"),
+        interval_ms: 500,
+        no_build: false,
+        once: true,
+    };
+    let create = inputs.join("fungible-create.json");
+
+    let rerun = dev_args.affected_by(&[expected_path(&create)])?;
+    assert!(!rerun.rebuild);
+    assert_eq!(rerun.fixtures, BTreeSet::from([create.clone()]));
+    assert!(rerun.scenarios.is_empty());
+
+    let rerun = dev_args.affected_by(&[PathBuf::from("scenarios/flow.json")])?;
+    assert_eq!(rerun.fixtures, BTreeSet::new());
+    assert_eq!(rerun.scenarios.len(), 1);
+
+    let rerun = dev_args.affected_by(&[PathBuf::from("src/main.rs")])?;
+    assert!(rerun.rebuild);
+    assert!(rerun.fixtures.contains(&create));
+    assert_eq!(rerun.scenarios.len(), 1);
+
+    assert!(dev_args
+        .affected_by(&[PathBuf::from("README.md")])?
+        .is_empty());
+    Ok(())
+}
@@ -0,0 +1,40 @@
+use anyhow::bail;
+use clap::Args;
+use serde_json::Value;
+use std::{fs, io::Read, path::PathBuf};
+
+use crate::lasrctl::explain::explain_outputs;
+
+#[derive(Args, Debug)]
+pub struct ExplainArgs {
+    /// Path to a JSON file holding a program's `Outputs`, read from stdin if omitted or `-`
+    outputs: Option<PathBuf>,
+    /// Exit with an error if any suspicious pattern is found
+    #[arg(long)]
+    deny_warnings: bool,
+}
+
+impl ExplainArgs {
+    pub fn lasr_explain(&self) -> anyhow::Result<()> {
+        let raw_outputs = match &self.outputs {
+            Some(path) if path.as_os_str() != "-" => fs::read_to_string(path)
+                .map_err(|e| anyhow::anyhow!("failed to read outputs {path:?}: {e:?}"))?,
+            _ => {
+                let mut raw_outputs = String::new();
+                std::io::stdin()
+                    .read_to_string(&mut raw_outputs)
+                    .map_err(|e| anyhow::anyhow!("failed to read outputs from stdin: {e:?}"))?;
+                raw_outputs
+            }
+        };
+        let outputs: Value = serde_json::from_str(&raw_outputs)
+            .map_err(|e| anyhow::anyhow!("outputs are not valid JSON: {e:?}"))?;
+
+        let explanation = explain_outputs(&outputs);
+        print!("{explanation}");
+        if self.deny_warnings && explanation.warning_count() > 0 {
+            bail!("found {} warning(s)", explanation.warning_count());
+        }
+        Ok(())
+    }
+}
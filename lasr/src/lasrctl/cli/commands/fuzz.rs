@@ -0,0 +1,99 @@
+use anyhow::bail;
+use clap::Args;
+use serde_json::Value;
+use std::{
+    fs,
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use super::test::fixtures;
+use crate::lasrctl::fuzz::{fuzz, run_binary, FuzzConfig};
+
+#[derive(Args, Debug)]
+pub struct FuzzArgs {
+    /// Filename of the built program. Ex: "target/release/example-program"
+    #[arg(short = 'b')]
+    build: PathBuf,
+    /// Path to the JSON input file or dir containing the JSON fixtures to mutate
+    #[arg(short = 'i')]
+    input_json: PathBuf,
+    /// Number of mutated calls to run
+    #[arg(short = 'n', long, default_value_t = 1000)]
+    iterations: usize,
+    /// Seed for the mutations, to reproduce a previous run. Random if omitted
+    #[arg(long)]
+    seed: Option<u64>,
+    /// Time a single call may take before it is reported as a timeout, in milliseconds
+    #[arg(long, default_value_t = 2000)]
+    timeout_ms: u64,
+    /// Directory to write the payload of each finding to, for `lasr test -i`
+    #[arg(long)]
+    save: Option<PathBuf>,
+}
+
+impl FuzzArgs {
+    /// Runs mutated fixtures against the program binary and reports every
+    /// panic, timeout, malformed output and invariant violation found.
+    pub fn lasr_fuzz(&self) -> anyhow::Result<()> {
+        if !self.build.exists() {
+            bail!(
+                "{:?} does not exist, please provide a valid path and try again.",
+                self.build
+            );
+        }
+        let mut corpus = Vec::new();
+        for fixture in fixtures(&self.input_json)? {
+            let contents = fs::read_to_string(&fixture)
+                .map_err(|e| anyhow::anyhow!("failed to read fixture {fixture:?}: {e:?}"))?;
+            if let Ok(inputs) = serde_json::from_str::<Value>(&contents) {
+                corpus.push((fixture.display().to_string(), inputs));
+            }
+        }
+        let seed = self.seed.unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_nanos() as u64)
+                .unwrap_or_default()
+        });
+        println!(
+            "fuzzing {:?} with {} mutations of {} fixtures (seed {seed})",
+            self.build,
+            self.iterations,
+            corpus.len()
+        );
+
+        let timeout = Duration::from_millis(self.timeout_ms);
+        let config = FuzzConfig {
+            iterations: self.iterations,
+            seed,
+        };
+        let report = fuzz(&corpus, config, |payload| {
+            run_binary(&self.build, payload, timeout)
+        })?;
+
+        if let Some(save) = &self.save {
+            fs::create_dir_all(save)
+                .map_err(|e| anyhow::anyhow!("failed to create {save:?}: {e:?}"))?;
+        }
+        for (index, finding) in report.findings.iter().enumerate() {
+            println!("\n{finding}");
+            if let Some(save) = &self.save {
+                // Not named `*.json`, so `lasr test` on the fixture directory
+                // doesn't pick them up.
+                let path = save.join(format!("finding-{seed}-{index}.payload"));
+                fs::write(&path, &finding.payload)
+                    .map_err(|e| anyhow::anyhow!("failed to write {path:?}: {e:?}"))?;
+                println!("    payload saved to {path:?}");
+            }
+        }
+        println!("\n{report}");
+        if !report.findings.is_empty() {
+            bail!(
+                "found {} issues, rerun with --seed {seed} to reproduce",
+                report.findings.len()
+            );
+        }
+        Ok(())
+    }
+}
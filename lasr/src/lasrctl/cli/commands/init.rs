@@ -1,185 +1,254 @@
-use crate::{cargo, scripts::consts::KEYPAIR_FILENAME};
+use crate::{
+    cargo,
+    lasrctl::{
+        keystore::{new_passphrase, KEYSTORE_PASSPHRASE_ENV},
+        templates::{list_templates, Template, TemplateSource, TemplateVariables, FIXTURES_DIR},
+        wallet::{KeypairFile, WalletDir, DEFAULT_KEYPAIR_NAME},
+    },
+    scripts::consts::LASR_RUST_GIT_URL,
+};
 use clap::Parser;
-use jsonrpsee::http_client::HttpClient;
-use lasr_wallet::Wallet;
 use std::{
     env, fs,
-    io::Write,
+    io::{self, IsTerminal},
     path::{Path, PathBuf},
+    process::Output,
 };
 
 #[derive(Debug, Parser)]
 #[clap(bin_name = "lasr init")]
+#[clap(group(clap::ArgGroup::new("template_choice").args(["template", "blank", "fungible", "non_fungible", "faucet"])))]
 pub struct InitArgs {
-    /// A minimal template to start from scratch
+    /// Name of the project, used for its directory and cargo package
+    pub name: String,
+    /// Directory to create the project in. Defaults to the current directory
+    #[clap(long)]
+    pub path: Option<PathBuf>,
+    /// Template to start from: a built-in or installed template name, a template
+    /// directory or a git url. See `lasr templates list`
+    #[clap(long, short = 't')]
+    pub template: Option<String>,
+    /// Token symbol substituted into the template. Defaults to the start of the name
+    #[clap(long)]
+    pub symbol: Option<String>,
+    /// Shorthand for `--template blank`
     #[clap(long)]
     pub blank: bool,
-    /// A template for creating fungible tokens
+    /// Shorthand for `--template fungible`
     #[clap(long)]
     pub fungible: bool,
-    /// A template for creating non-fungible tokens
+    /// Shorthand for `--template non_fungible`
     #[clap(long)]
     pub non_fungible: bool,
-    /// A template for creating a faucet, allowing users to request test tokens
+    /// Shorthand for `--template faucet`
     #[clap(long)]
     pub faucet: bool,
 }
-impl InitArgs {
-    fn init_template(
-        project_dir: &PathBuf,
-        json_content: &str,
-        example_program: &str,
-    ) -> anyhow::Result<()> {
-        fs::create_dir_all(&project_dir)?;
-
-        let src_dir = Path::new(&project_dir).join("src");
-        fs::create_dir_all(&src_dir)?;
 
-        let json_file_path = Path::new(&project_dir).join("example-program-inputs.json");
-        let mut json_file = fs::File::create(json_file_path)?;
-        json_file.write_all(json_content.as_bytes())?;
+impl InitArgs {
+    fn template_source(&self) -> anyhow::Result<TemplateSource> {
+        let template = match self {
+            InitArgs {
+                template: Some(template),
+                ..
+            } => template.as_str(),
+            InitArgs { blank: true, .. } => "blank",
+            InitArgs { fungible: true, .. } => "fungible",
+            InitArgs {
+                non_fungible: true, ..
+            } => "non_fungible",
+            InitArgs { faucet: true, .. } => "faucet",
+            _ => anyhow::bail!(
+                "no template selected, pass --template with one of: {}",
+                list_templates()?
+                    .iter()
+                    .map(|t| t.name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        };
+        TemplateSource::parse(template)
+    }
 
+    fn init_template(&self, project_dir: &Path, template: &Template) -> anyhow::Result<()> {
         // Run `cargo init` to initialize the project as a cargo project
-        let output = cargo!(&"init", &"--bin", project_dir)?;
-        if output.status.success() {
-            let main_rs_path = src_dir.join("main.rs");
-            let mut main_rs_file = fs::File::create(main_rs_path)?;
-            main_rs_file.write_all(example_program.as_bytes())?;
-
-            cargo!(&"add", &"anyhow@1.0", &"lasr_types", &"serde_json@1.0")?;
+        let output = cargo!(&"init", &"--bin", &"--name", &self.name, &project_dir)?;
+        check_cargo_status("init", &output)?;
+
+        write_template(project_dir, template)?;
+
+        let manifest_path = project_dir.join("Cargo.toml");
+        let output = cargo!(
+            &"add",
+            &"--manifest-path",
+            &manifest_path,
+            &"anyhow@1.0",
+            &"lasr_types",
+            &"serde_json@1.0"
+        )?;
+        check_cargo_status("add", &output)?;
+        let output = cargo!(
+            &"add",
+            &"--manifest-path",
+            &manifest_path,
+            &"lasr-rust",
+            &"--git",
+            &LASR_RUST_GIT_URL
+        )?;
+        check_cargo_status("add lasr-rust", &output)?;
+
+        create_new_keypair(project_dir)
+    }
 
-            //TODO: Eventually add `lasr_rust` as cargo dep for cli access
+    pub fn lasr_init(&self) -> anyhow::Result<()> {
+        let template = Template::load(
+            &self.template_source()?,
+            &TemplateVariables::new(&self.name, self.symbol.as_deref()),
+        )?;
+
+        let parent_dir = match &self.path {
+            Some(path) => path.clone(),
+            None => env::current_dir()?,
+        };
+        if !parent_dir.is_dir() {
+            anyhow::bail!("{parent_dir:?} is not a valid directory.");
+        }
+        let project_dir = parent_dir.join(&self.name);
+        if project_dir.exists() {
+            anyhow::bail!("{project_dir:?} already exists");
+        }
 
-            println!("Successfully initalized LASR application folder");
-        } else {
-            eprintln!("Failed to initalized LASR application folder");
-            eprintln!("Error: {}", String::from_utf8_lossy(&output.stderr));
+        println!(
+            "Creating LASR project '{}' from template '{}' at {project_dir:?}",
+            self.name, template.name
+        );
+        fs::create_dir_all(&project_dir)?;
+        // Leave nothing behind if any step fails, so the command can be re-run.
+        if let Err(e) = self.init_template(&project_dir, &template) {
+            fs::remove_dir_all(&project_dir).map_err(|cleanup| {
+                anyhow::anyhow!("{e:#}, and failed to remove {project_dir:?}: {cleanup:?}")
+            })?;
+            return Err(e);
         }
 
+        println!("Initialization completed successfully!");
+        println!(
+            "Build with `cargo build` in {project_dir:?}, then run `lasr test -b target/debug/{} -i {FIXTURES_DIR}`",
+            self.name
+        );
         Ok(())
     }
+}
 
-    pub fn lasr_init(&self) -> anyhow::Result<()> {
-        let project_dir = &env::current_dir()?;
-        if !project_dir.is_dir() {
-            anyhow::bail!(format!("{project_dir:?} is not a valid directory."));
-        }
+/// Writes the template's files into `project_dir`, pointing the imports of
+/// the built-in templates, which are compiled as part of this crate, at the
+/// published crate instead.
+fn write_template(project_dir: &Path, template: &Template) -> anyhow::Result<()> {
+    template.write(project_dir)?;
+    let main_rs_path = project_dir.join("src").join("main.rs");
+    let main_rs = fs::read_to_string(&main_rs_path)?;
+    fs::write(
+        &main_rs_path,
+        main_rs.replace("crate::lasrctl", "lasr_rust::lasrctl"),
+    )?;
+    Ok(())
+}
 
-        dbg!(
-            "\nUsing project directory at {}",
-            project_dir.canonicalize()?.display()
+fn check_cargo_status(command: &str, output: &Output) -> anyhow::Result<()> {
+    if !output.status.success() {
+        anyhow::bail!(
+            "cargo {command} failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
         );
-
-        let keypair_dir = Path::new(&project_dir).join(".lasr");
-        fs::create_dir_all(&keypair_dir)?;
-
-        create_new_keypair(keypair_dir);
-
-        match self {
-            InitArgs { blank: true, .. } => {
-                let json_content = include_str!(
-                    "../../../examples/blank/example-program-inputs/blank-create.json"
-                );
-
-                let example_program = include_str!("../../../examples/blank/example_program.rs");
-
-                if let Err(e) = Self::init_template(&project_dir, &json_content, &example_program) {
-                    eprintln!("Error initializing LASR program: {e:?}");
-                    Ok(())
-                } else {
-                    println!("Initialization completed successfully!");
-                    Ok(())
-                }
-            }
-            InitArgs { fungible: true, .. } => {
-                let json_content = include_str!(
-                    "../../../examples/fungible/example-program-inputs/fungible-create.json"
-                );
-
-                let example_program = include_str!("../../../examples/fungible/example_program.rs");
-
-                if let Err(e) = Self::init_template(&project_dir, &json_content, &example_program) {
-                    eprintln!("Error initializing LASR program: {e:?}");
-                    Ok(())
-                } else {
-                    println!("Initialization completed successfully!");
-                    Ok(())
-                }
-            }
-            InitArgs {
-                non_fungible: true, ..
-            } => {
-                let json_content = include_str!(
-                    "../../../examples/non_fungible/example-program-inputs/non-fungible-create.json"
-                );
-
-                let example_program =
-                    include_str!("../../../examples/non_fungible/example_program.rs");
-
-                if let Err(e) = Self::init_template(&project_dir, &json_content, &example_program) {
-                    eprintln!("Error initializing LASR program: {e:?}");
-                    Ok(())
-                } else {
-                    println!("Initialization completed successfully!");
-                    Ok(())
-                }
-            }
-            InitArgs { faucet: true, .. } => {
-                let json_content = include_str!(
-                    "../../../examples/faucet/example-program-inputs/faucet-create.json"
-                );
-
-                let example_program = include_str!("../../../examples/faucet/example_program.rs");
-
-                if let Err(e) = Self::init_template(&project_dir, &json_content, &example_program) {
-                    eprintln!("Error initializing LASR program: {e:?}");
-                    Ok(())
-                } else {
-                    println!("Initialization completed successfully!");
-                    Ok(())
-                }
-            }
-            _ => Err(anyhow::anyhow!("unsupported initialization method")),
-        }
     }
+    Ok(())
 }
 
-pub fn create_new_keypair(wallet_path: PathBuf) {
-    let mut keypair_path = wallet_path.clone();
-    keypair_path.push(KEYPAIR_FILENAME);
+/// Generates the project's default keypair in `.lasr/wallet`, unless one exists.
+pub fn create_new_keypair(project_dir: &Path) -> anyhow::Result<()> {
+    let wallet = WalletDir::in_project(project_dir);
+    let keypair_path = wallet.keypair_path(DEFAULT_KEYPAIR_NAME);
     if !keypair_path.exists() {
         println!("Generating new keypair at {keypair_path:?}");
-        let wallet_info = Wallet::<HttpClient>::get_info(None, None, None)
-            .expect("failed to create new LASR Wallet");
-        let contents =
-            serde_json::to_string_pretty(&wallet_info).expect("failed to serialize WalletInfo");
-        let mut f = fs::File::options()
-            .create(true)
-            .write(true)
-            .open(keypair_path.clone())
-            .unwrap();
-        writeln!(&mut f, "{contents}").unwrap();
+        let passphrase = if env::var(KEYSTORE_PASSPHRASE_ENV).is_ok() || io::stdin().is_terminal() {
+            Some(new_passphrase()?)
+        } else {
+            eprintln!(
+                "warning: no {KEYSTORE_PASSPHRASE_ENV} set, storing the keypair unencrypted. Run `lasr wallet encrypt` to protect it."
+            );
+            None
+        };
+        wallet.save(
+            DEFAULT_KEYPAIR_NAME,
+            &KeypairFile::generate()?,
+            passphrase.as_deref(),
+        )?;
     } else {
         println!("Found existing LASR Wallet!")
     }
+    Ok(())
 }
 
 #[test]
-fn test_init_template() {
-    let res = crate::lasrctl::cli::InitArgs::lasr_init(&InitArgs {
+fn test_init_template() -> Result<(), anyhow::Error> {
+    let parent_dir = env::temp_dir().join(format!("lasr-init-test-{}", std::process::id()));
+    let args = InitArgs {
+        name: "blank-program".to_string(),
+        path: Some(parent_dir.clone()),
+        template: None,
+        symbol: None,
         blank: true,
         fungible: false,
         non_fungible: false,
         faucet: false,
-    });
-
-    assert!(res.is_ok());
+    };
+    let project_dir = parent_dir.join(&args.name);
+    let template = Template::load(
+        &args.template_source()?,
+        &TemplateVariables::new(&args.name, args.symbol.as_deref()),
+    )?;
+
+    write_template(&project_dir, &template)?;
+    let main_rs = fs::read_to_string(project_dir.join("src/main.rs"))?;
+    assert!(main_rs.contains("lasr_rust::lasrctl"));
+    assert!(!main_rs.contains("crate::lasrctl"));
+    assert!(project_dir
+        .join(FIXTURES_DIR)
+        .join("blank-create.json")
+        .exists());
+
+    // Init refuses an existing project before running cargo, and leaves it be.
+    let err = InitArgs::lasr_init(&args).expect_err("init into an existing project");
+    assert!(err.to_string().contains("already exists"));
+    assert!(project_dir.join("src/main.rs").exists());
+    fs::remove_dir_all(&parent_dir)?;
+
+    let no_template = InitArgs {
+        blank: false,
+        ..args
+    };
+    let err = InitArgs::lasr_init(&no_template).expect_err("init without a template");
+    assert!(err.to_string().contains("hello_lasr"));
+    Ok(())
 }
 
 #[test]
-fn test_keypair_json_creation() {
-    let project_dir = &env::current_dir().expect("failed to obtain working directory");
-    let keypair_dir = Path::new(&project_dir).join(".lasr");
-    fs::create_dir_all(&keypair_dir).expect("failed to create keypair dir: .lasr");
-    create_new_keypair(keypair_dir);
+fn test_keypair_json_creation() -> Result<(), anyhow::Error> {
+    let project_dir = env::temp_dir().join(format!("lasr-keypair-test-{}", std::process::id()));
+    env::set_var(KEYSTORE_PASSPHRASE_ENV, "correct horse");
+    create_new_keypair(&project_dir)?;
+
+    let wallet = WalletDir::in_project(&project_dir);
+    let stored = wallet.load_stored(DEFAULT_KEYPAIR_NAME)?;
+    assert!(stored.is_encrypted());
+    let keypair = wallet.load(DEFAULT_KEYPAIR_NAME)?;
+    assert_eq!(keypair.address, stored.address());
+    assert!(keypair.mnemonic.is_some());
+
+    // An existing keypair is kept.
+    create_new_keypair(&project_dir)?;
+    assert_eq!(wallet.load_stored(DEFAULT_KEYPAIR_NAME)?, stored);
+
+    fs::remove_dir_all(&project_dir)?;
+    Ok(())
 }
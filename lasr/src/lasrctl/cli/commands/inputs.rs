@@ -0,0 +1,174 @@
+use std::{fs, path::PathBuf};
+
+use anyhow::bail;
+use clap::{Args, Subcommand};
+use lasr_types::{Inputs, U256};
+use serde_json::Value;
+
+use crate::lasrctl::{
+    builders::program::parse_u256,
+    ledger::{call_inputs_json, normalize, Ledger},
+    rpc::{compose_account_info, get_account},
+    signing::{address_from_secret_key, sign_transaction},
+    wallet::{read_keypair_file, WalletDir},
+};
+
+#[derive(Args, Debug)]
+pub struct InputsArgs {
+    #[command(subcommand)]
+    command: InputsCommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum InputsCommand {
+    /// Generate a well-formed `Inputs` fixture for a program call
+    New(NewInputsArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct NewInputsArgs {
+    /// Operation to be performed by the program
+    #[arg(long)]
+    op: String,
+    /// Address of the program being called
+    #[arg(short = 'p', long)]
+    program_id: String,
+    /// Address of the sender. Defaults to the address of the local keypair
+    #[arg(long)]
+    from: Option<String>,
+    /// Value sent with the call, in decimal or `0x` prefixed hex
+    #[arg(long, default_value = "0")]
+    value: String,
+    /// Transaction inputs as a JSON object, stringified into `transactionInputs`
+    #[arg(long, default_value = "{}")]
+    tx_inputs: String,
+    /// Nonce of the transaction. Defaults to the sender's current nonce
+    #[arg(long)]
+    nonce: Option<String>,
+    /// Sign the transaction with the local keypair
+    #[arg(long)]
+    sign: bool,
+    /// Name of the wallet keypair to use. Defaults to the wallet's default keypair
+    #[arg(long)]
+    wallet: Option<String>,
+    /// Path to a keypair file, overriding `--wallet`
+    #[arg(long)]
+    keypair_path: Option<PathBuf>,
+    /// Ledger state file (see `lasr test --state`) to fill `accountInfo` from
+    #[arg(long, conflicts_with = "network")]
+    state: Option<PathBuf>,
+    /// Network to fetch `accountInfo` from. Options: "stable", "unstable", "local" or an RPC URL
+    #[arg(short = 'x', long)]
+    network: Option<String>,
+    /// Write the fixture to this file instead of stdout
+    #[arg(short = 'o', long)]
+    output: Option<PathBuf>,
+}
+
+impl InputsArgs {
+    pub async fn lasr_inputs(&self) -> anyhow::Result<()> {
+        match &self.command {
+            InputsCommand::New(new_args) => new_args.run().await,
+        }
+    }
+}
+
+impl NewInputsArgs {
+    pub async fn run(&self) -> anyhow::Result<()> {
+        let inputs = self.build().await?;
+        let contents = format!("{}\n", serde_json::to_string_pretty(&inputs)?);
+        match &self.output {
+            Some(output) => {
+                fs::write(output, contents)
+                    .map_err(|e| anyhow::anyhow!("failed to write fixture to {output:?}: {e:?}"))?;
+                println!("Wrote {} fixture to {output:?}", self.op);
+            }
+            None => print!("{contents}"),
+        }
+        Ok(())
+    }
+
+    /// Builds the fixture, checking that it deserializes as `lasr_types::Inputs`.
+    pub async fn build(&self) -> anyhow::Result<Inputs> {
+        let secret_key = if self.sign || self.from.is_none() {
+            Some(
+                read_keypair_file(
+                    &WalletDir::default()
+                        .resolve(self.wallet.as_deref(), self.keypair_path.as_deref()),
+                )?
+                .secret_key()?,
+            )
+        } else {
+            None
+        };
+        let caller = match (&self.from, &secret_key) {
+            (Some(from), Some(secret_key))
+                if normalize(from) != address_from_secret_key(secret_key) =>
+            {
+                bail!(
+                    "--from {from} does not match the signing keypair's address {}",
+                    address_from_secret_key(secret_key)
+                )
+            }
+            (Some(from), _) => normalize(from),
+            (None, Some(secret_key)) => address_from_secret_key(secret_key),
+            (None, None) => bail!("a sender is required, pass --from or a keypair"),
+        };
+
+        let transaction_inputs = match serde_json::from_str::<Value>(&self.tx_inputs)
+            .map_err(|e| anyhow::anyhow!("--tx-inputs is not valid JSON: {e}"))?
+        {
+            Value::String(transaction_inputs) => transaction_inputs,
+            transaction_inputs => serde_json::to_string(&transaction_inputs)?,
+        };
+        let value = parse_u256(&self.value)?;
+
+        let (account_info, current_nonce) = match (&self.state, &self.network) {
+            (Some(state), _) => {
+                let ledger = Ledger::load(state)?;
+                let nonce = ledger
+                    .account(&caller)
+                    .map(|account| account.nonce)
+                    .unwrap_or_default();
+                (ledger.account_info(&caller, &self.program_id), nonce)
+            }
+            (None, Some(network)) => {
+                let caller_account = get_account(network, &caller).await?;
+                let program_account = get_account(network, &self.program_id).await?;
+                let nonce = caller_account
+                    .get("nonce")
+                    .and_then(Value::as_str)
+                    .map(parse_u256)
+                    .transpose()?
+                    .unwrap_or_default();
+                (
+                    compose_account_info(&caller_account, &program_account),
+                    nonce,
+                )
+            }
+            (None, None) => (
+                Ledger::new().account_info(&caller, &self.program_id),
+                U256::zero(),
+            ),
+        };
+        let nonce = match &self.nonce {
+            Some(nonce) => parse_u256(nonce)?,
+            None => current_nonce,
+        };
+
+        let mut inputs = call_inputs_json(
+            account_info,
+            &caller,
+            &self.program_id,
+            &self.op,
+            &transaction_inputs,
+            value,
+            nonce,
+        );
+        if let (true, Some(secret_key)) = (self.sign, &secret_key) {
+            sign_transaction(&mut inputs["transaction"], secret_key)?;
+        }
+        serde_json::from_value(inputs)
+            .map_err(|e| anyhow::anyhow!("generated fixture is not valid Inputs: {e:?}"))
+    }
+}
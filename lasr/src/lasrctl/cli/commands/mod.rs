@@ -1,6 +1,16 @@
 pub mod build;
 pub mod call;
 pub mod deploy;
+pub mod dev;
+pub mod explain;
+pub mod fuzz;
 pub mod init;
+pub mod inputs;
+pub mod node;
+pub mod query;
+pub mod scenario;
 pub mod send;
+pub mod templates;
 pub mod test;
+pub mod tx;
+pub mod wallet;
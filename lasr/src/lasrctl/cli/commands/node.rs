@@ -0,0 +1,51 @@
+use anyhow::bail;
+use clap::Args;
+use std::{net::SocketAddr, path::PathBuf};
+
+use crate::lasrctl::node::{faucet_amount, serve, LocalNode, FAUCET_METHOD};
+
+#[derive(Args, Debug)]
+pub struct NodeArgs {
+    /// Run a local stand-in node that executes registered program binaries
+    /// as subprocesses
+    #[arg(long)]
+    local: bool,
+    /// Port to serve JSON-RPC on, matching the "local" network by default
+    #[arg(long, default_value_t = 9292)]
+    port: u16,
+    /// File to keep the node's accounts and programs in; state is kept in
+    /// memory if omitted
+    #[arg(long)]
+    state: Option<PathBuf>,
+    /// Addresses to credit from the faucet on startup
+    #[arg(long)]
+    fund: Vec<String>,
+}
+
+impl NodeArgs {
+    pub async fn lasr_node(&self) -> anyhow::Result<()> {
+        if !self.local {
+            bail!("only local nodes are supported, run `lasr node --local`");
+        }
+        let mut node = match &self.state {
+            Some(state) => LocalNode::persistent(state)?,
+            None => LocalNode::in_memory(),
+        };
+        for address in &self.fund {
+            node.faucet(address)?;
+            println!("funded {address} with {:#x} ETH and VERSE", faucet_amount());
+        }
+
+        let addr = SocketAddr::from(([127, 0, 0, 1], self.port));
+        println!("local LASR node listening on http://{addr}");
+        match &self.state {
+            Some(state) => println!("state is saved to {state:?}"),
+            None => println!("state is kept in memory and lost on exit"),
+        }
+        println!("request funds with the {FAUCET_METHOD} method, e.g.");
+        println!(
+            "  curl -s -X POST -H 'Content-Type: application/json' http://{addr} \\\n    -d '{{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"{FAUCET_METHOD}\",\"params\":[\"0x...\"]}}'"
+        );
+        serve(node, addr).await
+    }
+}
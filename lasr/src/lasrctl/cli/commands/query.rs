@@ -0,0 +1,238 @@
+use anyhow::bail;
+use clap::Args;
+use serde_json::Value;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::lasrctl::{
+    builders::program::parse_u256,
+    rpc::{compose_account_info, get_account},
+};
+
+#[derive(Args, Debug)]
+pub struct QueryArgs {
+    /// Address of the account to fetch
+    address: String,
+    /// Network handle. Options: "stable", "unstable", "local" or an RPC URL
+    #[arg(short = 'x', default_value = "stable")]
+    network: String,
+    /// Print the raw `lasr_types::Account` JSON instead of a summary
+    #[arg(long)]
+    json: bool,
+    /// Write the fetched state into the `accountInfo` of this `Inputs` fixture
+    #[arg(long)]
+    save_fixture: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+pub struct AccountArgs {
+    #[command(flatten)]
+    query: QueryArgs,
+}
+
+#[derive(Args, Debug)]
+pub struct ProgramArgs {
+    #[command(flatten)]
+    query: QueryArgs,
+}
+
+impl AccountArgs {
+    /// Prints an account's nonce and its holdings of every program's token.
+    pub async fn lasr_account(&self) -> anyhow::Result<()> {
+        let account = get_account(&self.query.network, &self.query.address).await?;
+        if let Some(fixture) = &self.query.save_fixture {
+            // The account becomes the fixture's caller, keeping the program's state.
+            save_fixture(fixture, |account_info| {
+                compose_account_info(&account, account_info)
+            })?;
+        }
+        if self.query.json {
+            println!("{}", serde_json::to_string_pretty(&account)?);
+        } else {
+            println!(
+                "{}",
+                describe_account(&self.query.address, &account).join("\n")
+            );
+        }
+        Ok(())
+    }
+}
+
+impl ProgramArgs {
+    /// Prints a program account's owner, metadata, data and linked programs.
+    pub async fn lasr_program(&self) -> anyhow::Result<()> {
+        let program = get_account(&self.query.network, &self.query.address).await?;
+        if program.pointer("/accountType/program").is_none() {
+            bail!("{} is not a program account", self.query.address);
+        }
+        if let Some(fixture) = &self.query.save_fixture {
+            // The program's state replaces the fixture's, keeping the caller's holdings.
+            save_fixture(fixture, |account_info| {
+                compose_account_info(account_info, &program)
+            })?;
+        }
+        if self.query.json {
+            println!("{}", serde_json::to_string_pretty(&program)?);
+        } else {
+            println!(
+                "{}",
+                describe_program(&self.query.address, &program).join("\n")
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Replaces the `accountInfo` of the fixture at `path` with `update` of it.
+fn save_fixture(path: &Path, update: impl FnOnce(&Value) -> Value) -> anyhow::Result<()> {
+    if !path.exists() {
+        bail!("fixture {path:?} does not exist, create one with `lasr inputs new` first");
+    }
+    let contents = fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("failed to read fixture {path:?}: {e:?}"))?;
+    let mut fixture: Value = serde_json::from_str(&contents)
+        .map_err(|e| anyhow::anyhow!("failed to parse fixture {path:?}: {e:?}"))?;
+    let account_info = fixture.get("accountInfo").cloned().unwrap_or(Value::Null);
+    fixture["accountInfo"] = update(&account_info);
+    fs::write(
+        path,
+        format!("{}\n", serde_json::to_string_pretty(&fixture)?),
+    )
+    .map_err(|e| anyhow::anyhow!("failed to write fixture {path:?}: {e:?}"))?;
+    eprintln!("Wrote accountInfo to {path:?}");
+    Ok(())
+}
+
+/// Summarizes a serialized `lasr_types::Account` from its holder's point of view.
+pub fn describe_account(address: &str, account: &Value) -> Vec<String> {
+    let mut lines = vec![
+        format!("account {}", address.to_lowercase()),
+        format!("  type: {}", account_type(account)),
+        format!("  nonce: {}", amount(account.get("nonce"))),
+    ];
+    let programs = account
+        .get("programs")
+        .and_then(Value::as_object)
+        .cloned()
+        .unwrap_or_default();
+    if programs.is_empty() {
+        lines.push("  programs: none".to_string());
+    } else {
+        lines.push("  programs:".to_string());
+    }
+    for (program, token) in &programs {
+        lines.push(format!(
+            "    {program}: balance {}",
+            amount(token.get("balance"))
+        ));
+        let token_ids: Vec<String> = token
+            .get("tokenIds")
+            .and_then(Value::as_array)
+            .map(|ids| ids.iter().map(|id| amount(Some(id))).collect())
+            .unwrap_or_default();
+        if !token_ids.is_empty() {
+            lines.push(format!("      token IDs: {}", token_ids.join(", ")));
+        }
+        for field in ["approvals", "allowance", "metadata", "data"] {
+            push_entries(&mut lines, "      ", field, token.get(field));
+        }
+    }
+    lines
+}
+
+/// Summarizes the program state of a serialized `lasr_types::Account`.
+pub fn describe_program(address: &str, program: &Value) -> Vec<String> {
+    let mut lines = vec![
+        format!("program {}", address.to_lowercase()),
+        format!(
+            "  owner: {}",
+            program
+                .get("ownerAddress")
+                .and_then(Value::as_str)
+                .unwrap_or("?")
+        ),
+    ];
+    if let Some(namespace) = program.get("programNamespace").filter(|ns| !ns.is_null()) {
+        lines.push(format!("  namespace: {}", render(namespace)));
+    }
+    push_entries(
+        &mut lines,
+        "  ",
+        "metadata",
+        program.get("programAccountMetadata"),
+    );
+    push_entries(&mut lines, "  ", "data", program.get("programAccountData"));
+    let linked: Vec<String> = program
+        .get("programAccountLinkedPrograms")
+        .and_then(Value::as_array)
+        .map(|linked| linked.iter().map(render).collect())
+        .unwrap_or_default();
+    if !linked.is_empty() {
+        lines.push(format!("  linked programs: {}", linked.join(", ")));
+    }
+    lines
+}
+
+/// Pushes `name:` followed by one line per entry of a map, if it has any.
+fn push_entries(lines: &mut Vec<String>, indent: &str, name: &str, map: Option<&Value>) {
+    let Some(map) = map.and_then(Value::as_object).filter(|map| !map.is_empty()) else {
+        return;
+    };
+    lines.push(format!("{indent}{name}:"));
+    for (key, value) in map {
+        lines.push(format!("{indent}  {key} = {}", render(value)));
+    }
+}
+
+fn account_type(account: &Value) -> String {
+    match account.get("accountType") {
+        Some(Value::String(account_type)) => account_type.clone(),
+        Some(Value::Object(account_type)) => account_type
+            .iter()
+            .map(|(kind, address)| format!("{kind} {}", render(address)))
+            .collect::<Vec<_>>()
+            .join(", "),
+        _ => "?".to_string(),
+    }
+}
+
+/// Renders a `U256` hex string in decimal.
+fn amount(value: Option<&Value>) -> String {
+    match value.and_then(Value::as_str).map(parse_u256) {
+        Some(Ok(amount)) => amount.to_string(),
+        _ => value.map(render).unwrap_or_else(|| "?".to_string()),
+    }
+}
+
+fn render(value: &Value) -> String {
+    match value {
+        Value::String(value) => value.clone(),
+        value => value.to_string(),
+    }
+}
+
+#[test]
+fn test_describe_account() -> Result<(), anyhow::Error> {
+    use crate::lasrctl::ledger::Ledger;
+    use lasr_types::U256;
+
+    let holder = "0x482830d7655fb8465a43844fc1530a7713781b49";
+    let program = "0x100444c7d04a842d19bc3ee63cb7b96682ff3f43";
+    let mut ledger = Ledger::new();
+    ledger.credit(holder, program, U256::from(100));
+    let account = ledger.account_info(holder, holder);
+
+    assert_eq!(
+        describe_account(holder, &account),
+        vec![
+            format!("account {holder}"),
+            format!("  type: program {holder}"),
+            "  nonce: 0".to_string(),
+            "  programs:".to_string(),
+            format!("    {program}: balance 100"),
+        ]
+    );
+    Ok(())
+}
@@ -0,0 +1,100 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::bail;
+use clap::{Args, Subcommand};
+use serde_json::Value;
+
+use super::test::{failure_reason, run_program};
+use crate::lasrctl::{
+    builders::error::ProgramError,
+    scenario::{run_scenario, Scenario, ScenarioRun},
+};
+
+#[derive(Args, Debug)]
+pub struct ScenarioArgs {
+    #[command(subcommand)]
+    command: ScenarioCommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ScenarioCommand {
+    /// Run a scenario file against a built program, simulating state between steps
+    Run(ScenarioRunArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct ScenarioRunArgs {
+    /// Filename of the built program. Ex: "path/to/example-program"
+    #[arg(short = 'b')]
+    build: PathBuf,
+    /// Path to the scenario JSON file
+    scenario: PathBuf,
+    /// Write the ledger state left by the scenario to this file
+    #[arg(long)]
+    state: Option<PathBuf>,
+}
+
+impl ScenarioArgs {
+    pub fn lasr_scenario(&self) -> anyhow::Result<()> {
+        match &self.command {
+            ScenarioCommand::Run(run_args) => run_args.run(),
+        }
+    }
+}
+
+impl ScenarioRunArgs {
+    /// Runs every step of the scenario against the program binary, printing
+    /// the outcome of each step. Returns an error if any step failed.
+    pub fn run(&self) -> anyhow::Result<()> {
+        if !self.build.exists() {
+            bail!(
+                "{:?} does not exist, please provide a valid path and try again.",
+                self.build
+            );
+        }
+        let scenario = Scenario::load(&self.scenario)?;
+        println!(
+            "running scenario {} ({} steps) against {:?}",
+            scenario
+                .name
+                .clone()
+                .unwrap_or_else(|| self.scenario.display().to_string()),
+            scenario.steps.len(),
+            self.build
+        );
+
+        let ScenarioRun { ledger, reports } = run_steps(&self.build, &scenario)?;
+        if let Some(state) = &self.state {
+            ledger.save(state)?;
+        }
+
+        for report in &reports {
+            println!("{report}");
+        }
+        let failed = reports.iter().filter(|report| !report.passed()).count();
+        println!(
+            "\nscenario result: {}. {} passed; {failed} failed",
+            if failed == 0 { "ok" } else { "FAILED" },
+            reports.len() - failed
+        );
+        if failed > 0 {
+            bail!("{failed} of {} scenario steps failed", reports.len());
+        }
+        Ok(())
+    }
+}
+
+/// Runs the scenario from a fresh ledger against the program binary at `build`.
+pub fn run_steps(build: &Path, scenario: &Scenario) -> anyhow::Result<ScenarioRun> {
+    run_scenario(scenario, |inputs| {
+        let output = run_program(build, &serde_json::to_string(inputs)?)?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        if !output.status.success() {
+            return Ok(Err(ProgramError::from_json(&stdout).unwrap_or_else(|| {
+                ProgramError::internal(failure_reason(&output))
+            })));
+        }
+        Ok(serde_json::from_str::<Value>(&stdout)
+            .map_err(|e| ProgramError::internal(format!("program output is not valid JSON: {e}"))))
+    })
+}
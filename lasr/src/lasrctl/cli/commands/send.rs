@@ -1,5 +1,9 @@
+use std::path::PathBuf;
+
 use clap::Args;
 
+use crate::lasrctl::{builders::program::parse_u256, rpc::SigningClient};
+
 #[derive(Args, Debug)]
 pub struct SendArgs {
     /// Program address to be sent
@@ -7,16 +11,37 @@ pub struct SendArgs {
     program_address: String,
     /// Amount to be sent (in Verse)
     amount: String,
-    /// Address for the initialized supply
+    /// Address to send the tokens to
     #[arg(short = 'r')]
     recipient_address: String,
-    /// Network to send on
+    /// Network to send on. Options: "stable", "unstable", "local" or an RPC URL
     #[arg(short = 'x', default_value = "stable")]
     network: String,
-    /// Path to the keypair file
-    #[arg(default_value = "./.lasr/wallet/keypair.json")]
-    keypair_path: String,
-    /// Secret key for the wallet
-    #[arg(short = 'k')]
-    secret_key: String,
+    /// Name of the wallet keypair to use. Defaults to the wallet's default keypair
+    #[arg(long)]
+    wallet: Option<String>,
+    /// Path to a keypair file, overriding `--wallet`. Encrypted keystores are unlocked
+    /// with `LASR_KEYSTORE_PASSPHRASE`, or a passphrase prompt
+    #[arg(long)]
+    keypair_path: Option<PathBuf>,
+}
+
+impl SendArgs {
+    /// Signs a transfer with the wallet keypair and sends it to the network.
+    pub async fn lasr_send(&self) -> anyhow::Result<()> {
+        let client = SigningClient::from_wallet(
+            &self.network,
+            self.wallet.as_deref(),
+            self.keypair_path.as_deref(),
+        )?;
+        let amount = parse_u256(&self.amount)?;
+        let hash = client
+            .send(&self.program_address, &self.recipient_address, amount)
+            .await?;
+        println!(
+            "sent {amount} of {} to {}: transaction {hash}",
+            self.program_address, self.recipient_address
+        );
+        Ok(())
+    }
 }
@@ -0,0 +1,40 @@
+use clap::{Args, Subcommand};
+
+use crate::lasrctl::templates::{list_templates, templates_dir, TemplateSource};
+
+#[derive(Args, Debug)]
+pub struct TemplatesArgs {
+    #[command(subcommand)]
+    command: TemplatesCommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum TemplatesCommand {
+    /// List the built-in templates and those installed in the templates directory
+    List,
+}
+
+impl TemplatesArgs {
+    pub fn lasr_templates(&self) -> anyhow::Result<()> {
+        match &self.command {
+            TemplatesCommand::List => {
+                for template in list_templates()? {
+                    let origin = match &template.source {
+                        TemplateSource::Builtin(_) => "built-in".to_string(),
+                        TemplateSource::Path(path) => format!("{path:?}"),
+                        TemplateSource::Git(url) => url.clone(),
+                    };
+                    println!(
+                        "{:<20} {:<10} {}",
+                        template.name, origin, template.description
+                    );
+                }
+                println!(
+                    "\nInstall templates by adding directories with a lasr-template.json to {:?}",
+                    templates_dir()
+                );
+            }
+        }
+        Ok(())
+    }
+}
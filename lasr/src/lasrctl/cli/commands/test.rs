@@ -1,11 +1,32 @@
 use anyhow::bail;
 use clap::Args;
+use serde_json::Value;
 use std::{
-    io::Write,
-    path::PathBuf,
-    process::{Output, Stdio},
+    fs,
+    path::{Path, PathBuf},
+    process::Output,
+    time::Duration,
 };
 
+use crate::lasrctl::{
+    builders::error::{ProgramError, ProgramErrorKind},
+    determinism::{check_determinism, infer_source_dir, scan_source, DEFAULT_DETERMINISM_RUNS},
+    explain::explain_outputs,
+    ledger::Ledger,
+    profile::{human_bytes, run_profiled, ExecutionLimits},
+    validate::validate_outputs,
+};
+use lasr_types::Inputs;
+
+/// Suffix of the golden file holding a fixture's expected outputs, e.g.
+/// `create.json` is compared against `create.expected.json`.
+pub const EXPECTED_SUFFIX: &str = ".expected.json";
+/// Suffix of the file stem marking a fixture the program is expected to reject.
+pub const EXPECTED_FAILURE_SUFFIX: &str = "-fail";
+
+/// The maximum number of differences reported for a single fixture.
+const MAX_REPORTED_DIFFS: usize = 20;
+
 #[derive(Args, Debug)]
 pub struct TestArgs {
     /// Filename of the built program to be deployed. Ex: "path/to/example-program"
@@ -14,12 +35,130 @@ pub struct TestArgs {
     /// Path to the JSON input file or dir containing JSON files for testing
     #[arg(short = 'i')]
     input_json: PathBuf,
+    /// Regenerate the `*.expected.json` golden files from the program's current outputs
+    #[arg(long)]
+    update: bool,
+    /// Apply each fixture's outputs to an in-memory ledger, in name order, and
+    /// synthesize the next fixture's `accountInfo` from it
+    #[arg(long)]
+    simulate: bool,
+    /// Ledger state to start a simulated run from; the final state is written back to it
+    #[arg(long, requires = "simulate")]
+    state: Option<PathBuf>,
+    /// Print a plain-language summary of each fixture's outputs
+    #[arg(long)]
+    explain: bool,
+    /// Print the wall time, peak memory, output size and instruction count of
+    /// each run, and the binary's size
+    #[arg(long)]
+    profile: bool,
+    #[command(flatten)]
+    limits: LimitArgs,
+    /// Re-run each fixture under varied environment variables, working
+    /// directories and clock offsets, failing it if the outputs diverge, and
+    /// scan the program's source for nondeterministic APIs
+    #[arg(long)]
+    check_determinism: bool,
+    /// Number of varied runs compared against each fixture's baseline run
+    #[arg(long, default_value_t = DEFAULT_DETERMINISM_RUNS, requires = "check_determinism")]
+    determinism_runs: usize,
+    /// Source directory scanned by --check-determinism. Inferred from the
+    /// build path when it is under a cargo `target/` directory
+    #[arg(long, requires = "check_determinism")]
+    src: Option<PathBuf>,
+}
+
+/// Execution limits a program is held to, failing runs that exceed them.
+/// Only the limits that are passed are enforced.
+#[derive(Args, Debug, Default, Clone)]
+pub struct LimitArgs {
+    /// Maximum wall time of a run, in milliseconds. Runs are killed once it passes
+    #[arg(long)]
+    max_runtime_ms: Option<u64>,
+    /// Maximum peak memory of a run, in KiB
+    #[arg(long)]
+    max_memory_kib: Option<u64>,
+    /// Maximum size of a run's outputs, in bytes
+    #[arg(long)]
+    max_output_bytes: Option<u64>,
+    /// Maximum number of instructions in a run's outputs
+    #[arg(long)]
+    max_instructions: Option<u64>,
+    /// Maximum size of the program binary, in bytes
+    #[arg(long)]
+    max_binary_bytes: Option<u64>,
+}
+
+impl LimitArgs {
+    /// The limits to enforce.
+    pub fn limits(&self) -> ExecutionLimits {
+        ExecutionLimits {
+            max_runtime: self.max_runtime_ms.map(Duration::from_millis),
+            max_memory_kib: self.max_memory_kib,
+            max_output_bytes: self.max_output_bytes,
+            max_instructions: self.max_instructions,
+            max_binary_bytes: self.max_binary_bytes,
+        }
+    }
+}
+
+/// The result of running a single fixture against a program.
+#[derive(Debug, PartialEq, Eq)]
+pub enum FixtureOutcome {
+    /// The outputs matched the golden file, or the fixture was rejected as expected.
+    Passed(Option<String>),
+    /// The golden file was (re)generated from the program's outputs.
+    Updated,
+    /// The fixture did not behave as expected, for the given reasons.
+    Failed(Vec<String>),
+}
+
+/// Pass/fail counts for a `lasr test` run.
+#[derive(Debug, Default)]
+pub struct TestSummary {
+    pub passed: usize,
+    pub updated: usize,
+    pub failed: Vec<PathBuf>,
+}
+
+impl TestSummary {
+    pub fn total(&self) -> usize {
+        self.passed + self.updated + self.failed.len()
+    }
+}
+
+impl std::fmt::Display for TestSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let status = if self.failed.is_empty() {
+            "ok"
+        } else {
+            "FAILED"
+        };
+        write!(
+            f,
+            "test result: {status}. {} passed; {} failed; {} updated",
+            self.passed,
+            self.failed.len(),
+            self.updated
+        )
+    }
 }
 
 impl TestArgs {
-    #[cfg(test)]
     pub fn new(build: PathBuf, input_json: PathBuf) -> Self {
-        Self { build, input_json }
+        Self {
+            build,
+            input_json,
+            update: false,
+            simulate: false,
+            state: None,
+            explain: false,
+            profile: false,
+            limits: LimitArgs::default(),
+            check_determinism: false,
+            determinism_runs: DEFAULT_DETERMINISM_RUNS,
+            src: None,
+        }
     }
     /// Takes a build path to a lasr program binary, and a path to some json inputs
     /// and feeds the json inputs as bytes to the binary program via stdin, returning
@@ -40,34 +179,394 @@ impl TestArgs {
         }
 
         println!("Searching for program path: {:?}", self.build);
-        let json_input_str = &std::fs::read_to_string(&self.input_json).map_err(|e| {
+        println!("Cargo project running...");
+        self.run_fixture(&self.input_json)
+    }
+
+    /// Runs every fixture found at the input path, comparing each program output
+    /// against its golden file, and prints a pass/fail summary. Returns an error
+    /// if any fixture failed.
+    pub fn lasr_test(&self) -> anyhow::Result<()> {
+        let summary = self.run_suite()?;
+        println!("\n{summary}");
+        if !summary.failed.is_empty() {
+            bail!(
+                "{} of {} fixtures failed",
+                summary.failed.len(),
+                summary.total()
+            );
+        }
+        Ok(())
+    }
+
+    /// Runs every fixture found at the input path, printing the outcome of each.
+    pub fn run_suite(&self) -> anyhow::Result<TestSummary> {
+        if !self.build.exists() {
+            bail!(
+                "{:?} does not exist, please provide a valid path and try again.",
+                self.build
+            );
+        }
+
+        let fixtures = fixtures(&self.input_json)?;
+        if fixtures.is_empty() {
+            bail!("no JSON fixtures found at {:?}", self.input_json);
+        }
+
+        println!(
+            "running {} fixtures against {:?}",
+            fixtures.len(),
+            self.build
+        );
+        let mut summary = TestSummary::default();
+        let binary_bytes = fs::metadata(&self.build)
+            .map_err(|e| anyhow::anyhow!("failed to read {:?}: {e:?}", self.build))?
+            .len();
+        if self.profile {
+            println!("binary size: {}", human_bytes(binary_bytes));
+        }
+        if let Some(violation) = self.limits.limits().check_binary(binary_bytes) {
+            println!(
+                "binary {} ... FAILED\n    {violation}",
+                self.build.display()
+            );
+            summary.failed.push(self.build.clone());
+        }
+        if self.check_determinism {
+            self.scan_for_nondeterminism()?;
+        }
+        let mut ledger = match (&self.state, self.simulate) {
+            (Some(state), _) if state.exists() => Some(Ledger::load(state)?),
+            (_, true) => Some(Ledger::new()),
+            _ => None,
+        };
+        for fixture in fixtures {
+            let outcome = self.check_fixture(&fixture, ledger.as_mut())?;
+            let name = fixture.display();
+            match outcome {
+                FixtureOutcome::Passed(note) => {
+                    summary.passed += 1;
+                    match note {
+                        Some(note) => println!("test {name} ... ok ({note})"),
+                        None => println!("test {name} ... ok"),
+                    }
+                }
+                FixtureOutcome::Updated => {
+                    summary.updated += 1;
+                    println!("test {name} ... updated {:?}", expected_path(&fixture));
+                }
+                FixtureOutcome::Failed(reasons) => {
+                    println!("test {name} ... FAILED");
+                    for reason in reasons.iter().take(MAX_REPORTED_DIFFS) {
+                        println!("    {reason}");
+                    }
+                    if reasons.len() > MAX_REPORTED_DIFFS {
+                        println!(
+                            "    ... and {} more differences",
+                            reasons.len() - MAX_REPORTED_DIFFS
+                        );
+                    }
+                    summary.failed.push(fixture);
+                }
+            }
+        }
+        if let (Some(ledger), Some(state)) = (&ledger, &self.state) {
+            ledger.save(state)?;
+        }
+
+        Ok(summary)
+    }
+
+    /// Runs a single fixture and decides whether the program behaved as expected.
+    ///
+    /// With a `ledger`, the fixture's `accountInfo` is replaced by one
+    /// synthesized from the ledger, and successful outputs are applied to it.
+    pub fn check_fixture(
+        &self,
+        fixture: &Path,
+        ledger: Option<&mut Ledger>,
+    ) -> anyhow::Result<FixtureOutcome> {
+        let raw_inputs = fs::read_to_string(fixture)
+            .map_err(|e| anyhow::anyhow!("failed to read fixture {fixture:?}: {e:?}"))?;
+        // Fixtures the program should reject may not even parse as `Inputs`,
+        // in which case they are passed through untouched.
+        let inputs = match (
+            ledger.as_deref(),
+            serde_json::from_str::<Inputs>(&raw_inputs),
+        ) {
+            (Some(ledger), Ok(inputs)) => Some(ledger.prepare_inputs(&inputs)?),
+            (None, Ok(inputs)) => Some(inputs),
+            (_, Err(_)) => None,
+        };
+        let limits = self.limits.limits();
+        let (output, profile) = match (&ledger, &inputs) {
+            (Some(_), Some(inputs)) => run_profiled(
+                &self.build,
+                &serde_json::to_string(inputs)?,
+                limits.max_runtime,
+            )?,
+            _ => run_profiled(&self.build, &raw_inputs, limits.max_runtime)?,
+        };
+        if self.profile {
+            println!("profile {}: {profile}", fixture.display());
+        }
+        let violations = limits.check_run(&profile);
+        if !violations.is_empty() {
+            return Ok(FixtureOutcome::Failed(violations));
+        }
+
+        if self.check_determinism {
+            let input = match (&ledger, &inputs) {
+                (Some(_), Some(inputs)) => serde_json::to_string(inputs)?,
+                _ => raw_inputs.clone(),
+            };
+            let divergences = check_determinism(&self.build, &input, self.determinism_runs)?;
+            if !divergences.is_empty() {
+                return Ok(FixtureOutcome::Failed(
+                    divergences.iter().map(ToString::to_string).collect(),
+                ));
+            }
+        }
+
+        if is_expected_failure(fixture) {
+            return Ok(check_rejection(&output));
+        }
+
+        if !output.status.success() {
+            return Ok(FixtureOutcome::Failed(vec![failure_reason(&output)]));
+        }
+
+        let outputs_json = String::from_utf8_lossy(&output.stdout);
+        let actual: Value = match serde_json::from_str(&outputs_json) {
+            Ok(actual) => actual,
+            Err(e) => {
+                return Ok(FixtureOutcome::Failed(vec![format!(
+                    "program output is not valid JSON: {e}"
+                )]))
+            }
+        };
+        if self.explain {
+            print!(
+                "explain {}:\n{}",
+                fixture.display(),
+                explain_outputs(&actual)
+            );
+        }
+        let Some(inputs) = inputs else {
+            bail!("failed to parse fixture {fixture:?} as Inputs");
+        };
+        match validate_outputs(&outputs_json, &inputs) {
+            Ok(issues) if !issues.is_empty() => {
+                return Ok(FixtureOutcome::Failed(
+                    issues.iter().map(ToString::to_string).collect(),
+                ))
+            }
+            Ok(_) => {}
+            Err(e) => return Ok(FixtureOutcome::Failed(vec![format!("{e:#}")])),
+        }
+        if let Some(ledger) = ledger {
+            if let Err(e) = ledger.apply_value(&actual) {
+                return Ok(FixtureOutcome::Failed(vec![format!(
+                    "ledger rejected the outputs: {e:#}"
+                )]));
+            }
+        }
+
+        let expected_path = expected_path(fixture);
+        if self.update {
+            fs::write(
+                &expected_path,
+                format!("{}\n", serde_json::to_string_pretty(&actual)?),
+            )
+            .map_err(|e| anyhow::anyhow!("failed to write golden file {expected_path:?}: {e:?}"))?;
+            return Ok(FixtureOutcome::Updated);
+        }
+        if !expected_path.exists() {
+            return Ok(FixtureOutcome::Passed(Some(
+                "no golden file, run with --update to create one".to_string(),
+            )));
+        }
+
+        let expected: Value = serde_json::from_str(&fs::read_to_string(&expected_path)?)
+            .map_err(|e| anyhow::anyhow!("failed to parse golden file {expected_path:?}: {e:?}"))?;
+        let mut diffs = Vec::new();
+        json_diff("$", &expected, &actual, &mut diffs);
+        Ok(if diffs.is_empty() {
+            FixtureOutcome::Passed(None)
+        } else {
+            FixtureOutcome::Failed(diffs)
+        })
+    }
+
+    /// Warns about every use of a nondeterministic API in the program's source.
+    fn scan_for_nondeterminism(&self) -> anyhow::Result<()> {
+        let Some(src) = self.src.clone().or_else(|| infer_source_dir(&self.build)) else {
+            println!("warning: could not find the program's source to scan, pass it with --src");
+            return Ok(());
+        };
+        let warnings = scan_source(&src)?;
+        for warning in &warnings {
+            println!("warning: {warning}");
+        }
+        if warnings.is_empty() {
+            println!("no nondeterministic APIs found in {}", src.display());
+        }
+        Ok(())
+    }
+
+    /// Feeds the fixture at `fixture` to the program binary via stdin, returning
+    /// the program's output.
+    pub fn run_fixture(&self, fixture: &Path) -> anyhow::Result<Output> {
+        let json_input_str = &std::fs::read_to_string(fixture).map_err(|e| {
             anyhow::anyhow!(
                 "failed to read json inputs to string from path {:?}: {e:?}",
-                self.input_json
+                fixture
             )
         })?;
+        self.run_input(json_input_str)
+    }
 
-        println!("Inputs discovered: {json_input_str:?}");
-        let mut handle = std::process::Command::new(&self.build)
-            .stdin(Stdio::piped())
-            .stdout(Stdio::piped())
-            .spawn()
-            .map_err(|e| {
-                anyhow::anyhow!("failed to spawn child process for {:?}: {e:?}", self.build)
-            })?;
-        if let Some(mut stdin) = handle.stdin.take() {
-            // Write the json_input to the child's stdin
-            stdin
-                .write_all(json_input_str.as_bytes())
-                .map_err(|e| anyhow::anyhow!("failed to write json inputs to stdin: {e:?}"))?;
-        } else {
-            bail!("failed to acquire stdin for child process {:?}", self.build)
+    /// Feeds `json_input_str` to the program binary via stdin, returning the
+    /// program's output.
+    pub fn run_input(&self, json_input_str: &str) -> anyhow::Result<Output> {
+        run_program(&self.build, json_input_str)
+    }
+}
+
+/// Feeds `json_input_str` to the program binary at `build` via stdin,
+/// returning the program's output.
+pub fn run_program(build: &Path, json_input_str: &str) -> anyhow::Result<Output> {
+    run_profiled(build, json_input_str, None).map(|(output, _)| output)
+}
+
+/// Collects the JSON fixtures at `path`: the file itself, or every `*.json`
+/// file in the directory (excluding golden files), in name order.
+pub fn fixtures(path: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    if !path.exists() {
+        bail!("{path:?} does not exist, please provide a valid path and try again.");
+    }
+    if path.is_file() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+
+    let mut fixtures = fs::read_dir(path)
+        .map_err(|e| anyhow::anyhow!("failed to read fixture directory {path:?}: {e:?}"))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.is_file()
+                && path.extension().is_some_and(|ext| ext == "json")
+                && !path.to_string_lossy().ends_with(EXPECTED_SUFFIX)
+        })
+        .collect::<Vec<_>>();
+    fixtures.sort();
+    Ok(fixtures)
+}
+
+/// The path of the golden file holding the expected outputs for `fixture`.
+pub fn expected_path(fixture: &Path) -> PathBuf {
+    let stem = fixture
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    fixture.with_file_name(format!("{stem}{EXPECTED_SUFFIX}"))
+}
+
+/// Whether `fixture` is named `*-fail.json`, meaning the program should reject it.
+pub fn is_expected_failure(fixture: &Path) -> bool {
+    fixture
+        .file_stem()
+        .is_some_and(|stem| stem.to_string_lossy().ends_with(EXPECTED_FAILURE_SUFFIX))
+}
+
+/// Judges the run of a fixture the program is expected to reject. Like
+/// `fuzz::classify`, only a failure reported as a `ProgramError` on stdout
+/// counts as a rejection, and the process must also exit with that error's
+/// code; crashes and unclassified exits fail the fixture.
+pub fn check_rejection(output: &Output) -> FixtureOutcome {
+    if output.status.success() {
+        return FixtureOutcome::Failed(vec![
+            "expected the program to reject this fixture, but it succeeded".to_string(),
+        ]);
+    }
+    match ProgramError::from_json(&String::from_utf8_lossy(&output.stdout)) {
+        Some(program_error)
+            if output.status.code().and_then(ProgramErrorKind::from_exit_code)
+                != Some(program_error.kind) =>
+        {
+            FixtureOutcome::Failed(vec![format!(
+                "program reported {program_error}, but exited with {}",
+                output
+                    .status
+                    .code()
+                    .map_or("no code".to_string(), |code| format!("code {code}"))
+            )])
         }
+        Some(program_error) => {
+            FixtureOutcome::Passed(Some(format!("rejected as expected: {program_error}")))
+        }
+        None => FixtureOutcome::Failed(vec![format!(
+            "expected the program to reject this fixture with a ProgramError, but it failed without one: {}",
+            failure_reason(output)
+        )]),
+    }
+}
 
-        println!("Cargo project running...");
-        handle
-            .wait_with_output()
-            .map_err(|e| anyhow::anyhow!("failed to run program at path: {:?}: {e:?}", self.build))
+/// Describes why a program run failed, preferring the program's `ProgramError`.
+pub fn failure_reason(output: &Output) -> String {
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    if let Some(program_error) = ProgramError::from_json(&stdout) {
+        return format!("program rejected the call: {program_error}");
+    }
+    let mut stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+    if stderr.is_empty() {
+        stderr = String::from("process did not specify stderr");
+    }
+    match output.status.code() {
+        Some(code) => format!(
+            "encountered an error while attempting to retreive program outputs: {stderr} code: {code}"
+        ),
+        None => format!(
+            "encountered an error while attempting to retreive program outputs: {stderr}"
+        ),
+    }
+}
+
+/// Structurally compares two JSON values, pushing a line describing each
+/// difference (by JSON path) onto `diffs`.
+pub fn json_diff(path: &str, expected: &Value, actual: &Value, diffs: &mut Vec<String>) {
+    match (expected, actual) {
+        (Value::Object(expected), Value::Object(actual)) => {
+            for (key, expected_value) in expected {
+                let key_path = format!("{path}.{key}");
+                match actual.get(key) {
+                    Some(actual_value) => json_diff(&key_path, expected_value, actual_value, diffs),
+                    None => diffs.push(format!("{key_path}: missing, expected {expected_value}")),
+                }
+            }
+            for (key, actual_value) in actual {
+                if !expected.contains_key(key) {
+                    diffs.push(format!("{path}.{key}: unexpected {actual_value}"));
+                }
+            }
+        }
+        (Value::Array(expected), Value::Array(actual)) => {
+            for (index, expected_value) in expected.iter().enumerate() {
+                let index_path = format!("{path}[{index}]");
+                match actual.get(index) {
+                    Some(actual_value) => {
+                        json_diff(&index_path, expected_value, actual_value, diffs)
+                    }
+                    None => diffs.push(format!("{index_path}: missing, expected {expected_value}")),
+                }
+            }
+            for (index, actual_value) in actual.iter().enumerate().skip(expected.len()) {
+                diffs.push(format!("{path}[{index}]: unexpected {actual_value}"));
+            }
+        }
+        (expected, actual) if expected != actual => {
+            diffs.push(format!("{path}: expected {expected}, got {actual}"));
+        }
+        _ => {}
     }
 }
 
@@ -86,6 +585,75 @@ mod test_args_tests {
         assert!(result.is_ok());
         let output = result.unwrap();
         assert_eq!(output.status.code(), Some(0));
-        assert_eq!(String::from_utf8_lossy(&output.stdout), String::from("{\n  \"computeInputs\": {\n    \"version\": 1,\n    \"accountInfo\": {\n      \"accountType\": {\n        \"program\": \"0x57234c52617e7ca8edc5577ebe3eb38d53a77607\"\n      },\n      \"programNamespace\": null,\n      \"ownerAddress\": \"0x482830d7655fb8465a43844fc1530a7713781b49\",\n      \"programs\": {},\n      \"nonce\": \"0x000000000000000000000000000000000000000000000000000000000000001c\",\n      \"programAccountData\": {},\n      \"programAccountMetadata\": {\n        \"content_id\": \"bafyreidhfvw4jiqom72332brsln3micsa4b7grur4rixkwvyrh6u4i3ecy\",\n        \"initializedSupply\": \"1000000000000000000000000\",\n        \"name\": \"Anotha One\",\n        \"symbol\": \"LOVE\",\n        \"to\": \"0x57234c52617e7ca8edc5577ebe3eb38d53a77607\",\n        \"totalSupply\": \"1000000000000000000000000\"\n      },\n      \"programAccountLinkedPrograms\": []\n    },\n    \"transaction\": {\n      \"transactionType\": {\n        \"call\": \"0x0000000000000000000000000000000000000000000000000000000000000001\"\n      },\n      \"from\": \"0x100444c7d04a842d19bc3ee63cb7b96682ff3f43\",\n      \"to\": \"0x100444c7d04a842d19bc3ee63cb7b96682ff3f43\",\n      \"programId\": \"0x100444c7d04a842d19bc3ee63cb7b96682ff3f43\",\n      \"op\": \"create\",\n      \"transactionInputs\": \"{\\\"name\\\":\\\"HelloToken\\\",\\\"symbol\\\":\\\"HLLO\\\",\\\"totalSupply\\\":\\\"1000\\\",\\\"initializedSupply\\\":\\\"1000\\\",\\\"imgUrl\\\":\\\"https://pbs.twimg.com/profile_images/1765199894539583488/RUiZn7jT_400x400.jpg\\\",\\\"paymentProgramAddress\\\":\\\"0x0000000000000000000000000000000000000000\\\",\\\"price\\\":\\\"1\\\",\\\"collection\\\":\\\"test\\\"}\",\n      \"value\": \"0x0000000000000000000000000000000000000000000000000000000000000001\",\n      \"nonce\": \"0x0000000000000000000000000000000000000000000000000000000000000001\",\n      \"v\": 1,\n      \"r\": \"0x1234567890abcdef1234567890abcdef1234567890abcdef1234567890abcdef\",\n      \"s\": \"0xfedcba0987654321fedcba0987654321fedcba0987654321fedcba0987654321\"\n    },\n    \"op\": \"create\",\n    \"contractInputs\": \"{}\"\n  },\n  \"instructions\": [\n    {\n      \"create\": {\n        \"programNamespace\": \"this\",\n        \"programId\": \"this\",\n        \"programOwner\": \"0x100444c7d04a842d19bc3ee63cb7b96682ff3f43\",\n        \"totalSupply\": \"0x0000000000000000000000000000000000000000000000000000000000001000\",\n        \"initializedSupply\": \"0x0000000000000000000000000000000000000000000000000000000000001000\",\n        \"distribution\": []\n      }\n    },\n    {\n      \"update\": {\n        \"updates\": [\n          {\n            \"programUpdate\": {\n              \"account\": \"this\",\n              \"updates\": [\n                {\n                  \"field\": \"metadata\",\n                  \"value\": {\n                    \"metadata\": {\n                      \"insert\": [\n                        \"metadata\",\n                        \"[\\\"HLLO\\\",\\\"HelloToken\\\",\\\"1000\\\"]\"\n                      ]\n                    }\n                  }\n                }\n              ]\n            }\n          }\n        ]\n      }\n    }\n  ]\n}\n"));
+        assert_eq!(String::from_utf8_lossy(&output.stdout), String::from("{\n  \"computeInputs\": {\n    \"version\": 1,\n    \"accountInfo\": {\n      \"accountType\": {\n        \"program\": \"0x57234c52617e7ca8edc5577ebe3eb38d53a77607\"\n      },\n      \"programNamespace\": null,\n      \"ownerAddress\": \"0x482830d7655fb8465a43844fc1530a7713781b49\",\n      \"programs\": {},\n      \"nonce\": \"0x000000000000000000000000000000000000000000000000000000000000001c\",\n      \"programAccountData\": {},\n      \"programAccountMetadata\": {\n        \"content_id\": \"bafyreidhfvw4jiqom72332brsln3micsa4b7grur4rixkwvyrh6u4i3ecy\",\n        \"initializedSupply\": \"1000000000000000000000000\",\n        \"name\": \"Anotha One\",\n        \"symbol\": \"LOVE\",\n        \"to\": \"0x57234c52617e7ca8edc5577ebe3eb38d53a77607\",\n        \"totalSupply\": \"1000000000000000000000000\"\n      },\n      \"programAccountLinkedPrograms\": []\n    },\n    \"transaction\": {\n      \"transactionType\": {\n        \"call\": \"0x0000000000000000000000000000000000000000000000000000000000000001\"\n      },\n      \"from\": \"0x100444c7d04a842d19bc3ee63cb7b96682ff3f43\",\n      \"to\": \"0x100444c7d04a842d19bc3ee63cb7b96682ff3f43\",\n      \"programId\": \"0x100444c7d04a842d19bc3ee63cb7b96682ff3f43\",\n      \"op\": \"create\",\n      \"transactionInputs\": \"{\\\"name\\\":\\\"HelloToken\\\",\\\"symbol\\\":\\\"HLLO\\\",\\\"totalSupply\\\":\\\"1000\\\",\\\"initializedSupply\\\":\\\"1000\\\",\\\"imgUrl\\\":\\\"https://pbs.twimg.com/profile_images/1765199894539583488/RUiZn7jT_400x400.jpg\\\",\\\"paymentProgramAddress\\\":\\\"0x0000000000000000000000000000000000000000\\\",\\\"price\\\":\\\"1\\\",\\\"collection\\\":\\\"test\\\"}\",\n      \"value\": \"0x0000000000000000000000000000000000000000000000000000000000000001\",\n      \"nonce\": \"0x0000000000000000000000000000000000000000000000000000000000000001\",\n      \"v\": 1,\n      \"r\": \"0x1234567890abcdef1234567890abcdef1234567890abcdef1234567890abcdef\",\n      \"s\": \"0xfedcba0987654321fedcba0987654321fedcba0987654321fedcba0987654321\"\n    },\n    \"op\": \"create\",\n    \"contractInputs\": \"{}\"\n  },\n  \"instructions\": [\n    {\n      \"create\": {\n        \"programNamespace\": \"this\",\n        \"programId\": \"this\",\n        \"programOwner\": \"0x100444c7d04a842d19bc3ee63cb7b96682ff3f43\",\n        \"totalSupply\": \"0x0000000000000000000000000000000000000000000000000000000000001000\",\n        \"initializedSupply\": \"0x0000000000000000000000000000000000000000000000000000000000001000\",\n        \"distribution\": []\n      }\n    },\n    {\n      \"update\": {\n        \"updates\": [\n          {\n            \"programUpdate\": {\n              \"account\": \"this\",\n              \"updates\": [\n                {\n                  \"field\": \"metadata\",\n                  \"value\": {\n                    \"metadata\": {\n                      \"insert\": [\n                        \"name\",\n                        \"HelloToken\"\n                      ]\n                    }\n                  }\n                },\n                {\n                  \"field\": \"metadata\",\n                  \"value\": {\n                    \"metadata\": {\n                      \"insert\": [\n                        \"symbol\",\n                        \"HLLO\"\n                      ]\n                    }\n                  }\n                },\n                {\n                  \"field\": \"metadata\",\n                  \"value\": {\n                    \"metadata\": {\n                      \"insert\": [\n                        \"totalSupply\",\n                        \"0x1000\"\n                      ]\n                    }\n                  }\n                },\n                {\n                  \"field\": \"metadata\",\n                  \"value\": {\n                    \"metadata\": {\n                      \"insert\": [\n                        \"initializedSupply\",\n                        \"0x1000\"\n                      ]\n                    }\n                  }\n                },\n                {\n                  \"field\": \"metadata\",\n                  \"value\": {\n                    \"metadata\": {\n                      \"insert\": [\n                        \"imgUrl\",\n                        \"https://pbs.twimg.com/profile_images/1765199894539583488/RUiZn7jT_400x400.jpg\"\n                      ]\n                    }\n                  }\n                },\n                {\n                  \"field\": \"metadata\",\n                  \"value\": {\n                    \"metadata\": {\n                      \"insert\": [\n                        \"collection\",\n                        \"test\"\n                      ]\n                    }\n                  }\n                },\n                {\n                  \"field\": \"metadata\",\n                  \"value\": {\n                    \"metadata\": {\n                      \"insert\": [\n                        \"paymentProgramAddress\",\n                        \"0x0000000000000000000000000000000000000000\"\n                      ]\n                    }\n                  }\n                },\n                {\n                  \"field\": \"metadata\",\n                  \"value\": {\n                    \"metadata\": {\n                      \"insert\": [\n                        \"price\",\n                        \"1\"\n                      ]\n                    }\n                  }\n                }\n              ]\n            }\n          }\n        ]\n      }\n    }\n  ]\n}\n"));
     }
 }
+
+#[test]
+fn test_json_diff() {
+    let expected = serde_json::json!({"op": "create", "instructions": [{"amount": "0x1"}]});
+    let actual = serde_json::json!({"op": "update", "instructions": [{"amount": "0x1"}, {}]});
+
+    let mut diffs = Vec::new();
+    json_diff("$", &expected, &actual, &mut diffs);
+    assert_eq!(
+        diffs,
+        vec![
+            "$.instructions[1]: unexpected {}".to_string(),
+            "$.op: expected \"create\", got \"update\"".to_string(),
+        ]
+    );
+}
+
+#[test]
+fn test_fixture_naming() {
+    let fixture = Path::new("inputs/fungible-mint-fail.json");
+    assert!(is_expected_failure(fixture));
+    assert_eq!(
+        expected_path(fixture),
+        PathBuf::from("inputs/fungible-mint-fail.expected.json")
+    );
+    assert!(!is_expected_failure(Path::new("inputs/fungible-mint.json")));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_check_rejection() {
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::ExitStatus;
+
+    let output = |code: i32, stdout: &str, stderr: &str| Output {
+        status: ExitStatus::from_raw(code << 8),
+        stdout: stdout.as_bytes().to_vec(),
+        stderr: stderr.as_bytes().to_vec(),
+    };
+    let rejection = ProgramError::rejected("supply exhausted");
+
+    assert!(matches!(
+        check_rejection(&output(rejection.code, &rejection.to_json(), "")),
+        FixtureOutcome::Passed(_)
+    ));
+    // The envelope must agree with the exit code, e.g. not be a stale error
+    // printed before `main` failed with anyhow's code 1.
+    assert!(matches!(
+        check_rejection(&output(1, &rejection.to_json(), "")),
+        FixtureOutcome::Failed(_)
+    ));
+    assert!(matches!(
+        check_rejection(&output(0, "{}", "")),
+        FixtureOutcome::Failed(_)
+    ));
+    // A panic or any other exit without the error envelope is not a rejection.
+    assert!(matches!(
+        check_rejection(&output(
+            101,
+            "",
+            "thread 'main' panicked at src/main.rs:1:1"
+        )),
+        FixtureOutcome::Failed(_)
+    ));
+    assert!(matches!(
+        check_rejection(&output(1, "not json", "")),
+        FixtureOutcome::Failed(_)
+    ));
+}
@@ -0,0 +1,114 @@
+use clap::{Args, Subcommand};
+use serde_json::Value;
+use std::{
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use crate::lasrctl::{
+    signing::{sign_transaction, transaction_hash, verify_transaction},
+    wallet::{read_keypair_file, WalletDir},
+};
+
+#[derive(Args, Debug)]
+pub struct TxArgs {
+    #[command(subcommand)]
+    command: TxCommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum TxCommand {
+    /// Print the hash a transaction is signed over, as computed by a node
+    Hash(TxFileArgs),
+    /// Sign a transaction with a wallet keypair, setting its `from`, `r`, `s` and `v`
+    Sign(TxSignArgs),
+    /// Recover a transaction's signer and check that it matches `from`
+    Verify(TxFileArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct TxFileArgs {
+    /// Path to a JSON transaction, or `Inputs` fixture holding one. Read from
+    /// stdin if omitted or `-`
+    path: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+pub struct TxSignArgs {
+    #[command(flatten)]
+    file: TxFileArgs,
+    /// Name of the wallet keypair to sign with. Defaults to the wallet's default keypair
+    #[arg(long)]
+    wallet: Option<String>,
+    /// Path to a keypair file, overriding `--wallet`. Encrypted keypairs are
+    /// unlocked with LASR_KEYSTORE_PASSPHRASE or a prompt
+    #[arg(long)]
+    keypair_path: Option<PathBuf>,
+    /// Overwrite the input file with the signed transaction instead of printing it
+    #[arg(long, requires = "path")]
+    in_place: bool,
+}
+
+impl TxArgs {
+    pub fn lasr_tx(&self) -> anyhow::Result<()> {
+        match &self.command {
+            TxCommand::Hash(args) => {
+                let document = read_document(args.path.as_deref())?;
+                let hash = transaction_hash(transaction(&document))?;
+                println!("0x{}", hex::encode(hash));
+            }
+            TxCommand::Sign(args) => {
+                let mut document = read_document(args.file.path.as_deref())?;
+                let secret_key = read_keypair_file(
+                    &WalletDir::default()
+                        .resolve(args.wallet.as_deref(), args.keypair_path.as_deref()),
+                )?
+                .secret_key()?;
+                if document.get("transaction").is_some() {
+                    sign_transaction(&mut document["transaction"], &secret_key)?;
+                } else {
+                    sign_transaction(&mut document, &secret_key)?;
+                }
+
+                let signed = serde_json::to_string_pretty(&document)?;
+                match (&args.file.path, args.in_place) {
+                    (Some(path), true) => {
+                        fs::write(path, format!("{signed}\n")).map_err(|e| {
+                            anyhow::anyhow!("failed to write signed transaction to {path:?}: {e:?}")
+                        })?;
+                        println!("signed {path:?}");
+                    }
+                    _ => println!("{signed}"),
+                }
+            }
+            TxCommand::Verify(args) => {
+                let document = read_document(args.path.as_deref())?;
+                let signer = verify_transaction(transaction(&document))?;
+                println!("signature ok: signed by {signer}");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The transaction of an `Inputs` fixture, or the document itself.
+fn transaction(document: &Value) -> &Value {
+    document.get("transaction").unwrap_or(document)
+}
+
+fn read_document(path: Option<&Path>) -> anyhow::Result<Value> {
+    let contents = match path {
+        Some(path) if path.as_os_str() != "-" => fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("failed to read transaction {path:?}: {e:?}"))?,
+        _ => {
+            let mut contents = String::new();
+            std::io::stdin()
+                .read_to_string(&mut contents)
+                .map_err(|e| anyhow::anyhow!("failed to read transaction from stdin: {e:?}"))?;
+            contents
+        }
+    };
+    serde_json::from_str(&contents)
+        .map_err(|e| anyhow::anyhow!("transaction is not valid JSON: {e:?}"))
+}
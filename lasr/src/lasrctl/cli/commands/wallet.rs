@@ -0,0 +1,235 @@
+use std::io::{self, BufRead};
+
+use anyhow::bail;
+use clap::{Args, Subcommand};
+
+use crate::lasrctl::{
+    keystore::new_passphrase,
+    wallet::{KeypairFile, StoredKeypair, WalletDir},
+};
+
+#[derive(Args, Debug)]
+pub struct WalletArgs {
+    #[command(subcommand)]
+    command: WalletCommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum WalletCommand {
+    /// Generate a new named keypair from a fresh mnemonic
+    New {
+        /// Name of the keypair
+        name: String,
+        /// Make the new keypair the default
+        #[arg(long)]
+        default: bool,
+        /// Store the keypair unencrypted
+        #[arg(long)]
+        plaintext: bool,
+    },
+    /// Import a keypair from a secret key or mnemonic read from stdin
+    Import {
+        /// Name of the keypair
+        name: String,
+        /// Read a mnemonic phrase instead of a hex encoded secret key
+        #[arg(long)]
+        mnemonic: bool,
+        /// Make the imported keypair the default
+        #[arg(long)]
+        default: bool,
+        /// Store the keypair unencrypted
+        #[arg(long)]
+        plaintext: bool,
+    },
+    /// List the keypairs in the wallet
+    List,
+    /// Show the address and public key of a keypair
+    Inspect {
+        /// Name of the keypair. Defaults to the default keypair
+        name: Option<String>,
+    },
+    /// Print a keypair as JSON
+    Export {
+        /// Name of the keypair. Defaults to the default keypair
+        name: Option<String>,
+        /// Include the secret key and mnemonic
+        #[arg(long)]
+        secret: bool,
+    },
+    /// Set the keypair used by default for deploy, call, send and inputs
+    Default {
+        /// Name of the keypair
+        name: String,
+    },
+    /// Replace a keypair with a freshly generated one, backing up the old one
+    Rotate {
+        /// Name of the keypair. Defaults to the default keypair
+        name: Option<String>,
+    },
+    /// Encrypt plaintext keypairs into passphrase protected keystores
+    Encrypt {
+        /// Name of the keypair. Defaults to the default keypair
+        name: Option<String>,
+        /// Encrypt every plaintext keypair in the wallet
+        #[arg(long, conflicts_with = "name")]
+        all: bool,
+    },
+}
+
+impl WalletArgs {
+    pub fn lasr_wallet(&self) -> anyhow::Result<()> {
+        let wallet = WalletDir::default();
+        let name_or_default =
+            |name: &Option<String>| name.clone().unwrap_or_else(|| wallet.default_name());
+
+        match &self.command {
+            WalletCommand::New {
+                name,
+                default,
+                plaintext,
+            } => {
+                let keypair = KeypairFile::generate()?;
+                let passphrase = passphrase_unless(*plaintext)?;
+                let keypair_path = wallet.save(name, &keypair, passphrase.as_deref())?;
+                println!("Generated keypair '{name}' at {keypair_path:?}");
+                println!("address: {}", keypair.address);
+                if let Some(mnemonic) = &keypair.mnemonic {
+                    println!("mnemonic (write this down, it can restore the keypair): {mnemonic}");
+                }
+                if *default {
+                    wallet.set_default(name)?;
+                }
+            }
+            WalletCommand::Import {
+                name,
+                mnemonic,
+                default,
+                plaintext,
+            } => {
+                let secret = read_secret_line(if *mnemonic { "mnemonic" } else { "secret key" })?;
+                let keypair = if *mnemonic {
+                    KeypairFile::from_mnemonic(&secret)?
+                } else {
+                    KeypairFile::from_secret_key_hex(&secret)?
+                };
+                let passphrase = passphrase_unless(*plaintext)?;
+                let keypair_path = wallet.save(name, &keypair, passphrase.as_deref())?;
+                println!(
+                    "Imported keypair '{name}' ({}) to {keypair_path:?}",
+                    keypair.address
+                );
+                if *default {
+                    wallet.set_default(name)?;
+                }
+            }
+            WalletCommand::List => {
+                let default_name = wallet.default_name();
+                let names = wallet.list()?;
+                if names.is_empty() {
+                    println!("No keypairs found in {:?}", wallet.path());
+                }
+                for name in names {
+                    let marker = if name == default_name { "*" } else { " " };
+                    match wallet.load_stored(&name) {
+                        Ok(stored) => println!(
+                            "{marker} {name:<20} {} {}",
+                            stored.address(),
+                            if stored.is_encrypted() {
+                                "(encrypted)"
+                            } else {
+                                "(plaintext)"
+                            }
+                        ),
+                        Err(e) => println!("{marker} {name:<20} unreadable: {e:#}"),
+                    }
+                }
+            }
+            WalletCommand::Inspect { name } => {
+                let name = name_or_default(name);
+                let stored = wallet.load_stored(&name)?;
+                println!("name:       {name}");
+                println!("path:       {:?}", wallet.keypair_path(&name));
+                println!("address:    {}", stored.address());
+                match stored {
+                    StoredKeypair::Plaintext(keypair) => {
+                        println!("public key: {}", keypair.public_key);
+                        println!("storage:    plaintext, run `lasr wallet encrypt` to protect it");
+                    }
+                    StoredKeypair::Encrypted(_) => println!("storage:    encrypted keystore"),
+                }
+            }
+            WalletCommand::Export { name, secret } => {
+                let name = name_or_default(name);
+                let keypair = if *secret {
+                    wallet.load(&name)?
+                } else {
+                    match wallet.load_stored(&name)? {
+                        StoredKeypair::Plaintext(keypair) => keypair,
+                        StoredKeypair::Encrypted(keystore) => {
+                            println!("{}", serde_json::to_string_pretty(&keystore)?);
+                            return Ok(());
+                        }
+                    }
+                };
+                let mut json = serde_json::to_value(&keypair)?;
+                if let (false, Some(obj)) = (*secret, json.as_object_mut()) {
+                    obj.remove("secret_key");
+                    obj.remove("mnemonic");
+                }
+                println!("{}", serde_json::to_string_pretty(&json)?);
+            }
+            WalletCommand::Default { name } => {
+                wallet.set_default(name)?;
+                println!("Default keypair set to '{name}'");
+            }
+            WalletCommand::Rotate { name } => {
+                let name = name_or_default(name);
+                let passphrase = passphrase_unless(!wallet.load_stored(&name)?.is_encrypted())?;
+                let (backup_path, keypair) = wallet.rotate(&name, passphrase.as_deref())?;
+                println!(
+                    "Rotated keypair '{name}', the previous keypair was moved to {backup_path:?}"
+                );
+                println!("new address: {}", keypair.address);
+            }
+            WalletCommand::Encrypt { name, all } => {
+                let names = if *all {
+                    wallet.list()?
+                } else {
+                    vec![name_or_default(name)]
+                };
+                let passphrase = new_passphrase()?;
+                for name in names {
+                    if !wallet.encrypt(&name, &passphrase)? {
+                        println!("'{name}' is already encrypted");
+                        continue;
+                    }
+                    println!("Encrypted '{name}' at {:?}", wallet.keypair_path(&name));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Reads a single line from stdin, so secrets never land in shell history.
+fn read_secret_line(what: &str) -> anyhow::Result<String> {
+    eprintln!("Enter the {what} to import:");
+    let mut line = String::new();
+    io::stdin()
+        .lock()
+        .read_line(&mut line)
+        .map_err(|e| anyhow::anyhow!("failed to read {what} from stdin: {e:?}"))?;
+    let line = line.trim().to_string();
+    if line.is_empty() {
+        bail!("no {what} was provided");
+    }
+    Ok(line)
+}
+
+/// Asks for a new keystore passphrase, unless the keypair is stored in plaintext.
+fn passphrase_unless(plaintext: bool) -> anyhow::Result<Option<String>> {
+    if plaintext {
+        return Ok(None);
+    }
+    new_passphrase().map(Some)
+}
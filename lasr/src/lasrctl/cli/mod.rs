@@ -1,7 +1,21 @@
 pub mod commands;
 use self::commands::{
-    build::BuildArgs, call::CallArgs, deploy::DeployArgs, init::InitArgs, send::SendArgs,
+    build::BuildArgs,
+    call::CallArgs,
+    deploy::DeployArgs,
+    dev::DevArgs,
+    explain::ExplainArgs,
+    fuzz::FuzzArgs,
+    init::InitArgs,
+    inputs::InputsArgs,
+    node::NodeArgs,
+    query::{AccountArgs, ProgramArgs},
+    scenario::ScenarioArgs,
+    send::SendArgs,
+    templates::TemplatesArgs,
     test::TestArgs,
+    tx::TxArgs,
+    wallet::WalletArgs,
 };
 use clap::{Parser, Subcommand};
 
@@ -21,14 +35,36 @@ impl LasrCtl {
 pub enum LasrCommand {
     /// Initialize a project with an example program
     Init(InitArgs),
+    /// List the templates available to `lasr init`
+    Templates(TemplatesArgs),
     /// Build the project with the specified contract
     Build(BuildArgs),
     /// Run the test suite for the project
     Test(TestArgs),
+    /// Run mutated fixtures against the program to find panics and invariant violations
+    Fuzz(FuzzArgs),
+    /// Summarize a program's outputs in plain language
+    Explain(ExplainArgs),
+    /// Rebuild the program and rerun its fixtures and scenarios on every change
+    Dev(DevArgs),
+    /// Generate `Inputs` fixtures for program calls
+    Inputs(InputsArgs),
+    /// Run multi-step scenarios against a program with simulated state
+    Scenario(ScenarioArgs),
+    /// Show an account's nonce and token holdings
+    Account(AccountArgs),
+    /// Show a program account's owner, metadata, data and linked programs
+    Program(ProgramArgs),
+    /// Run a local LASR node serving the JSON-RPC methods offline
+    Node(NodeArgs),
     /// Deploy a program to LASR
     Deploy(DeployArgs),
     /// Call a program method with the specified arguments
     Call(CallArgs),
     /// Send a specified amount of tokens to a recipient
     Send(SendArgs),
+    /// Hash, sign and verify transactions
+    Tx(TxArgs),
+    /// Manage the keypairs in the project's wallet
+    Wallet(WalletArgs),
 }
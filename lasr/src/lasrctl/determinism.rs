@@ -0,0 +1,434 @@
+//! Determinism checks for LASR programs.
+//!
+//! Every validator re-executes a call and must arrive at identical outputs, so
+//! a program may not depend on anything that differs between machines: the
+//! clock, the environment, the filesystem, the network or a source of
+//! randomness. Fixtures are re-run under varied environment variables, working
+//! directories and clock offsets and their outputs compared against a baseline
+//! run, and a program's source is scanned for uses of those APIs.
+use std::{
+    env, fmt, fs,
+    path::{Path, PathBuf},
+    process::{Command, Output},
+    sync::Once,
+};
+
+use serde_json::Value;
+
+use crate::lasrctl::{
+    cli::commands::test::json_diff, profile::run_command_profiled, watch::Snapshot,
+};
+
+/// The default number of varied runs compared against the baseline run.
+pub const DEFAULT_DETERMINISM_RUNS: usize = 3;
+
+/// Environment variable naming a libfaketime library, used to offset the clock
+/// of a run when it isn't installed in a well-known location.
+pub const LIBFAKETIME_ENV: &str = "LASR_LIBFAKETIME";
+
+/// Well-known install locations of libfaketime.
+const LIBFAKETIME_PATHS: &[&str] = &[
+    "/usr/lib/x86_64-linux-gnu/faketime/libfaketime.so.1",
+    "/usr/lib/aarch64-linux-gnu/faketime/libfaketime.so.1",
+    "/usr/lib/faketime/libfaketime.so.1",
+    "/usr/local/lib/faketime/libfaketime.so.1",
+    "/opt/homebrew/lib/faketime/libfaketime.1.dylib",
+    "/usr/local/lib/faketime/libfaketime.1.dylib",
+];
+
+/// Clock offsets applied to varied runs, in libfaketime's format.
+const CLOCK_OFFSETS: &[&str] = &["+365d", "-30d", "+7h"];
+
+/// Time zones and locales applied to varied runs.
+const LOCALES: &[(&str, &str)] = &[
+    ("Pacific/Kiritimati", "tr_TR.UTF-8"),
+    ("America/St_Johns", "ja_JP.UTF-8"),
+    ("Asia/Kathmandu", "de_DE.UTF-8"),
+];
+
+/// The conditions a single run executes under.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Variation {
+    pub label: String,
+    /// Environment variables set for the run, on top of the inherited ones.
+    pub env: Vec<(String, String)>,
+    /// The run's working directory, if not the current one.
+    pub current_dir: Option<PathBuf>,
+    /// The offset applied to the run's clock, e.g. `+365d`.
+    pub clock_offset: Option<String>,
+}
+
+impl Variation {
+    /// The unvaried run that the others are compared against.
+    pub fn baseline() -> Self {
+        Self {
+            label: "baseline".to_string(),
+            ..Self::default()
+        }
+    }
+
+    /// The `run`th varied run, cycling through varied environment variables,
+    /// working directories and clock offsets.
+    pub fn nth(run: usize) -> Self {
+        let round = run / 3;
+        match run % 3 {
+            0 => {
+                let (tz, locale) = LOCALES[round % LOCALES.len()];
+                Self {
+                    label: format!("environment TZ={tz} LANG={locale}"),
+                    env: vec![
+                        ("TZ".to_string(), tz.to_string()),
+                        ("LANG".to_string(), locale.to_string()),
+                        ("LC_ALL".to_string(), locale.to_string()),
+                        ("LASR_DETERMINISM_RUN".to_string(), run.to_string()),
+                    ],
+                    ..Self::default()
+                }
+            }
+            1 => {
+                let dir =
+                    env::temp_dir().join(format!("lasr-determinism-{}-{run}", std::process::id()));
+                Self {
+                    label: format!("working directory {}", dir.display()),
+                    env: vec![("HOME".to_string(), dir.display().to_string())],
+                    current_dir: Some(dir),
+                    ..Self::default()
+                }
+            }
+            _ => {
+                let offset = CLOCK_OFFSETS[round % CLOCK_OFFSETS.len()];
+                Self {
+                    label: format!("clock offset {offset}"),
+                    clock_offset: Some(offset.to_string()),
+                    ..Self::default()
+                }
+            }
+        }
+    }
+
+    /// A command running `build` under this variation. Clock offsets need
+    /// libfaketime; without it the run only differs by the time elapsed.
+    fn command(&self, build: &Path, libfaketime: Option<&Path>) -> anyhow::Result<Command> {
+        let mut command = Command::new(build);
+        command.envs(self.env.iter().map(|(key, value)| (key, value)));
+        if let Some(dir) = &self.current_dir {
+            fs::create_dir_all(dir).map_err(|e| {
+                anyhow::anyhow!("failed to create working directory {dir:?}: {e:?}")
+            })?;
+            command.current_dir(dir);
+        }
+        if let (Some(offset), Some(libfaketime)) = (&self.clock_offset, libfaketime) {
+            command.env("FAKETIME", offset);
+            if cfg!(target_os = "macos") {
+                command
+                    .env("DYLD_INSERT_LIBRARIES", libfaketime)
+                    .env("DYLD_FORCE_FLAT_NAMESPACE", "1");
+            } else {
+                command.env("LD_PRELOAD", libfaketime);
+            }
+        }
+        Ok(command)
+    }
+}
+
+/// Locates libfaketime, preferring `LASR_LIBFAKETIME`.
+pub fn find_libfaketime() -> Option<PathBuf> {
+    env::var_os(LIBFAKETIME_ENV)
+        .map(PathBuf::from)
+        .into_iter()
+        .chain(LIBFAKETIME_PATHS.iter().map(PathBuf::from))
+        .find(|path| path.is_file())
+}
+
+/// A varied run whose output differed from the baseline run's.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+    pub variation: String,
+    pub differences: Vec<String>,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "nondeterministic under {}: {}",
+            self.variation,
+            self.differences.join("; ")
+        )
+    }
+}
+
+/// Runs the program at `build` on `input` once as a baseline, then `runs`
+/// more times under varied conditions, returning every run that diverged.
+pub fn check_determinism(
+    build: &Path,
+    input: &str,
+    runs: usize,
+) -> anyhow::Result<Vec<Divergence>> {
+    // The build must still resolve from another working directory.
+    let build = fs::canonicalize(build)
+        .map_err(|e| anyhow::anyhow!("failed to resolve program path {build:?}: {e:?}"))?;
+    let libfaketime = find_libfaketime();
+    if libfaketime.is_none() {
+        static WARN_ONCE: Once = Once::new();
+        WARN_ONCE.call_once(|| {
+            eprintln!(
+                "warning: libfaketime was not found, varied runs will not offset the clock. Set {LIBFAKETIME_ENV} to its path."
+            )
+        });
+    }
+    let run = |variation: &Variation| -> anyhow::Result<Output> {
+        let command = variation.command(&build, libfaketime.as_deref())?;
+        let (output, _) = run_command_profiled(command, input, None)?;
+        if let Some(dir) = &variation.current_dir {
+            let _ = fs::remove_dir_all(dir);
+        }
+        Ok(output)
+    };
+
+    let baseline = run(&Variation::baseline())?;
+    let mut divergences = Vec::new();
+    for variation in (0..runs).map(Variation::nth) {
+        let differences = compare_outputs(&baseline, &run(&variation)?);
+        if !differences.is_empty() {
+            divergences.push(Divergence {
+                variation: variation.label,
+                differences,
+            });
+        }
+    }
+    Ok(divergences)
+}
+
+/// Describes how `other` differs from `baseline`: its exit status, and its
+/// stdout, structurally if both are JSON.
+pub fn compare_outputs(baseline: &Output, other: &Output) -> Vec<String> {
+    let mut differences = Vec::new();
+    if baseline.status.code() != other.status.code() {
+        differences.push(format!(
+            "exited with {:?}, baseline exited with {:?}",
+            other.status.code(),
+            baseline.status.code()
+        ));
+    }
+    match (
+        serde_json::from_slice::<Value>(&baseline.stdout),
+        serde_json::from_slice::<Value>(&other.stdout),
+    ) {
+        (Ok(expected), Ok(actual)) => json_diff("$", &expected, &actual, &mut differences),
+        _ if baseline.stdout != other.stdout => {
+            differences.push("stdout differs from the baseline run".to_string())
+        }
+        _ => {}
+    }
+    differences
+}
+
+/// A source of nondeterminism a program must not depend on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hazard {
+    Clock,
+    Environment,
+    Filesystem,
+    Network,
+    Randomness,
+}
+
+impl Hazard {
+    /// Source patterns that indicate a use of each hazard.
+    const PATTERNS: &'static [(Hazard, &'static [&'static str])] = &[
+        (
+            Hazard::Clock,
+            &["std::time", "SystemTime", "Instant::now", "chrono::"],
+        ),
+        (Hazard::Environment, &["std::env", "env::var", "env::args"]),
+        (
+            Hazard::Filesystem,
+            &[
+                "std::fs",
+                "fs::read",
+                "fs::write",
+                "File::open",
+                "File::create",
+            ],
+        ),
+        (
+            Hazard::Network,
+            &["std::net", "TcpStream", "UdpSocket", "reqwest", "hyper::"],
+        ),
+        (
+            Hazard::Randomness,
+            &["rand::", "thread_rng", "OsRng", "getrandom", "RandomState"],
+        ),
+    ];
+}
+
+impl fmt::Display for Hazard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let description = match self {
+            Hazard::Clock => "the clock",
+            Hazard::Environment => "the environment",
+            Hazard::Filesystem => "the filesystem",
+            Hazard::Network => "the network",
+            Hazard::Randomness => "randomness",
+        };
+        f.write_str(description)
+    }
+}
+
+/// A line of program source using a source of nondeterminism.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceWarning {
+    pub path: PathBuf,
+    pub line: usize,
+    pub hazard: Hazard,
+    pub pattern: &'static str,
+}
+
+impl fmt::Display for SourceWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}: `{}` reads {}, which differs between validators",
+            self.path.display(),
+            self.line,
+            self.pattern,
+            self.hazard
+        )
+    }
+}
+
+/// Scans every `.rs` file under `src` for uses of the clock, environment,
+/// filesystem, network or randomness.
+pub fn scan_source(src: &Path) -> anyhow::Result<Vec<SourceWarning>> {
+    let mut warnings = Vec::new();
+    let snapshot = Snapshot::take(&[src.to_path_buf()]);
+    for path in snapshot
+        .paths()
+        .filter(|path| path.extension().is_some_and(|ext| ext == "rs"))
+    {
+        let source = fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("failed to read source file {path:?}: {e:?}"))?;
+        for (index, line) in source.lines().enumerate() {
+            warnings.extend(
+                scan_line(line)
+                    .into_iter()
+                    .map(|(hazard, pattern)| SourceWarning {
+                        path: path.to_path_buf(),
+                        line: index + 1,
+                        hazard,
+                        pattern,
+                    }),
+            );
+        }
+    }
+    Ok(warnings)
+}
+
+/// The hazards used on a line of source, each with the first pattern that
+/// matched. Line comments are ignored.
+pub fn scan_line(line: &str) -> Vec<(Hazard, &'static str)> {
+    let code = strip_line_comment(line);
+    Hazard::PATTERNS
+        .iter()
+        .filter_map(|(hazard, patterns)| {
+            patterns
+                .iter()
+                .find(|pattern| code.contains(*pattern))
+                .map(|pattern| (*hazard, *pattern))
+        })
+        .collect()
+}
+
+/// `line` up to its line comment, if it has one. A `//` inside a string or
+/// character literal, e.g. in a URL, does not start a comment.
+fn strip_line_comment(line: &str) -> &str {
+    let bytes = line.as_bytes();
+    // Skips past the closing quote of a string whose contents start at `i`.
+    let skip_string = |mut i: usize, hashes: Option<usize>| {
+        while i < bytes.len() {
+            match (bytes[i], hashes) {
+                (b'\\', None) => i += 1,
+                (b'"', None) => return i,
+                (b'"', Some(hashes))
+                    if bytes[i + 1..].iter().take_while(|b| **b == b'#').count() >= hashes =>
+                {
+                    return i + hashes;
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+        i
+    };
+
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'/' if bytes.get(i + 1) == Some(&b'/') => return &line[..i],
+            b'"' => i = skip_string(i + 1, None),
+            b'r' => {
+                let hashes = bytes[i + 1..].iter().take_while(|b| **b == b'#').count();
+                if bytes.get(i + 1 + hashes) == Some(&b'"') {
+                    i = skip_string(i + hashes + 2, Some(hashes));
+                }
+            }
+            // Character literals, leaving lifetimes alone.
+            b'\'' if bytes.get(i + 1) == Some(&b'\\') => {
+                i += 2;
+                while i < bytes.len() && bytes[i] != b'\'' {
+                    i += 1;
+                }
+            }
+            b'\'' if bytes.get(i + 2) == Some(&b'\'') => i += 2,
+            _ => {}
+        }
+        i += 1;
+    }
+    line
+}
+
+/// The `src` directory of the cargo project a binary under `target/` was built
+/// from, if it can be found.
+pub fn infer_source_dir(build: &Path) -> Option<PathBuf> {
+    let build = fs::canonicalize(build).ok()?;
+    build
+        .ancestors()
+        .find(|dir| dir.file_name().is_some_and(|name| name == "target"))
+        .and_then(Path::parent)
+        .map(|project| project.join("src"))
+        .filter(|src| src.is_dir())
+}
+
+#[test]
+fn test_scan_source() -> Result<(), anyhow::Error> {
+    assert_eq!(
+        scan_line("        let current_time = std::time::SystemTime::now();"),
+        vec![(Hazard::Clock, "std::time")]
+    );
+    assert_eq!(
+        scan_line("let key = std::env::var(\"KEY\").map(|_| rand::random::<u8>());"),
+        vec![
+            (Hazard::Environment, "std::env"),
+            (Hazard::Randomness, "rand::")
+        ]
+    );
+    assert!(scan_line("// SystemTime would break consensus here").is_empty());
+    assert_eq!(
+        scan_line(r#"let url = "https://example.com"; let now = std::time::Instant::now();"#),
+        vec![(Hazard::Clock, "std::time")]
+    );
+    assert_eq!(
+        scan_line(r##"let url = r#"https://"quoted""#; std::env::args()"##),
+        vec![(Hazard::Environment, "std::env")]
+    );
+    assert!(scan_line(r#"let c = '"'; let url = "a\"b"; // std::time"#).is_empty());
+
+    let src = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("src/examples/blank");
+    let warnings = scan_source(&src)?;
+    assert!(warnings
+        .iter()
+        .any(|warning| warning.hazard == Hazard::Clock
+            && warning.path.ends_with("example_program.rs")));
+    Ok(())
+}
@@ -0,0 +1,396 @@
+//! Plain-language summaries of the `lasr_types::Outputs` a program prints.
+//!
+//! Each instruction is rendered as one line per effect, e.g. `create program
+//! this: total 0x1000, initialized 0x1000, 0 distributions`, followed by
+//! warnings for patterns that are valid but usually a mistake, such as a
+//! zero supply or an initialized supply that is never distributed.
+use std::fmt;
+
+use lasr_types::U256;
+use serde_json::Value;
+
+use crate::lasrctl::builders::program::parse_u256;
+
+/// A summary of one instruction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExplainedInstruction {
+    pub lines: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+/// A summary of a program's outputs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Explanation {
+    /// The call the outputs answer, e.g. `op create by 0x… on 0x…`.
+    pub call: String,
+    pub instructions: Vec<ExplainedInstruction>,
+    pub warnings: Vec<String>,
+}
+
+impl Explanation {
+    pub fn warning_count(&self) -> usize {
+        self.warnings.len()
+            + self
+                .instructions
+                .iter()
+                .map(|instruction| instruction.warnings.len())
+                .sum::<usize>()
+    }
+}
+
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", self.call)?;
+        for warning in &self.warnings {
+            writeln!(f, "  warning: {warning}")?;
+        }
+        for (index, instruction) in self.instructions.iter().enumerate() {
+            for (line_index, line) in instruction.lines.iter().enumerate() {
+                if line_index == 0 {
+                    writeln!(f, "  {}. {line}", index + 1)?;
+                } else {
+                    writeln!(f, "     {line}")?;
+                }
+            }
+            for warning in &instruction.warnings {
+                writeln!(f, "     warning: {warning}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Summarizes serialized `Outputs`.
+pub fn explain_outputs(outputs: &Value) -> Explanation {
+    let inputs = outputs.get("computeInputs").unwrap_or(&Value::Null);
+    let field = |pointer: &str| {
+        inputs
+            .pointer(pointer)
+            .and_then(Value::as_str)
+            .unwrap_or("?")
+            .to_lowercase()
+    };
+    let call = format!(
+        "op {} by {} on {}",
+        inputs.get("op").and_then(Value::as_str).unwrap_or("?"),
+        field("/transaction/from"),
+        field("/transaction/programId"),
+    );
+
+    let instructions: Vec<ExplainedInstruction> = outputs
+        .get("instructions")
+        .and_then(Value::as_array)
+        .map(|instructions| instructions.iter().map(explain_instruction).collect())
+        .unwrap_or_default();
+    let mut warnings = Vec::new();
+    if instructions.is_empty() {
+        warnings.push("the program produced no instructions".to_string());
+    }
+
+    Explanation {
+        call,
+        instructions,
+        warnings,
+    }
+}
+
+fn explain_instruction(instruction: &Value) -> ExplainedInstruction {
+    let mut explained = ExplainedInstruction {
+        lines: Vec::new(),
+        warnings: Vec::new(),
+    };
+    let Some((kind, body)) = instruction.as_object().and_then(|obj| obj.iter().next()) else {
+        explained
+            .lines
+            .push(format!("unrecognized instruction {instruction}"));
+        return explained;
+    };
+    match kind.as_str() {
+        "create" => explain_create(body, &mut explained),
+        "transfer" => explain_transfer(body, &mut explained),
+        "burn" => explain_burn(body, &mut explained),
+        "update" => explain_update(body, &mut explained),
+        "log" => explained.lines.push(format!("log {}", compact(body))),
+        _ => explained.lines.push(format!("{kind} {}", compact(body))),
+    }
+    explained
+}
+
+fn explain_create(create: &Value, explained: &mut ExplainedInstruction) {
+    let total = amount(create, "totalSupply");
+    let initialized = amount(create, "initializedSupply");
+    let distributions = list(create, "distribution");
+    explained.lines.push(format!(
+        "create program {}: owner {}, total {}, initialized {}, {} distributions",
+        account(create.get("programId")),
+        account(create.get("programOwner")),
+        show(total),
+        show(initialized),
+        distributions.len()
+    ));
+
+    let mut distributed = U256::zero();
+    for distribution in distributions {
+        let distribution_amount = amount(distribution, "amount");
+        let token_ids = list(distribution, "tokenIds");
+        let mut line = format!(
+            "distribute {} to {}",
+            show(distribution_amount),
+            account(distribution.get("to"))
+        );
+        if !token_ids.is_empty() {
+            line.push_str(&format!(" ({} token IDs)", token_ids.len()));
+        }
+        explained.lines.push(line);
+        distributed = distributed.saturating_add(distribution_amount.unwrap_or_default());
+    }
+
+    if total.map_or(false, |total| total.is_zero()) {
+        explained.warnings.push("total supply is zero".to_string());
+    }
+    match initialized {
+        Some(initialized) if initialized.is_zero() => explained
+            .warnings
+            .push("initialized supply is zero, nothing is created up front".to_string()),
+        Some(initialized) if distributions.is_empty() => explained.warnings.push(format!(
+            "empty distribution: the initialized supply of {} is not handed to anyone",
+            show(Some(initialized))
+        )),
+        Some(initialized) if distributed < initialized => explained.warnings.push(format!(
+            "only {} of the initialized supply of {} is distributed",
+            show(Some(distributed)),
+            show(Some(initialized))
+        )),
+        _ => {}
+    }
+    if let (Some(total), Some(initialized)) = (total, initialized) {
+        if initialized > total {
+            explained.warnings.push(format!(
+                "initialized supply {} exceeds total supply {}",
+                show(Some(initialized)),
+                show(Some(total))
+            ));
+        }
+    }
+}
+
+fn explain_transfer(transfer: &Value, explained: &mut ExplainedInstruction) {
+    let transfer_amount = amount(transfer, "amount");
+    let token_ids = list(transfer, "ids");
+    let mut line = format!(
+        "transfer {} of {} from {} to {}",
+        show(transfer_amount),
+        account(transfer.get("token")),
+        account(transfer.get("from")),
+        account(transfer.get("to"))
+    );
+    if !token_ids.is_empty() {
+        line.push_str(&format!(", token IDs {}", show_ids(token_ids)));
+    }
+    explained.lines.push(line);
+
+    if transfer_amount.unwrap_or_default().is_zero() && token_ids.is_empty() {
+        explained
+            .warnings
+            .push("transfers neither an amount nor any token IDs".to_string());
+    }
+    if transfer.get("from").is_some() && transfer.get("from") == transfer.get("to") {
+        explained
+            .warnings
+            .push("transfers to the account it debits".to_string());
+    }
+}
+
+fn explain_burn(burn: &Value, explained: &mut ExplainedInstruction) {
+    let burn_amount = amount(burn, "amount");
+    let token_ids = list(burn, "tokenIds");
+    let mut line = format!(
+        "burn {} of {} from {}",
+        show(burn_amount),
+        account(burn.get("token")),
+        account(burn.get("from"))
+    );
+    if !token_ids.is_empty() {
+        line.push_str(&format!(", token IDs {}", show_ids(token_ids)));
+    }
+    explained.lines.push(line);
+
+    if burn_amount.unwrap_or_default().is_zero() && token_ids.is_empty() {
+        explained
+            .warnings
+            .push("burns neither an amount nor any token IDs".to_string());
+    }
+}
+
+fn explain_update(update: &Value, explained: &mut ExplainedInstruction) {
+    let updates = list(update, "updates");
+    if updates.is_empty() {
+        explained.lines.push("update nothing".to_string());
+        explained.warnings.push("contains no updates".to_string());
+    }
+    for update in updates {
+        let Some((kind, body)) = update.as_object().and_then(|obj| obj.iter().next()) else {
+            explained.lines.push(format!("update {}", compact(update)));
+            continue;
+        };
+        let target = match kind.as_str() {
+            "programUpdate" => account(body.get("account")),
+            "tokenUpdate" => format!(
+                "token {} of {}",
+                account(body.get("token")),
+                account(body.get("account"))
+            ),
+            _ => kind.clone(),
+        };
+        let field_updates = list(body, "updates");
+        if field_updates.is_empty() {
+            explained.lines.push(format!("update nothing on {target}"));
+            explained
+                .warnings
+                .push(format!("the update of {target} contains no field updates"));
+        }
+        for field_update in field_updates {
+            let field = field_update
+                .get("field")
+                .and_then(Value::as_str)
+                .unwrap_or("?");
+            explained.lines.push(format!(
+                "update {field}{} on {target}",
+                describe_value(field_update.get("value"))
+            ));
+        }
+    }
+}
+
+/// Describes a field update's value, e.g. ` key 'metadata'` for an insert.
+fn describe_value(value: Option<&Value>) -> String {
+    // Values are wrapped in their field's variant, e.g. `{"metadata": {"insert": [..]}}`.
+    let Some((_, operation)) = value
+        .and_then(Value::as_object)
+        .and_then(|obj| obj.iter().next())
+    else {
+        return String::new();
+    };
+    let Some((op, argument)) = operation.as_object().and_then(|obj| obj.iter().next()) else {
+        return format!(" to {}", compact(operation));
+    };
+    let keys = |map: &Value| {
+        map.as_object()
+            .map(|map| {
+                map.keys()
+                    .map(|key| format!("'{key}'"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            })
+            .unwrap_or_default()
+    };
+    match (op.as_str(), argument) {
+        ("insert", Value::Array(pair)) => match pair.first() {
+            Some(Value::String(key)) => format!(" key '{key}'"),
+            _ => format!(" insert {}", compact(argument)),
+        },
+        ("insert", Value::String(item)) => format!(" add {item}"),
+        ("extend", Value::Object(_)) => format!(" keys {}", keys(argument)),
+        ("remove", Value::String(key)) => format!(" remove '{key}'"),
+        ("replaceAll", Value::Object(_)) => format!(" replace all with keys {}", keys(argument)),
+        (op, argument) => format!(" {op} {}", compact(argument)),
+    }
+}
+
+fn amount(instruction: &Value, field: &str) -> Option<U256> {
+    instruction
+        .get(field)
+        .and_then(Value::as_str)
+        .and_then(|amount| parse_u256(amount).ok())
+}
+
+fn list<'a>(instruction: &'a Value, field: &str) -> &'a [Value] {
+    instruction
+        .get(field)
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+}
+
+fn show(amount: Option<U256>) -> String {
+    match amount {
+        Some(amount) => format!("{amount:#x}"),
+        None => "?".to_string(),
+    }
+}
+
+fn show_ids(token_ids: &[Value]) -> String {
+    let ids: Vec<String> = token_ids
+        .iter()
+        .map(|id| show(id.as_str().and_then(|id| parse_u256(id).ok())))
+        .collect();
+    format!("[{}]", ids.join(", "))
+}
+
+/// Renders an `AddressOrNamespace`, e.g. `this` or `0x…`.
+fn account(value: Option<&Value>) -> String {
+    match value {
+        None | Some(Value::Null) => "?".to_string(),
+        Some(Value::String(account)) => account.to_lowercase(),
+        Some(Value::Object(obj)) => match obj.iter().next() {
+            Some((_, Value::String(account))) => account.to_lowercase(),
+            _ => compact(&Value::Object(obj.clone())),
+        },
+        Some(other) => compact(other),
+    }
+}
+
+fn compact(value: &Value) -> String {
+    serde_json::to_string(value).unwrap_or_default()
+}
+
+#[test]
+fn test_explain_outputs() {
+    let outputs = serde_json::json!({
+        "computeInputs": {
+            "op": "create",
+            "transaction": {
+                "from": "0x100444c7D04A842D19bc3eE63cB7b96682FF3f43",
+                "programId": "0x100444c7d04a842d19bc3ee63cb7b96682ff3f43"
+            }
+        },
+        "instructions": [
+            { "create": {
+                "programNamespace": "this",
+                "programId": "this",
+                "programOwner": "0x100444c7d04a842d19bc3ee63cb7b96682ff3f43",
+                "totalSupply": "0x0000000000000000000000000000000000000000000000000000000000001000",
+                "initializedSupply": "0x0000000000000000000000000000000000000000000000000000000000001000",
+                "distribution": []
+            } },
+            { "update": { "updates": [ { "programUpdate": {
+                "account": "this",
+                "updates": [ {
+                    "field": "metadata",
+                    "value": { "metadata": { "insert": ["metadata", "[\"HLLO\"]"] } }
+                } ]
+            } } ] } }
+        ]
+    });
+
+    let explanation = explain_outputs(&outputs);
+    assert_eq!(
+        explanation.call,
+        "op create by 0x100444c7d04a842d19bc3ee63cb7b96682ff3f43 on 0x100444c7d04a842d19bc3ee63cb7b96682ff3f43"
+    );
+    assert_eq!(
+        explanation.instructions[0].lines,
+        vec![
+            "create program this: owner 0x100444c7d04a842d19bc3ee63cb7b96682ff3f43, total 0x1000, initialized 0x1000, 0 distributions"
+        ]
+    );
+    assert_eq!(
+        explanation.instructions[0].warnings,
+        vec!["empty distribution: the initialized supply of 0x1000 is not handed to anyone"]
+    );
+    assert_eq!(
+        explanation.instructions[1].lines,
+        vec!["update metadata key 'metadata' on this"]
+    );
+    assert_eq!(explanation.warning_count(), 1);
+}
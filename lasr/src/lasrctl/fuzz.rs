@@ -0,0 +1,716 @@
+//! Mutation fuzzing of LASR programs.
+//!
+//! Valid fixtures are mutated (ops, `transactionInputs`, amounts, addresses,
+//! malformed JSON) and fed to a program, either a built binary or an
+//! in-process `Program`. A program may reject any mutated call, but only
+//! with a classified `ProgramError`; panics, timeouts, output that isn't
+//! JSON and outputs that break an invariant are reported as findings.
+//!
+//! Mutations are drawn from a seeded PRNG, so a run is reproducible from its
+//! seed.
+use std::{
+    cell::Cell,
+    fmt,
+    io::Write,
+    panic::{self, AssertUnwindSafe},
+    path::Path,
+    process::{Command, Stdio},
+    sync::Once,
+    thread,
+    time::{Duration, Instant},
+};
+
+use lasr_types::{Inputs, U256};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use serde_json::Value;
+
+use crate::lasrctl::{
+    builders::{
+        account::AccountState,
+        error::ProgramError,
+        program::{parse_u256, Program},
+    },
+    profile::read_in_background,
+    validate::validate_outputs,
+};
+
+/// Ops tried alongside the ones found in the fixtures.
+const EXTRA_OPS: &[&str] = &["", "unknown", "CREATE", "create ", "__proto__"];
+
+/// Values substituted for amounts and other leaves of `transactionInputs`.
+const EXTREME_VALUES: &[&str] = &[
+    "0",
+    "0x0",
+    "1",
+    "-1",
+    "0x",
+    "0xzz",
+    "1e309",
+    "",
+    "0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff",
+    "0x1ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff",
+    "115792089237316195423570985008687907853269984665640564039457584007913129639935",
+    "115792089237316195423570985008687907853269984665640564039457584007913129639936",
+];
+
+/// Values substituted for addresses.
+const BAD_ADDRESSES: &[&str] = &[
+    "0x0000000000000000000000000000000000000000",
+    "0x0",
+    "",
+    "this",
+    "0x100444c7d04a842d19bc3ee63cb7b96682ff3f4",
+    "0x100444c7d04a842d19bc3ee63cb7b96682ff3f4300",
+    "0xZZ0444c7d04a842d19bc3ee63cb7b96682ff3f43",
+];
+
+/// Documents substituted for the whole of `transactionInputs`.
+const MALFORMED_TRANSACTION_INPUTS: &[&str] = &[
+    "{",
+    "null",
+    "[]",
+    "\"\"",
+    "42",
+    "{\"amount\":{}}",
+    "{\"amount\":[1,2,3]}",
+    "\u{0}",
+];
+
+/// How a program run ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RunOutcome {
+    /// The program exited, successfully or not, with the given output.
+    Completed {
+        success: bool,
+        exit_code: Option<i32>,
+        stdout: String,
+        stderr: String,
+    },
+    /// The program panicked in process, with the panic message.
+    Panicked(String),
+    /// The program did not finish within the timeout.
+    TimedOut,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FindingKind {
+    Panic,
+    Timeout,
+    NonJsonOutput,
+    UnclassifiedError,
+    InvariantViolation,
+}
+
+impl fmt::Display for FindingKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self {
+            FindingKind::Panic => "panic",
+            FindingKind::Timeout => "timeout",
+            FindingKind::NonJsonOutput => "non-JSON output",
+            FindingKind::UnclassifiedError => "unclassified error",
+            FindingKind::InvariantViolation => "invariant violation",
+        };
+        write!(f, "{kind}")
+    }
+}
+
+/// A mutated call the program mishandled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    pub kind: FindingKind,
+    pub fixture: String,
+    pub mutation: String,
+    pub detail: String,
+    /// The mutated payload, to reproduce the finding with `lasr test`.
+    pub payload: String,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} in {} ({}): {}",
+            self.kind, self.fixture, self.mutation, self.detail
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct FuzzConfig {
+    pub iterations: usize,
+    pub seed: u64,
+}
+
+/// The results of a fuzzing run. Findings are deduplicated by kind and
+/// detail, so each distinct bug is reported once.
+#[derive(Debug, Default)]
+pub struct FuzzReport {
+    pub runs: usize,
+    pub accepted: usize,
+    pub rejected: usize,
+    pub findings: Vec<Finding>,
+}
+
+impl fmt::Display for FuzzReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "fuzz result: {}. {} runs; {} accepted; {} rejected; {} findings",
+            if self.findings.is_empty() {
+                "ok"
+            } else {
+                "FAILED"
+            },
+            self.runs,
+            self.accepted,
+            self.rejected,
+            self.findings.len()
+        )
+    }
+}
+
+/// Runs `config.iterations` mutations of `fixtures` (name, serialized
+/// `Inputs`) through `run`, classifying each outcome.
+pub fn fuzz<F>(
+    fixtures: &[(String, Value)],
+    config: FuzzConfig,
+    mut run: F,
+) -> anyhow::Result<FuzzReport>
+where
+    F: FnMut(&str) -> anyhow::Result<RunOutcome>,
+{
+    if fixtures.is_empty() {
+        anyhow::bail!("no fixtures to mutate");
+    }
+    let mut ops: Vec<String> = fixtures
+        .iter()
+        .filter_map(|(_, fixture)| fixture.get("op").and_then(Value::as_str))
+        .map(str::to_string)
+        .collect();
+    ops.extend(EXTRA_OPS.iter().map(|op| op.to_string()));
+    ops.sort();
+    ops.dedup();
+
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    let mut report = FuzzReport::default();
+    for _ in 0..config.iterations {
+        let (name, fixture) = &fixtures[rng.gen_range(0..fixtures.len())];
+        let (payload, mutation) = mutate(fixture, &ops, &mut rng);
+        let outcome = run(&payload)?;
+        report.runs += 1;
+        match classify(&payload, &outcome) {
+            Ok(true) => report.accepted += 1,
+            Ok(false) => report.rejected += 1,
+            Err((kind, detail)) => {
+                let duplicate = report.findings.iter().any(|finding| {
+                    finding.kind == kind
+                        && without_numbers(&finding.detail) == without_numbers(&detail)
+                });
+                if !duplicate {
+                    report.findings.push(Finding {
+                        kind,
+                        fixture: name.clone(),
+                        mutation,
+                        detail,
+                        payload,
+                    });
+                }
+            }
+        }
+    }
+    Ok(report)
+}
+
+/// `detail` with every run of digits replaced by `N`, so that findings that
+/// only differ in amounts or indices count as one.
+fn without_numbers(detail: &str) -> String {
+    let mut normalized = String::with_capacity(detail.len());
+    for c in detail.chars() {
+        if !c.is_ascii_digit() {
+            normalized.push(c);
+        } else if !normalized.ends_with('N') {
+            normalized.push('N');
+        }
+    }
+    normalized
+}
+
+/// Applies one random mutation to a serialized `Inputs` fixture, returning
+/// the payload to run and a description of the mutation.
+pub fn mutate(fixture: &Value, ops: &[String], rng: &mut StdRng) -> (String, String) {
+    let mut inputs = fixture.clone();
+    let transaction_inputs: Option<Value> = inputs
+        .pointer("/transaction/transactionInputs")
+        .and_then(Value::as_str)
+        .and_then(|transaction_inputs| serde_json::from_str(transaction_inputs).ok());
+
+    let description = match rng.gen_range(0..8) {
+        0 => {
+            let op = ops.choose(rng).cloned().unwrap_or_default();
+            inputs["op"] = Value::String(op.clone());
+            if inputs.get("transaction").is_some() {
+                inputs["transaction"]["op"] = Value::String(op.clone());
+            }
+            format!("op set to {op:?}")
+        }
+        1 => {
+            let malformed = MALFORMED_TRANSACTION_INPUTS
+                .choose(rng)
+                .copied()
+                .unwrap_or("{");
+            set_transaction_inputs(&mut inputs, malformed.to_string());
+            format!("transactionInputs set to {malformed:?}")
+        }
+        2 => match transaction_inputs {
+            Some(mut transaction_inputs) => {
+                let description = mutate_leaf(&mut transaction_inputs, rng);
+                set_transaction_inputs(&mut inputs, transaction_inputs.to_string());
+                format!("transactionInputs{description}")
+            }
+            None => {
+                let value = EXTREME_VALUES.choose(rng).copied().unwrap_or("0");
+                inputs["transaction"]["value"] = Value::String(value.to_string());
+                format!("transaction value set to {value:?}")
+            }
+        },
+        3 => {
+            let value = EXTREME_VALUES.choose(rng).copied().unwrap_or("0");
+            inputs["transaction"]["value"] = Value::String(value.to_string());
+            format!("transaction value set to {value:?}")
+        }
+        4 => {
+            let addresses = leaf_pointers(&inputs, &|leaf| {
+                leaf.as_str().is_some_and(|leaf| {
+                    leaf.len() == 42 && leaf.starts_with("0x") && hex::decode(&leaf[2..]).is_ok()
+                })
+            });
+            match addresses.choose(rng) {
+                Some(pointer) => {
+                    let address = BAD_ADDRESSES.choose(rng).copied().unwrap_or("");
+                    if let Some(leaf) = inputs.pointer_mut(pointer) {
+                        *leaf = Value::String(address.to_string());
+                    }
+                    format!("{pointer} set to {address:?}")
+                }
+                None => "unchanged".to_string(),
+            }
+        }
+        5 => {
+            let supplies = ["totalSupply", "initializedSupply", "maxSupply"];
+            let key = supplies.choose(rng).copied().unwrap_or("totalSupply");
+            let value = EXTREME_VALUES.choose(rng).copied().unwrap_or("0");
+            inputs["accountInfo"]["programAccountMetadata"][key] = Value::String(value.to_string());
+            format!("program metadata {key} set to {value:?}")
+        }
+        6 => match transaction_inputs {
+            Some(Value::Object(mut map)) if !map.is_empty() => {
+                let keys: Vec<String> = map.keys().cloned().collect();
+                let key = keys.choose(rng).cloned().unwrap_or_default();
+                map.remove(&key);
+                set_transaction_inputs(&mut inputs, Value::Object(map).to_string());
+                format!("transactionInputs field {key:?} removed")
+            }
+            _ => {
+                set_transaction_inputs(&mut inputs, String::new());
+                "transactionInputs emptied".to_string()
+            }
+        },
+        _ => {
+            let payload = inputs.to_string();
+            let cut = rng.gen_range(0..payload.len().max(1));
+            let cut = (0..=cut)
+                .rev()
+                .find(|cut| payload.is_char_boundary(*cut))
+                .unwrap_or(0);
+            return (
+                payload[..cut].to_string(),
+                format!("payload truncated at byte {cut}"),
+            );
+        }
+    };
+    (inputs.to_string(), description)
+}
+
+fn set_transaction_inputs(inputs: &mut Value, transaction_inputs: String) {
+    if inputs.get("transaction").is_some() {
+        inputs["transaction"]["transactionInputs"] = Value::String(transaction_inputs);
+    }
+}
+
+/// Replaces a random leaf of `value` with an extreme value or wrong type,
+/// returning the leaf's JSON pointer and replacement.
+fn mutate_leaf(value: &mut Value, rng: &mut StdRng) -> String {
+    let leaves = leaf_pointers(value, &|leaf| !leaf.is_array() && !leaf.is_object());
+    let Some(pointer) = leaves.choose(rng).cloned() else {
+        *value = Value::String(
+            EXTREME_VALUES
+                .choose(rng)
+                .copied()
+                .unwrap_or("")
+                .to_string(),
+        );
+        return format!(" set to {value}");
+    };
+    let replacement = if rng.gen_bool(0.8) {
+        Value::String(
+            EXTREME_VALUES
+                .choose(rng)
+                .copied()
+                .unwrap_or("")
+                .to_string(),
+        )
+    } else {
+        [
+            Value::Null,
+            Value::Bool(true),
+            Value::Array(Vec::new()),
+            serde_json::json!({}),
+            serde_json::json!(u64::MAX),
+            serde_json::json!(-1),
+        ]
+        .choose(rng)
+        .cloned()
+        .unwrap_or(Value::Null)
+    };
+    let description = format!("{pointer} set to {replacement}");
+    if let Some(leaf) = value.pointer_mut(&pointer) {
+        *leaf = replacement;
+    }
+    description
+}
+
+/// The JSON pointers of every leaf of `value` matching `matches`.
+fn leaf_pointers(value: &Value, matches: &dyn Fn(&Value) -> bool) -> Vec<String> {
+    fn walk(value: &Value, path: String, matches: &dyn Fn(&Value) -> bool, out: &mut Vec<String>) {
+        match value {
+            Value::Object(map) => map.iter().for_each(|(key, value)| {
+                let key = key.replace('~', "~0").replace('/', "~1");
+                walk(value, format!("{path}/{key}"), matches, out)
+            }),
+            Value::Array(items) => items
+                .iter()
+                .enumerate()
+                .for_each(|(index, value)| walk(value, format!("{path}/{index}"), matches, out)),
+            leaf if matches(leaf) => out.push(path),
+            _ => {}
+        }
+    }
+    let mut pointers = Vec::new();
+    walk(value, String::new(), matches, &mut pointers);
+    pointers
+}
+
+/// Decides whether a run accepted (`Ok(true)`) or cleanly rejected
+/// (`Ok(false)`) the payload, or mishandled it.
+pub fn classify(payload: &str, outcome: &RunOutcome) -> Result<bool, (FindingKind, String)> {
+    let (success, exit_code, stdout, stderr) = match outcome {
+        RunOutcome::Panicked(message) => return Err((FindingKind::Panic, message.clone())),
+        RunOutcome::TimedOut => {
+            return Err((FindingKind::Timeout, "program did not finish".to_string()))
+        }
+        RunOutcome::Completed {
+            success,
+            exit_code,
+            stdout,
+            stderr,
+        } => (*success, *exit_code, stdout, stderr),
+    };
+
+    if let Some(panic) = stderr.lines().find(|line| line.contains("panicked at")) {
+        return Err((FindingKind::Panic, panic.trim().to_string()));
+    }
+    if !success {
+        return match (exit_code, ProgramError::from_json(stdout)) {
+            (_, Some(_)) => Ok(false),
+            (None, None) => Err((
+                FindingKind::Panic,
+                "program was terminated by a signal".to_string(),
+            )),
+            (Some(code), None) => Err((
+                FindingKind::UnclassifiedError,
+                format!("program exited with code {code} without a ProgramError"),
+            )),
+        };
+    }
+
+    if let Err(e) = serde_json::from_str::<Value>(stdout) {
+        return Err((FindingKind::NonJsonOutput, format!("{e}")));
+    }
+    let Ok(inputs) = serde_json::from_str::<Inputs>(payload) else {
+        return Err((
+            FindingKind::InvariantViolation,
+            "program accepted a payload that is not valid Inputs".to_string(),
+        ));
+    };
+    let mut violations: Vec<String> = match validate_outputs(stdout, &inputs) {
+        Ok(issues) => issues.iter().map(ToString::to_string).collect(),
+        Err(e) => vec![format!("{e:#}")],
+    };
+    if let Some(violation) = supply_violation(&inputs, stdout) {
+        violations.push(violation);
+    }
+    match violations.into_iter().next() {
+        Some(violation) => Err((FindingKind::InvariantViolation, violation)),
+        None => Ok(true),
+    }
+}
+
+/// Checks that the program does not hand out more of its own token than its
+/// `totalSupply` allows on top of the `currentSupply` already minted, e.g. by
+/// minting past it. Until the first mint records it, `currentSupply` is the
+/// program's `initializedSupply`.
+fn supply_violation(inputs: &Inputs, outputs: &str) -> Option<String> {
+    let state = AccountState::new(inputs);
+    let total_supply: U256 = state.setting("totalSupply").ok()??;
+    let current_supply: U256 = match state.setting("currentSupply").ok()? {
+        Some(current_supply) => current_supply,
+        None => state.setting("initializedSupply").ok()?.unwrap_or_default(),
+    };
+    let program_id = inputs
+        .transaction
+        .program_id()
+        .to_full_string()
+        .to_lowercase();
+    let outputs: Value = serde_json::from_str(outputs).ok()?;
+    let is_program = |account: Option<&Value>| match account {
+        Some(Value::String(account)) => account == "this" || account.to_lowercase() == program_id,
+        Some(Value::Object(account)) => account
+            .values()
+            .any(|account| account.as_str().map(str::to_lowercase) == Some(program_id.clone())),
+        _ => false,
+    };
+
+    let mut handed_out = U256::zero();
+    for instruction in outputs.get("instructions")?.as_array()? {
+        let Some(transfer) = instruction.get("transfer") else {
+            continue;
+        };
+        if is_program(transfer.get("token")) && is_program(transfer.get("from")) {
+            let amount = transfer
+                .get("amount")
+                .and_then(Value::as_str)
+                .and_then(|amount| parse_u256(amount).ok())
+                .unwrap_or_default();
+            handed_out = handed_out.saturating_add(amount);
+        }
+    }
+    let supply = current_supply.saturating_add(handed_out);
+    (supply > total_supply).then(|| {
+        format!(
+            "transfers {handed_out} of the program's token on top of its currentSupply {current_supply}, more than its totalSupply {total_supply}"
+        )
+    })
+}
+
+/// Runs a program binary against `payload`, killing it after `timeout`.
+pub fn run_binary(build: &Path, payload: &str, timeout: Duration) -> anyhow::Result<RunOutcome> {
+    let mut child = Command::new(build)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| anyhow::anyhow!("failed to spawn child process for {build:?}: {e:?}"))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // The program may exit without reading its input.
+        let _ = stdin.write_all(payload.as_bytes());
+    }
+    let stdout = child.stdout.take().map(read_in_background);
+    let stderr = child.stderr.take().map(read_in_background);
+
+    let started = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break Some(status);
+        }
+        if started.elapsed() > timeout {
+            let _ = child.kill();
+            let _ = child.wait();
+            break None;
+        }
+        thread::sleep(Duration::from_millis(5));
+    };
+    let collect = |reader: Option<thread::JoinHandle<Vec<u8>>>| {
+        let bytes = reader
+            .and_then(|reader| reader.join().ok())
+            .unwrap_or_default();
+        String::from_utf8_lossy(&bytes).into_owned()
+    };
+    let (stdout, stderr) = (collect(stdout), collect(stderr));
+    Ok(match status {
+        Some(status) => RunOutcome::Completed {
+            success: status.success(),
+            exit_code: status.code(),
+            stdout,
+            stderr,
+        },
+        None => RunOutcome::TimedOut,
+    })
+}
+
+thread_local! {
+    /// Whether this thread is inside `run_in_process`, whose panics are
+    /// reported as findings instead of printed.
+    static CATCHING_PANICS: Cell<bool> = Cell::new(false);
+}
+
+/// Installs, once per process, a panic hook that stays quiet for panics
+/// caught by `run_in_process` and defers to the previous hook for any other,
+/// so concurrent callers never swap the hook out from under each other.
+fn install_quiet_panic_hook() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        let previous_hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if !CATCHING_PANICS.with(Cell::get) {
+                previous_hook(info);
+            }
+        }));
+    });
+}
+
+/// Runs an in-process `Program` against `payload`, catching panics.
+pub fn run_in_process(program: &Program<Inputs>, payload: &str) -> RunOutcome {
+    install_quiet_panic_hook();
+    CATCHING_PANICS.with(|catching| catching.set(true));
+    let result = panic::catch_unwind(AssertUnwindSafe(|| program.respond(payload)));
+    CATCHING_PANICS.with(|catching| catching.set(false));
+
+    match result {
+        Ok(Ok(stdout)) => RunOutcome::Completed {
+            success: true,
+            exit_code: Some(0),
+            stdout,
+            stderr: String::new(),
+        },
+        Ok(Err(error)) => RunOutcome::Completed {
+            success: false,
+            exit_code: Some(error.code),
+            stdout: error.to_json(),
+            stderr: String::new(),
+        },
+        Err(panic) => RunOutcome::Panicked(
+            panic
+                .downcast_ref::<&str>()
+                .map(|message| message.to_string())
+                .or_else(|| panic.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "program panicked".to_string()),
+        ),
+    }
+}
+
+#[test]
+fn test_fuzz_in_process() -> Result<(), anyhow::Error> {
+    let fixture: Value = serde_json::from_str(include_str!(
+        "../examples/fungible/example-program-inputs/fungible-create.json"
+    ))?;
+    let fixtures = vec![("fungible-create.json".to_string(), fixture.clone())];
+    let config = FuzzConfig {
+        iterations: 50,
+        seed: 7,
+    };
+
+    // The same seed produces the same mutations.
+    let ops = vec!["create".to_string()];
+    let mutations = |seed| {
+        let mut rng = StdRng::seed_from_u64(seed);
+        (0..20)
+            .map(|_| mutate(&fixture, &ops, &mut rng))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(mutations(7), mutations(7));
+
+    // A program that panics on any transactionInputs but the fixture's.
+    let original_inputs = fixture["transaction"]["transactionInputs"]
+        .as_str()
+        .unwrap_or_default()
+        .to_string();
+    let mut program = Program::empty();
+    program.register_raw_method("create", move |inputs: &Inputs| {
+        if inputs.transaction.inputs() != original_inputs {
+            panic!("unparseable amount");
+        }
+        anyhow::bail!("rejected")
+    });
+    let report = fuzz(&fixtures, config, |payload| {
+        Ok(run_in_process(&program, payload))
+    })?;
+    assert_eq!(report.runs, 50);
+    assert_eq!(report.accepted, 0);
+    assert!(!report.findings.is_empty());
+    assert!(report
+        .findings
+        .iter()
+        .all(|finding| finding.kind == FindingKind::Panic));
+    Ok(())
+}
+
+#[test]
+fn test_fuzz_default_program() -> Result<(), anyhow::Error> {
+    let fixtures = [
+        (
+            "fungible-create.json",
+            include_str!("../examples/fungible/example-program-inputs/fungible-create.json"),
+        ),
+        (
+            "fungible-mint.json",
+            include_str!("../examples/fungible/example-program-inputs/fungible-mint.json"),
+        ),
+        (
+            "fungible-burn.json",
+            include_str!("../examples/fungible/example-program-inputs/fungible-burn.json"),
+        ),
+        (
+            "fungible-approve.json",
+            include_str!("../examples/fungible/example-program-inputs/fungible-approve.json"),
+        ),
+        (
+            "fungible-update.json",
+            include_str!("../examples/fungible/example-program-inputs/fungible-update.json"),
+        ),
+    ]
+    .into_iter()
+    .map(|(name, fixture)| Ok((name.to_string(), serde_json::from_str(fixture)?)))
+    .collect::<anyhow::Result<Vec<(String, Value)>>>()?;
+
+    let program = Program::new();
+    let report = fuzz(
+        &fixtures,
+        FuzzConfig {
+            iterations: 200,
+            seed: 7,
+        },
+        |payload| Ok(run_in_process(&program, payload)),
+    )?;
+    assert_eq!(report.runs, 200);
+    assert_eq!(report.accepted + report.rejected, report.runs);
+    assert!(report.findings.is_empty(), "{:#?}", report.findings);
+    Ok(())
+}
+
+#[test]
+fn test_supply_violation_counts_current_supply() -> Result<(), anyhow::Error> {
+    let mut inputs: Value = serde_json::from_str(include_str!(
+        "../examples/fungible/example-program-inputs/fungible-mint.json"
+    ))?;
+    inputs["accountInfo"]["programAccountData"]["totalSupply"] = "1000".into();
+    inputs["accountInfo"]["programAccountData"]["currentSupply"] = "900".into();
+    let inputs: Inputs = serde_json::from_value(inputs)?;
+    let outputs = |amount: u64| {
+        serde_json::json!({
+            "instructions": [{
+                "transfer": {
+                    "token": "this",
+                    "from": "this",
+                    "to": { "address": "0x482830d7655fb8465a43844fc1530a7713781b49" },
+                    "amount": format!("{amount:#x}"),
+                    "ids": []
+                }
+            }]
+        })
+        .to_string()
+    };
+
+    assert_eq!(supply_violation(&inputs, &outputs(100)), None);
+    assert!(supply_violation(&inputs, &outputs(101)).is_some());
+    Ok(())
+}
@@ -0,0 +1,329 @@
+//! Passphrase encrypted keystores in the Web3 secret-storage (v3) format.
+//!
+//! The secret key is encrypted with AES-128-CTR under the first half of a
+//! scrypt derived key, and authenticated with a keccak256 MAC over the second
+//! half of the derived key and the ciphertext, so a wrong passphrase or a
+//! tampered file is detected before the key is used.
+//!
+//! Keypairs derived from a mnemonic also keep it, encrypted the same way
+//! under its own IV and MAC, in a `mnemonic` field other v3 readers ignore.
+use std::io::{self, IsTerminal};
+
+use aes::cipher::{KeyIvInit, StreamCipher};
+use anyhow::bail;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Keccak256};
+
+/// The environment variable a keystore passphrase is read from before prompting.
+pub const KEYSTORE_PASSPHRASE_ENV: &str = "LASR_KEYSTORE_PASSPHRASE";
+
+type Aes128Ctr = ctr::Ctr128BE<aes::Aes128>;
+
+/// scrypt cost parameters. `n` is `2^log_n`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScryptParams {
+    pub log_n: u8,
+    pub r: u32,
+    pub p: u32,
+}
+
+impl Default for ScryptParams {
+    fn default() -> Self {
+        Self {
+            log_n: 15,
+            r: 8,
+            p: 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Keystore {
+    pub version: u8,
+    pub id: String,
+    /// The keypair's address, without the `0x` prefix.
+    pub address: String,
+    pub crypto: KeystoreCrypto,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mnemonic: Option<EncryptedMnemonic>,
+}
+
+/// A BIP-39 mnemonic encrypted under the keystore's derived key.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EncryptedMnemonic {
+    pub cipherparams: CipherParams,
+    pub ciphertext: String,
+    pub mac: String,
+}
+
+/// The contents of a decrypted keystore.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecryptedKeystore {
+    pub secret_key: [u8; 32],
+    pub mnemonic: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeystoreCrypto {
+    pub cipher: String,
+    pub cipherparams: CipherParams,
+    pub ciphertext: String,
+    pub kdf: String,
+    pub kdfparams: KdfParams,
+    pub mac: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CipherParams {
+    pub iv: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KdfParams {
+    pub dklen: usize,
+    pub n: u64,
+    pub r: u32,
+    pub p: u32,
+    pub salt: String,
+}
+
+impl Keystore {
+    /// Encrypts `secret_key`, and the `mnemonic` it was derived from if any,
+    /// under `passphrase`.
+    pub fn encrypt(
+        secret_key: &[u8; 32],
+        mnemonic: Option<&str>,
+        address: &str,
+        passphrase: &str,
+        params: ScryptParams,
+    ) -> anyhow::Result<Self> {
+        let mut rng = rand::thread_rng();
+        let mut salt = [0u8; 32];
+        let mut iv = [0u8; 16];
+        let mut id = [0u8; 16];
+        rng.fill_bytes(&mut salt);
+        rng.fill_bytes(&mut iv);
+        rng.fill_bytes(&mut id);
+        // Mark the id as a version 4, variant 1 UUID.
+        id[6] = (id[6] & 0x0f) | 0x40;
+        id[8] = (id[8] & 0x3f) | 0x80;
+
+        let derived_key = derive_key(passphrase, &salt, params)?;
+        let mut ciphertext = secret_key.to_vec();
+        apply_cipher(&derived_key, &iv, &mut ciphertext)?;
+        let mnemonic = mnemonic
+            .map(|mnemonic| {
+                let mut iv = [0u8; 16];
+                rng.fill_bytes(&mut iv);
+                let mut ciphertext = mnemonic.as_bytes().to_vec();
+                apply_cipher(&derived_key, &iv, &mut ciphertext)?;
+                anyhow::Ok(EncryptedMnemonic {
+                    cipherparams: CipherParams {
+                        iv: hex::encode(iv),
+                    },
+                    mac: hex::encode(mac(&derived_key, &ciphertext)),
+                    ciphertext: hex::encode(ciphertext),
+                })
+            })
+            .transpose()?;
+        let id = hex::encode(id);
+
+        Ok(Self {
+            version: 3,
+            id: format!(
+                "{}-{}-{}-{}-{}",
+                &id[..8],
+                &id[8..12],
+                &id[12..16],
+                &id[16..20],
+                &id[20..]
+            ),
+            address: address.trim_start_matches("0x").to_lowercase(),
+            crypto: KeystoreCrypto {
+                cipher: "aes-128-ctr".to_string(),
+                cipherparams: CipherParams {
+                    iv: hex::encode(iv),
+                },
+                mac: hex::encode(mac(&derived_key, &ciphertext)),
+                ciphertext: hex::encode(ciphertext),
+                kdf: "scrypt".to_string(),
+                kdfparams: KdfParams {
+                    dklen: 32,
+                    n: 1 << params.log_n,
+                    r: params.r,
+                    p: params.p,
+                    salt: hex::encode(salt),
+                },
+            },
+            mnemonic,
+        })
+    }
+
+    /// Decrypts the secret key and mnemonic, failing if the passphrase is wrong.
+    pub fn decrypt(&self, passphrase: &str) -> anyhow::Result<DecryptedKeystore> {
+        let crypto = &self.crypto;
+        if self.version != 3 {
+            bail!("unsupported keystore version {}", self.version);
+        }
+        if crypto.kdf != "scrypt" {
+            bail!("unsupported keystore kdf '{}'", crypto.kdf);
+        }
+        if crypto.cipher != "aes-128-ctr" {
+            bail!("unsupported keystore cipher '{}'", crypto.cipher);
+        }
+        let kdfparams = &crypto.kdfparams;
+        if !kdfparams.n.is_power_of_two() || kdfparams.dklen != 32 {
+            bail!(
+                "unsupported scrypt parameters n = {}, dklen = {}",
+                kdfparams.n,
+                kdfparams.dklen
+            );
+        }
+
+        let decode = |field: &str, value: &str| {
+            hex::decode(value)
+                .map_err(|e| anyhow::anyhow!("keystore {field} is not valid hex: {e:?}"))
+        };
+        let salt = decode("salt", &kdfparams.salt)?;
+        let iv = decode("iv", &crypto.cipherparams.iv)?;
+        let plaintext = decode("ciphertext", &crypto.ciphertext)?;
+        let params = ScryptParams {
+            log_n: kdfparams.n.trailing_zeros() as u8,
+            r: kdfparams.r,
+            p: kdfparams.p,
+        };
+
+        let derived_key = derive_key(passphrase, &salt, params)?;
+        let decrypt =
+            |iv: &[u8], mut plaintext: Vec<u8>, expected_mac: &[u8]| -> anyhow::Result<Vec<u8>> {
+                if !constant_time_eq(&mac(&derived_key, &plaintext), expected_mac) {
+                    bail!("incorrect passphrase, or the keystore has been tampered with");
+                }
+                apply_cipher(&derived_key, iv, &mut plaintext)?;
+                Ok(plaintext)
+            };
+        let secret_key = decrypt(&iv, plaintext, &decode("mac", &crypto.mac)?)?
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("keystore does not hold a 32 byte secret key"))?;
+        let mnemonic = self
+            .mnemonic
+            .as_ref()
+            .map(|mnemonic| {
+                let phrase = decrypt(
+                    &decode("mnemonic iv", &mnemonic.cipherparams.iv)?,
+                    decode("mnemonic ciphertext", &mnemonic.ciphertext)?,
+                    &decode("mnemonic mac", &mnemonic.mac)?,
+                )?;
+                String::from_utf8(phrase)
+                    .map_err(|e| anyhow::anyhow!("keystore mnemonic is not valid UTF-8: {e:?}"))
+            })
+            .transpose()?;
+        Ok(DecryptedKeystore {
+            secret_key,
+            mnemonic,
+        })
+    }
+}
+
+fn derive_key(passphrase: &str, salt: &[u8], params: ScryptParams) -> anyhow::Result<[u8; 32]> {
+    let params = scrypt::Params::new(params.log_n, params.r, params.p, 32)
+        .map_err(|e| anyhow::anyhow!("invalid scrypt parameters: {e:?}"))?;
+    let mut derived_key = [0u8; 32];
+    scrypt::scrypt(passphrase.as_bytes(), salt, &params, &mut derived_key)
+        .map_err(|e| anyhow::anyhow!("failed to derive keystore key: {e:?}"))?;
+    Ok(derived_key)
+}
+
+fn apply_cipher(derived_key: &[u8; 32], iv: &[u8], data: &mut [u8]) -> anyhow::Result<()> {
+    let mut cipher = Aes128Ctr::new_from_slices(&derived_key[..16], iv)
+        .map_err(|e| anyhow::anyhow!("invalid keystore cipher parameters: {e:?}"))?;
+    cipher.apply_keystream(data);
+    Ok(())
+}
+
+fn mac(derived_key: &[u8; 32], ciphertext: &[u8]) -> [u8; 32] {
+    let mut hasher = Keccak256::new();
+    hasher.update(&derived_key[16..]);
+    hasher.update(ciphertext);
+    hasher.finalize().into()
+}
+
+/// Compares two MACs in time independent of where they first differ.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+/// Reads the passphrase for an existing keystore from `LASR_KEYSTORE_PASSPHRASE`,
+/// or prompts for it when running interactively.
+pub fn read_passphrase(prompt: &str) -> anyhow::Result<String> {
+    if let Ok(passphrase) = std::env::var(KEYSTORE_PASSPHRASE_ENV) {
+        return Ok(passphrase);
+    }
+    if !io::stdin().is_terminal() {
+        bail!("{prompt} required, set {KEYSTORE_PASSPHRASE_ENV} when running non-interactively");
+    }
+    rpassword::prompt_password(format!("{prompt}: "))
+        .map_err(|e| anyhow::anyhow!("failed to read passphrase: {e:?}"))
+}
+
+/// Like `read_passphrase`, but asks for the passphrase twice when prompting.
+pub fn new_passphrase() -> anyhow::Result<String> {
+    if let Ok(passphrase) = std::env::var(KEYSTORE_PASSPHRASE_ENV) {
+        if passphrase.is_empty() {
+            bail!("{KEYSTORE_PASSPHRASE_ENV} must not be empty");
+        }
+        return Ok(passphrase);
+    }
+    let passphrase = read_passphrase("New keystore passphrase")?;
+    if passphrase.is_empty() {
+        bail!("the keystore passphrase must not be empty");
+    }
+    if read_passphrase("Repeat passphrase")? != passphrase {
+        bail!("passphrases do not match");
+    }
+    Ok(passphrase)
+}
+
+#[test]
+fn test_keystore_round_trip() -> Result<(), anyhow::Error> {
+    let secret_key = [7u8; 32];
+    let params = ScryptParams {
+        log_n: 4,
+        r: 8,
+        p: 1,
+    };
+    let keystore = Keystore::encrypt(
+        &secret_key,
+        None,
+        "0x2C7536E3605D9C16a7a3D7b1898e529396a65c23",
+        "correct horse",
+        params,
+    )?;
+    assert_eq!(keystore.address, "2c7536e3605d9c16a7a3d7b1898e529396a65c23");
+    assert_eq!(keystore.crypto.kdfparams.n, 16);
+    assert_ne!(keystore.crypto.ciphertext, hex::encode(secret_key));
+
+    assert_eq!(keystore.decrypt("correct horse")?.secret_key, secret_key);
+    assert_eq!(keystore.decrypt("correct horse")?.mnemonic, None);
+    assert!(keystore.decrypt("battery staple").is_err());
+
+    let mnemonic = "test test test test test test test test test test test junk";
+    let keystore = Keystore::encrypt(&secret_key, Some(mnemonic), "0x00", "correct horse", params)?;
+    assert!(!serde_json::to_string(&keystore)?.contains("junk"));
+    let decrypted = keystore.decrypt("correct horse")?;
+    assert_eq!(decrypted.secret_key, secret_key);
+    assert_eq!(decrypted.mnemonic.as_deref(), Some(mnemonic));
+
+    let mut tampered = keystore.clone();
+    if let Some(encrypted) = tampered.mnemonic.as_mut() {
+        encrypted.mac = hex::encode([0u8; 32]);
+    }
+    assert!(tampered.decrypt("correct horse").is_err());
+
+    assert!(constant_time_eq(b"mac", b"mac"));
+    assert!(!constant_time_eq(b"mac", b"max"));
+    assert!(!constant_time_eq(b"mac", b"ma"));
+
+    Ok(())
+}
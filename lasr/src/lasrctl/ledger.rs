@@ -0,0 +1,849 @@
+//! An in-memory stand-in for LASR's account state.
+//!
+//! A `Ledger` applies the `Create`, `Transfer`, `Burn` and `Update`
+//! instructions of a program's `Outputs` with the same checks the protocol
+//! performs (sufficient balances, held token IDs, supply limits), and can
+//! synthesize the `accountInfo` for the next call, so that calls like
+//! create → mint → transfer → burn can be chained without hand-written
+//! account snapshots.
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::Path,
+};
+
+use anyhow::{bail, Context};
+use lasr_types::{Inputs, Outputs, U256};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::lasrctl::builders::program::{parse_u256, Program};
+
+/// A holder's balance, token IDs and token-level fields for one program's token.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenState {
+    pub balance: U256,
+    pub token_ids: BTreeSet<U256>,
+    pub metadata: BTreeMap<String, String>,
+    pub data: BTreeMap<String, String>,
+    /// The token IDs each spender is approved to transfer, as in
+    /// `lasr_types::Token::approvals`.
+    pub approvals: BTreeMap<String, BTreeSet<U256>>,
+}
+
+/// The state of a program account, set by its `create` instruction.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProgramState {
+    pub owner: String,
+    pub total_supply: U256,
+    pub initialized_supply: U256,
+    pub burned: U256,
+    pub metadata: BTreeMap<String, String>,
+    pub data: BTreeMap<String, String>,
+    pub linked_programs: BTreeSet<String>,
+}
+
+/// An account's nonce and token holdings, plus its program state if it is a
+/// program account.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountState {
+    pub nonce: U256,
+    pub tokens: BTreeMap<String, TokenState>,
+    pub program: Option<ProgramState>,
+}
+
+/// In-memory LASR account state, keyed by lowercase `0x` prefixed address.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Ledger {
+    pub accounts: BTreeMap<String, AccountState>,
+}
+
+/// The sender and program of the call whose outputs are being applied, used
+/// to resolve `this`.
+struct CallContext {
+    caller: String,
+    program_id: String,
+}
+
+impl Ledger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads a ledger previously written with `Ledger::save`.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("failed to read ledger state from {path:?}: {e:?}"))?;
+        serde_json::from_str(&contents)
+            .map_err(|e| anyhow::anyhow!("failed to parse ledger state from {path:?}: {e:?}"))
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        fs::write(path, format!("{}\n", serde_json::to_string_pretty(self)?))
+            .map_err(|e| anyhow::anyhow!("failed to write ledger state to {path:?}: {e:?}"))
+    }
+
+    pub fn account(&self, address: &str) -> Option<&AccountState> {
+        self.accounts.get(&normalize(address))
+    }
+
+    pub fn program(&self, address: &str) -> Option<&ProgramState> {
+        self.account(address)
+            .and_then(|account| account.program.as_ref())
+    }
+
+    pub fn token(&self, holder: &str, program: &str) -> Option<&TokenState> {
+        self.account(holder)
+            .and_then(|account| account.tokens.get(&normalize(program)))
+    }
+
+    pub fn balance_of(&self, holder: &str, program: &str) -> U256 {
+        self.token(holder, program)
+            .map(|token| token.balance)
+            .unwrap_or_default()
+    }
+
+    pub fn token_ids_of(&self, holder: &str, program: &str) -> Vec<U256> {
+        self.token(holder, program)
+            .map(|token| token.token_ids.iter().copied().collect())
+            .unwrap_or_default()
+    }
+
+    /// Credits `holder` with `amount` of `program`'s token, e.g. to fund test
+    /// accounts with ETH or VERSE before a scenario runs.
+    pub fn credit(&mut self, holder: &str, program: &str, amount: U256) {
+        let token = self.token_mut(&normalize(holder), &normalize(program));
+        token.balance = token.balance.saturating_add(amount);
+    }
+
+    /// Moves `amount` of `program`'s token from `from` to `to`, as a `send`
+    /// transaction does, and bumps the sender's nonce.
+    pub fn transfer(
+        &mut self,
+        from: &str,
+        to: &str,
+        program: &str,
+        amount: U256,
+    ) -> anyhow::Result<()> {
+        let (from, to, program) = (normalize(from), normalize(to), normalize(program));
+        self.debit(&from, &program, amount, &[])?;
+        self.deposit(&to, &program, amount, &[]);
+        let sender = self.accounts.entry(from).or_default();
+        sender.nonce = sender.nonce.saturating_add(U256::one());
+        Ok(())
+    }
+
+    /// Synthesizes the `accountInfo` the protocol would pass to `program_id`
+    /// when called by `caller`: the caller's holdings alongside the program's
+    /// own metadata, data and linked programs.
+    pub fn account_info(&self, caller: &str, program_id: &str) -> Value {
+        let caller = normalize(caller);
+        let program_id = normalize(program_id);
+        let caller_account = self.accounts.get(&caller).cloned().unwrap_or_default();
+        let program = self
+            .accounts
+            .get(&program_id)
+            .and_then(|account| account.program.clone())
+            .unwrap_or_default();
+
+        let programs: serde_json::Map<String, Value> = caller_account
+            .tokens
+            .iter()
+            .map(|(token_program, token)| {
+                (
+                    token_program.clone(),
+                    json!({
+                        "programId": token_program,
+                        "ownerId": caller,
+                        "balance": hex(token.balance),
+                        "metadata": token.metadata,
+                        "tokenIds": token.token_ids.iter().copied().map(hex).collect::<Vec<_>>(),
+                        "allowance": {},
+                        "approvals": token
+                            .approvals
+                            .iter()
+                            .map(|(spender, ids)| {
+                                (spender.clone(), ids.iter().copied().map(hex).collect::<Vec<_>>())
+                            })
+                            .collect::<BTreeMap<_, _>>(),
+                        "data": token.data,
+                        "status": "free",
+                    }),
+                )
+            })
+            .collect();
+        let owner = if program.owner.is_empty() {
+            caller.clone()
+        } else {
+            program.owner.clone()
+        };
+
+        json!({
+            "accountType": { "program": program_id },
+            "programNamespace": null,
+            "ownerAddress": owner,
+            "programs": programs,
+            "nonce": hex(caller_account.nonce),
+            "programAccountData": program.data,
+            "programAccountMetadata": program.metadata,
+            "programAccountLinkedPrograms": program.linked_programs,
+        })
+    }
+
+    /// Replaces the `accountInfo` of `inputs` with one synthesized from the
+    /// ledger for the transaction's sender and program.
+    pub fn prepare_inputs(&self, inputs: &Inputs) -> anyhow::Result<Inputs> {
+        let mut value = serde_json::to_value(inputs)?;
+        let caller = string_at(&value, "/transaction/from")?;
+        let program_id = string_at(&value, "/transaction/programId")?;
+        value["accountInfo"] = self.account_info(&caller, &program_id);
+        serde_json::from_value(value)
+            .map_err(|e| anyhow::anyhow!("failed to synthesize accountInfo: {e:?}"))
+    }
+
+    /// Builds unsigned `Inputs` for a call of `op` on `program_id` by
+    /// `caller`, using the caller's next nonce and a synthesized `accountInfo`.
+    pub fn call_inputs(
+        &self,
+        caller: &str,
+        program_id: &str,
+        op: &str,
+        transaction_inputs: &str,
+        value: U256,
+    ) -> anyhow::Result<Inputs> {
+        let nonce = self
+            .account(caller)
+            .map(|account| account.nonce)
+            .unwrap_or_default();
+        let inputs = call_inputs_json(
+            self.account_info(caller, program_id),
+            caller,
+            program_id,
+            op,
+            transaction_inputs,
+            value,
+            nonce,
+        );
+        serde_json::from_value(inputs)
+            .map_err(|e| anyhow::anyhow!("failed to build inputs for op '{op}': {e:?}"))
+    }
+
+    /// Runs `program` in process against `inputs` (with a synthesized
+    /// `accountInfo`) and applies the resulting outputs.
+    pub fn execute(
+        &mut self,
+        program: &Program<Inputs>,
+        inputs: &Inputs,
+    ) -> anyhow::Result<Outputs> {
+        let inputs = self.prepare_inputs(inputs)?;
+        let outputs = program.execute(&inputs)?;
+        self.apply(&outputs)?;
+        Ok(outputs)
+    }
+
+    /// Applies every instruction in `outputs`. If any instruction is rejected,
+    /// the ledger is left unchanged.
+    pub fn apply(&mut self, outputs: &Outputs) -> anyhow::Result<()> {
+        self.apply_value(&serde_json::to_value(outputs)?)
+    }
+
+    /// Like `Ledger::apply`, for outputs that have already been serialized.
+    pub fn apply_value(&mut self, outputs: &Value) -> anyhow::Result<()> {
+        let ctx = CallContext {
+            caller: normalize(&string_at(outputs, "/computeInputs/transaction/from")?),
+            program_id: normalize(&string_at(outputs, "/computeInputs/transaction/programId")?),
+        };
+
+        let mut next = self.clone();
+        let instructions = outputs
+            .get("instructions")
+            .and_then(Value::as_array)
+            .cloned()
+            .unwrap_or_default();
+        for (index, instruction) in instructions.iter().enumerate() {
+            let Some((kind, body)) = instruction.as_object().and_then(|obj| obj.iter().next())
+            else {
+                bail!("instructions[{index}] is not a valid instruction");
+            };
+            match kind.as_str() {
+                "create" => next.apply_create(&ctx, body),
+                "transfer" => next.apply_transfer(&ctx, body),
+                "burn" => next.apply_burn(&ctx, body),
+                "update" => next.apply_update(&ctx, body),
+                "log" => Ok(()),
+                other => Err(anyhow::anyhow!("unsupported instruction '{other}'")),
+            }
+            .with_context(|| format!("instructions[{index}].{kind} was rejected"))?;
+        }
+
+        let caller = next.accounts.entry(ctx.caller.clone()).or_default();
+        caller.nonce = caller.nonce.saturating_add(U256::one());
+        *self = next;
+        Ok(())
+    }
+
+    fn token_mut(&mut self, holder: &str, program: &str) -> &mut TokenState {
+        self.accounts
+            .entry(holder.to_string())
+            .or_default()
+            .tokens
+            .entry(program.to_string())
+            .or_default()
+    }
+
+    fn debit(
+        &mut self,
+        holder: &str,
+        program: &str,
+        amount: U256,
+        ids: &[U256],
+    ) -> anyhow::Result<()> {
+        let balance = self.balance_of(holder, program);
+        if balance < amount {
+            bail!("{holder} holds {balance} of {program}, which is less than {amount}");
+        }
+        let held = self.token_ids_of(holder, program);
+        if let Some(missing) = ids.iter().find(|id| !held.contains(id)) {
+            bail!("{holder} does not hold token ID {missing} of {program}");
+        }
+
+        let token = self.token_mut(holder, program);
+        token.balance = balance - amount;
+        for id in ids {
+            token.token_ids.remove(id);
+        }
+        Ok(())
+    }
+
+    fn deposit(&mut self, holder: &str, program: &str, amount: U256, ids: &[U256]) {
+        let token = self.token_mut(holder, program);
+        token.balance = token.balance.saturating_add(amount);
+        token.token_ids.extend(ids.iter().copied());
+    }
+
+    fn apply_create(&mut self, ctx: &CallContext, create: &Value) -> anyhow::Result<()> {
+        let program = resolve(ctx, field(create, "programId")?)?;
+        if self.program(&program).is_some() {
+            bail!("program {program} has already been created");
+        }
+        let total_supply = amount(create, "totalSupply")?;
+        let initialized_supply = amount(create, "initializedSupply")?;
+        if initialized_supply > total_supply {
+            bail!("initializedSupply {initialized_supply} exceeds totalSupply {total_supply}");
+        }
+        let owner = normalize(
+            create
+                .get("programOwner")
+                .and_then(Value::as_str)
+                .unwrap_or(&ctx.caller),
+        );
+
+        let mut distributed = U256::zero();
+        let distributions = create
+            .get("distribution")
+            .and_then(Value::as_array)
+            .cloned()
+            .unwrap_or_default();
+        for distribution in &distributions {
+            let to = resolve(ctx, field(distribution, "to")?)?;
+            let amount = amount(distribution, "amount")?;
+            distributed = distributed.saturating_add(amount);
+            if distributed > initialized_supply {
+                bail!("distributions exceed initializedSupply {initialized_supply}");
+            }
+            self.deposit(&to, &program, amount, &token_ids(distribution)?);
+        }
+        // Everything up to totalSupply that was not distributed is held by
+        // the program itself, since mint pays callers from `this` until
+        // currentSupply reaches totalSupply, not just initializedSupply.
+        self.deposit(&program, &program, total_supply - distributed, &[]);
+
+        self.accounts.entry(program).or_default().program = Some(ProgramState {
+            owner,
+            total_supply,
+            initialized_supply,
+            ..Default::default()
+        });
+        Ok(())
+    }
+
+    fn apply_transfer(&mut self, ctx: &CallContext, transfer: &Value) -> anyhow::Result<()> {
+        let token = resolve(ctx, field(transfer, "token")?)?;
+        let from = resolve(ctx, field(transfer, "from")?)?;
+        let to = resolve(ctx, field(transfer, "to")?)?;
+        let amount = amount(transfer, "amount")?;
+        let ids = token_ids(transfer)?;
+
+        self.debit(&from, &token, amount, &ids)?;
+        self.deposit(&to, &token, amount, &ids);
+        Ok(())
+    }
+
+    fn apply_burn(&mut self, ctx: &CallContext, burn: &Value) -> anyhow::Result<()> {
+        let token = resolve(ctx, field(burn, "token")?)?;
+        let from = resolve(ctx, field(burn, "from")?)?;
+        let amount = amount(burn, "amount")?;
+        let ids = token_ids(burn)?;
+
+        self.debit(&from, &token, amount, &ids)?;
+        if let Some(program) = self
+            .accounts
+            .get_mut(&token)
+            .and_then(|account| account.program.as_mut())
+        {
+            program.burned = program.burned.saturating_add(amount);
+        }
+        Ok(())
+    }
+
+    fn apply_update(&mut self, ctx: &CallContext, update: &Value) -> anyhow::Result<()> {
+        let updates = update
+            .get("updates")
+            .and_then(Value::as_array)
+            .cloned()
+            .unwrap_or_default();
+        for update in &updates {
+            if let Some(program_update) = update.get("programUpdate") {
+                let account = resolve(ctx, field(program_update, "account")?)?;
+                let program = self
+                    .accounts
+                    .get_mut(&account)
+                    .and_then(|account| account.program.as_mut())
+                    .ok_or_else(|| anyhow::anyhow!("program {account} has not been created"))?;
+                for field_update in field_updates(program_update) {
+                    let value = field(&field_update, "value")?;
+                    if let Some(op) = value.get("metadata") {
+                        apply_map_op(&mut program.metadata, op)?;
+                    } else if let Some(op) = value.get("data") {
+                        apply_map_op(&mut program.data, op)?;
+                    } else if let Some(op) = value.get("linkedPrograms") {
+                        apply_set_op(&mut program.linked_programs, op)?;
+                    } else {
+                        bail!("unsupported program update value {value}");
+                    }
+                }
+            } else if let Some(token_update) = update.get("tokenUpdate") {
+                let holder = resolve(ctx, field(token_update, "account")?)?;
+                let program = resolve(ctx, field(token_update, "token")?)?;
+                let token = self.token_mut(&holder, &program);
+                for field_update in field_updates(token_update) {
+                    let value = field(&field_update, "value")?;
+                    let target = match field_update.get("field").and_then(Value::as_str) {
+                        Some("approvals") => {
+                            apply_approvals_op(&mut token.approvals, value)?;
+                            continue;
+                        }
+                        Some("metadata") => &mut token.metadata,
+                        _ => &mut token.data,
+                    };
+                    match value
+                        .get("data")
+                        .or_else(|| value.get("metadata"))
+                        .or_else(|| value.get("approvals"))
+                    {
+                        Some(op) => apply_map_op(target, op)?,
+                        None => bail!("unsupported token update value {value}"),
+                    }
+                }
+            } else {
+                bail!("unsupported update {update}");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Lowercases an address so the same account is always found under one key.
+pub fn normalize(address: &str) -> String {
+    address.trim().to_lowercase()
+}
+
+/// Builds serialized, unsigned `Inputs` for a call of `op` on `program_id`.
+/// `transaction_inputs` is embedded as a string, as the protocol expects.
+pub fn call_inputs_json(
+    account_info: Value,
+    caller: &str,
+    program_id: &str,
+    op: &str,
+    transaction_inputs: &str,
+    value: U256,
+    nonce: U256,
+) -> Value {
+    json!({
+        "version": 1,
+        "accountInfo": account_info,
+        "transaction": {
+            "transactionType": { "call": hex(nonce) },
+            "from": normalize(caller),
+            "to": normalize(program_id),
+            "programId": normalize(program_id),
+            "op": op,
+            "transactionInputs": transaction_inputs,
+            "value": hex(value),
+            "nonce": hex(nonce),
+            "v": 0,
+            "r": hex(U256::zero()),
+            "s": hex(U256::zero()),
+        },
+        "op": op,
+        "contractInputs": "{}",
+    })
+}
+
+fn hex(value: U256) -> String {
+    format!("0x{value:064x}")
+}
+
+fn field<'a>(value: &'a Value, name: &str) -> anyhow::Result<&'a Value> {
+    value
+        .get(name)
+        .ok_or_else(|| anyhow::anyhow!("missing field '{name}'"))
+}
+
+fn string_at(value: &Value, pointer: &str) -> anyhow::Result<String> {
+    value
+        .pointer(pointer)
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .ok_or_else(|| anyhow::anyhow!("missing string at {pointer}"))
+}
+
+fn amount(value: &Value, name: &str) -> anyhow::Result<U256> {
+    match value.get(name) {
+        None | Some(Value::Null) => Ok(U256::zero()),
+        Some(Value::String(amount)) => parse_u256(amount),
+        Some(other) => bail!("'{name}' is not a U256: {other}"),
+    }
+}
+
+fn token_ids(value: &Value) -> anyhow::Result<Vec<U256>> {
+    value
+        .get("tokenIds")
+        .or_else(|| value.get("ids"))
+        .and_then(Value::as_array)
+        .map(|ids| {
+            ids.iter()
+                .map(|id| match id {
+                    Value::String(id) => parse_u256(id),
+                    other => bail!("token ID is not a U256: {other}"),
+                })
+                .collect()
+        })
+        .unwrap_or_else(|| Ok(Vec::new()))
+}
+
+fn field_updates(update: &Value) -> Vec<Value> {
+    update
+        .get("updates")
+        .and_then(Value::as_array)
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// Resolves an `AddressOrNamespace` to the address of the account it refers to.
+fn resolve(ctx: &CallContext, value: &Value) -> anyhow::Result<String> {
+    match value {
+        Value::String(this) if this == "this" => Ok(ctx.program_id.clone()),
+        Value::String(address) => Ok(normalize(address)),
+        Value::Object(obj) => match obj.get("address").and_then(Value::as_str) {
+            Some(address) => Ok(normalize(address)),
+            None => bail!("unsupported account reference {value}"),
+        },
+        _ => bail!("unsupported account reference {value}"),
+    }
+}
+
+/// Applies a serialized `MetadataValue` or `DataValue` to `map`.
+fn apply_map_op(map: &mut BTreeMap<String, String>, op: &Value) -> anyhow::Result<()> {
+    let Some((kind, args)) = op.as_object().and_then(|obj| obj.iter().next()) else {
+        bail!("unsupported map update {op}");
+    };
+    match kind.as_str() {
+        "insert" => {
+            let (key, value): (String, String) = serde_json::from_value(args.clone())?;
+            map.insert(key, value);
+        }
+        "extend" => map.extend(serde_json::from_value::<BTreeMap<String, String>>(
+            args.clone(),
+        )?),
+        "remove" => {
+            map.remove(args.as_str().unwrap_or_default());
+        }
+        "replaceAll" => *map = serde_json::from_value(args.clone())?,
+        other => bail!("unsupported map update '{other}'"),
+    }
+    Ok(())
+}
+
+/// Applies an update of a token's approvals: either a serialized
+/// `ApprovalsValue`, or the `approve` strategy's `Insert("approvals", ..)` of
+/// its raw `[[spender, [tokenId, ..]], ..]` transaction inputs.
+fn apply_approvals_op(
+    approvals: &mut BTreeMap<String, BTreeSet<U256>>,
+    value: &Value,
+) -> anyhow::Result<()> {
+    let parse_ids = |ids: &Value| -> anyhow::Result<Vec<U256>> {
+        serde_json::from_value::<Vec<String>>(ids.clone())?
+            .iter()
+            .map(|id| parse_u256(id))
+            .collect()
+    };
+    let mut approve = |spender: &str, ids: &Value| -> anyhow::Result<()> {
+        approvals
+            .entry(normalize(spender))
+            .or_default()
+            .extend(parse_ids(ids)?);
+        Ok(())
+    };
+
+    if let Some(op) = value.get("data").or_else(|| value.get("metadata")) {
+        let (_, pairs): (String, String) = serde_json::from_value(
+            op.get("insert")
+                .cloned()
+                .ok_or_else(|| anyhow::anyhow!("unsupported approvals update {value}"))?,
+        )?;
+        let pairs: Vec<(String, Value)> = serde_json::from_str(&pairs)
+            .map_err(|e| anyhow::anyhow!("approvals are not [spender, [tokenId]] pairs: {e:?}"))?;
+        for (spender, ids) in &pairs {
+            approve(spender, ids)?;
+        }
+        return Ok(());
+    }
+
+    let Some((kind, args)) = value
+        .get("approvals")
+        .and_then(Value::as_object)
+        .and_then(|obj| obj.iter().next())
+    else {
+        bail!("unsupported approvals update {value}");
+    };
+    match kind.as_str() {
+        "insert" => {
+            let (spender, ids): (String, Value) = serde_json::from_value(args.clone())?;
+            approve(&spender, &ids)?;
+        }
+        "extend" => {
+            for (spender, ids) in serde_json::from_value::<Vec<(String, Value)>>(args.clone())? {
+                approve(&spender, &ids)?;
+            }
+        }
+        "remove" => {
+            let (spender, ids): (String, Value) = serde_json::from_value(args.clone())?;
+            let ids = parse_ids(&ids)?;
+            if let Some(approved) = approvals.get_mut(&normalize(&spender)) {
+                approved.retain(|id| !ids.contains(id));
+            }
+        }
+        "revoke" => {
+            approvals.remove(&normalize(args.as_str().unwrap_or_default()));
+        }
+        other => bail!("unsupported approvals update '{other}'"),
+    }
+    Ok(())
+}
+
+/// Applies a serialized `LinkedProgramsValue` to `set`.
+fn apply_set_op(set: &mut BTreeSet<String>, op: &Value) -> anyhow::Result<()> {
+    let Some((kind, args)) = op.as_object().and_then(|obj| obj.iter().next()) else {
+        bail!("unsupported linked programs update {op}");
+    };
+    let addresses: Vec<String> = match args {
+        Value::String(address) => vec![normalize(address)],
+        other => serde_json::from_value::<Vec<String>>(other.clone())?
+            .iter()
+            .map(|address| normalize(address))
+            .collect(),
+    };
+    match kind.as_str() {
+        "insert" | "extend" => set.extend(addresses),
+        "remove" => addresses.iter().for_each(|address| {
+            set.remove(address);
+        }),
+        other => bail!("unsupported linked programs update '{other}'"),
+    }
+    Ok(())
+}
+
+#[test]
+fn test_ledger_create_mint_burn() -> Result<(), anyhow::Error> {
+    const CALLER: &str = "0x100444c7d04a842d19bc3ee63cb7b96682ff3f43";
+    const PROGRAM: &str = "0x57234c52617e7ca8edc5577ebe3eb38d53a77607";
+    const ETH: &str = "0x0000000000000000000000000000000000000000";
+
+    let call = |op: &str, value: &str, instructions: Value| {
+        json!({
+            "computeInputs": {
+                "op": op,
+                "transaction": { "from": CALLER, "programId": PROGRAM, "value": value }
+            },
+            "instructions": instructions,
+        })
+    };
+
+    let mut ledger = Ledger::new();
+    ledger.credit(CALLER, ETH, U256::from(10));
+
+    ledger.apply_value(&call(
+        "create",
+        "0x0",
+        json!([{ "create": {
+            "programNamespace": "this",
+            "programId": "this",
+            "programOwner": CALLER,
+            "totalSupply": "0x64",
+            "initializedSupply": "0x64",
+            "distribution": []
+        } }]),
+    ))?;
+    assert_eq!(ledger.balance_of(PROGRAM, PROGRAM), U256::from(100));
+
+    ledger.apply_value(&call(
+        "mint",
+        "0x5",
+        json!([
+            { "transfer": { "token": ETH, "from": { "address": CALLER }, "to": "this", "amount": "0x5", "ids": [] } },
+            { "transfer": { "token": PROGRAM, "from": "this", "to": { "address": CALLER }, "amount": "0x5", "ids": [] } }
+        ]),
+    ))?;
+    assert_eq!(ledger.balance_of(CALLER, ETH), U256::from(5));
+    assert_eq!(ledger.balance_of(CALLER, PROGRAM), U256::from(5));
+
+    let overdraw = ledger.apply_value(&call(
+        "burn",
+        "0x6",
+        json!([{ "burn": { "caller": CALLER, "programId": "this", "token": PROGRAM, "from": { "address": CALLER }, "amount": "0x6", "tokenIds": [] } }]),
+    ));
+    assert!(overdraw.is_err());
+    assert_eq!(ledger.balance_of(CALLER, PROGRAM), U256::from(5));
+
+    let account_info = ledger.account_info(CALLER, PROGRAM);
+    assert_eq!(account_info["ownerAddress"], CALLER);
+    assert_eq!(
+        account_info["programs"][PROGRAM]["balance"],
+        format!("0x{:064x}", 5)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_ledger_approve_then_call() -> Result<(), anyhow::Error> {
+    const CALLER: &str = "0x100444c7d04a842d19bc3ee63cb7b96682ff3f43";
+    const SPENDER: &str = "0x482830d7655fb8465a43844fc1530a7713781b49";
+    const PROGRAM: &str = "0x57234c52617e7ca8edc5577ebe3eb38d53a77607";
+
+    let program = Program::new();
+    let mut ledger = Ledger::new();
+    let mut call = |op: &str, transaction_inputs: &str| -> anyhow::Result<Outputs> {
+        let inputs = ledger.call_inputs(CALLER, PROGRAM, op, transaction_inputs, U256::zero())?;
+        ledger.execute(&program, &inputs)
+    };
+
+    call(
+        "create",
+        r#"{"name":"HelloToken","symbol":"HLLO","totalSupply":"100","initializedSupply":"100"}"#,
+    )?;
+    call("approve", &format!(r#"[["{SPENDER}",["0x1","0x2"]]]"#))?;
+    // The next call's accountInfo must still deserialize with the approvals.
+    call("update", r#"{"metadata":{"symbol":"SNICKERS"}}"#)?;
+
+    let approved: BTreeSet<U256> = [U256::from(1), U256::from(2)].into();
+    assert_eq!(
+        ledger
+            .token(CALLER, PROGRAM)
+            .and_then(|token| token.approvals.get(SPENDER)),
+        Some(&approved)
+    );
+    assert_eq!(
+        ledger.account_info(CALLER, PROGRAM)["programs"][PROGRAM]["approvals"],
+        json!({ SPENDER: [format!("0x{:064x}", 1), format!("0x{:064x}", 2)] })
+    );
+    Ok(())
+}
+
+#[test]
+fn test_ledger_create_then_mint() -> Result<(), anyhow::Error> {
+    use crate::lasrctl::builders::error::{ProgramError, ProgramErrorKind};
+
+    const CALLER: &str = "0x100444c7d04a842d19bc3ee63cb7b96682ff3f43";
+    const PROGRAM: &str = "0x57234c52617e7ca8edc5577ebe3eb38d53a77607";
+    const ETH: &str = "0x0000000000000000000000000000000000000000";
+
+    let program = Program::new();
+    let mut ledger = Ledger::new();
+    ledger.credit(CALLER, ETH, U256::from(5000));
+
+    let create = ledger.call_inputs(
+        CALLER,
+        PROGRAM,
+        "create",
+        r#"{"name":"HelloToken","symbol":"HLLO","totalSupply":"3e8","initializedSupply":"0"}"#,
+        U256::zero(),
+    )?;
+    ledger.execute(&program, &create)?;
+    let state = ledger.program(PROGRAM).expect("program was created");
+    assert_eq!(state.total_supply, U256::from(1000));
+    assert_eq!(
+        state.metadata.get("totalSupply").map(String::as_str),
+        Some("0x3e8")
+    );
+
+    // mint reads the supply create stored, so exactly totalSupply may be minted.
+    let mint = |value: u64| -> anyhow::Result<Outputs> {
+        let inputs = ledger.call_inputs(CALLER, PROGRAM, "mint", "{}", U256::from(value))?;
+        program.execute(&inputs)
+    };
+    assert!(mint(1000).is_ok());
+    let err = ProgramError::from(mint(1001).unwrap_err());
+    assert_eq!(err.kind, ProgramErrorKind::Rejected);
+    Ok(())
+}
+
+#[test]
+fn test_ledger_mint_past_initialized_supply() -> Result<(), anyhow::Error> {
+    use crate::lasrctl::builders::error::{ProgramError, ProgramErrorKind};
+
+    const CALLER: &str = "0x100444c7d04a842d19bc3ee63cb7b96682ff3f43";
+    const PROGRAM: &str = "0x57234c52617e7ca8edc5577ebe3eb38d53a77607";
+    const ETH: &str = "0x0000000000000000000000000000000000000000";
+
+    let program = Program::new();
+    let mut ledger = Ledger::new();
+    ledger.credit(CALLER, ETH, U256::from(1000));
+
+    let create = ledger.call_inputs(
+        CALLER,
+        PROGRAM,
+        "create",
+        r#"{"name":"HelloToken","symbol":"HLLO","totalSupply":"3e8","initializedSupply":"64"}"#,
+        U256::zero(),
+    )?;
+    ledger.execute(&program, &create)?;
+    assert_eq!(ledger.balance_of(PROGRAM, PROGRAM), U256::from(1000));
+
+    // Mints are applied to the ledger, so the program must hold what it
+    // pays out: 500 is past initializedSupply but within totalSupply.
+    let mint = ledger.call_inputs(CALLER, PROGRAM, "mint", "{}", U256::from(500))?;
+    ledger.execute(&program, &mint)?;
+    assert_eq!(ledger.balance_of(CALLER, PROGRAM), U256::from(500));
+    assert_eq!(
+        ledger
+            .program(PROGRAM)
+            .and_then(|state| state.data.get("currentSupply"))
+            .map(String::as_str),
+        Some("600")
+    );
+
+    // currentSupply started at initializedSupply, so only 400 remain.
+    let mint = ledger.call_inputs(CALLER, PROGRAM, "mint", "{}", U256::from(401))?;
+    let err = ProgramError::from(ledger.execute(&program, &mint).unwrap_err());
+    assert_eq!(err.kind, ProgramErrorKind::Rejected);
+    let mint = ledger.call_inputs(CALLER, PROGRAM, "mint", "{}", U256::from(400))?;
+    ledger.execute(&program, &mint)?;
+    assert_eq!(ledger.balance_of(CALLER, PROGRAM), U256::from(900));
+    Ok(())
+}
@@ -1,5 +1,19 @@
 pub mod builders;
 pub mod cli;
+pub mod determinism;
+pub mod explain;
+pub mod fuzz;
+pub mod keystore;
+pub mod ledger;
+pub mod node;
+pub mod profile;
+pub mod rpc;
+pub mod scenario;
+pub mod signing;
+pub mod templates;
+pub mod validate;
+pub mod wallet;
+pub mod watch;
 
 #[macro_export]
 macro_rules! cargo {
@@ -0,0 +1,454 @@
+//! A local stand-in for a LASR node, serving the `lasr_rpc` methods over
+//! JSON-RPC so deploy, call and send can be exercised offline.
+//!
+//! Registered programs are the paths of locally built program binaries,
+//! which are run as subprocesses for every call. Account state lives in a
+//! `Ledger`, optionally persisted to disk after every transaction.
+//!
+//! Like a node, transactions are only accepted when they are signed by their
+//! `from` address and carry that account's current nonce, so a transaction
+//! can't be replayed.
+use std::{
+    collections::BTreeMap,
+    fs,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use anyhow::bail;
+use jsonrpsee::{
+    server::Server,
+    types::{error::CALL_EXECUTION_FAILED_CODE, ErrorObjectOwned},
+    RpcModule,
+};
+use lasr_types::U256;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha3::{Digest, Keccak256};
+
+use crate::{
+    lasrctl::{
+        builders::{error::ProgramError, program::parse_u256},
+        cli::commands::test::{failure_reason, run_program},
+        ledger::{call_inputs_json, normalize, Ledger},
+        signing::{transaction_hash, verify_transaction},
+    },
+    scripts::consts::{ETH_PROGRAM_ADDRESS, VERSE_PROGRAM_ADDRESS},
+};
+
+/// The JSON-RPC method crediting an address from the local faucet.
+pub const FAUCET_METHOD: &str = "lasr_faucet";
+
+/// The amount of ETH and VERSE the faucet credits per request: 1 token with
+/// 18 decimals.
+pub fn faucet_amount() -> U256 {
+    U256::exp10(18)
+}
+
+/// The state of a local node: its ledger and the binaries of its registered
+/// programs, keyed by program address.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeState {
+    pub ledger: Ledger,
+    pub programs: BTreeMap<String, PathBuf>,
+}
+
+/// A local node, keeping its state in memory or in a JSON file.
+#[derive(Debug, Default)]
+pub struct LocalNode {
+    pub state: NodeState,
+    state_path: Option<PathBuf>,
+}
+
+impl LocalNode {
+    /// A node whose state is kept in memory only.
+    pub fn in_memory() -> Self {
+        Self::default()
+    }
+
+    /// A node whose state is loaded from, and saved after every transaction
+    /// to, `path`. A missing file starts an empty node.
+    pub fn persistent(path: &Path) -> anyhow::Result<Self> {
+        let state = if path.exists() {
+            let contents = fs::read_to_string(path)
+                .map_err(|e| anyhow::anyhow!("failed to read node state from {path:?}: {e:?}"))?;
+            serde_json::from_str(&contents)
+                .map_err(|e| anyhow::anyhow!("failed to parse node state from {path:?}: {e:?}"))?
+        } else {
+            NodeState::default()
+        };
+        Ok(Self {
+            state,
+            state_path: Some(path.to_path_buf()),
+        })
+    }
+
+    fn save(&self) -> anyhow::Result<()> {
+        let Some(path) = &self.state_path else {
+            return Ok(());
+        };
+        fs::write(
+            path,
+            format!("{}\n", serde_json::to_string_pretty(&self.state)?),
+        )
+        .map_err(|e| anyhow::anyhow!("failed to write node state to {path:?}: {e:?}"))
+    }
+
+    /// Checks that `transaction` was signed by its `from` address and carries
+    /// that account's current nonce. Returns the sender.
+    pub fn authorize(&self, transaction: &Value) -> anyhow::Result<String> {
+        let from = verify_transaction(transaction)?;
+        let expected = self
+            .state
+            .ledger
+            .account(&from)
+            .map(|account| account.nonce)
+            .unwrap_or_default();
+        let nonce = amount_field(transaction, "nonce")?;
+        if nonce < expected {
+            bail!("nonce {nonce} of {from} is stale, expected {expected}");
+        }
+        if nonce > expected {
+            bail!("nonce {nonce} of {from} is ahead of the account, expected {expected}");
+        }
+        Ok(from)
+    }
+
+    /// Registers the program binary at the local path named by the
+    /// transaction's `transactionInputs`, `{"binaryPath": ..}`. Returns the
+    /// program's address.
+    pub fn register_program(&mut self, transaction: &Value) -> anyhow::Result<String> {
+        let from = self.authorize(transaction)?;
+        let program_inputs: Value =
+            serde_json::from_str(&string_field(transaction, "transactionInputs")?)
+                .map_err(|e| anyhow::anyhow!("transactionInputs are not valid JSON: {e:?}"))?;
+        let Some(binary) = program_inputs.get("binaryPath").and_then(Value::as_str) else {
+            if let Some(content_id) = program_inputs.get("contentId") {
+                bail!(
+                    "the local node cannot fetch published package {content_id}, name the built program with binaryPath"
+                );
+            }
+            bail!("transactionInputs must name the program binary with binaryPath");
+        };
+        let binary = fs::canonicalize(binary)
+            .map_err(|e| anyhow::anyhow!("program binary {binary:?} was not found: {e:?}"))?;
+
+        // Like contract addresses, derived from the deployer and its nonce.
+        let nonce = self
+            .state
+            .ledger
+            .account(&from)
+            .map(|account| account.nonce)
+            .unwrap_or_default();
+        let hash = Keccak256::digest(format!("{}:{nonce:#x}", normalize(&from)));
+        let address = format!("0x{}", hex::encode(&hash[12..]));
+
+        self.state.programs.insert(address.clone(), binary);
+        let ledger = &mut self.state.ledger;
+        ledger.accounts.entry(address.clone()).or_default();
+        let deployer = ledger.accounts.entry(normalize(&from)).or_default();
+        deployer.nonce = deployer.nonce.saturating_add(U256::one());
+        self.save()?;
+        Ok(address)
+    }
+
+    /// Runs the called program's binary against inputs built from the ledger
+    /// and applies its outputs. Returns the transaction hash.
+    pub fn call(&mut self, transaction: &Value) -> anyhow::Result<String> {
+        let from = self.authorize(transaction)?;
+        let program_id = normalize(&string_field(transaction, "programId")?);
+        let op = string_field(transaction, "op")?;
+        let Some(binary) = self.state.programs.get(&program_id) else {
+            bail!("program {program_id} has not been registered");
+        };
+
+        let ledger = &self.state.ledger;
+        let nonce = ledger
+            .account(&from)
+            .map(|account| account.nonce)
+            .unwrap_or_default();
+        let mut inputs = call_inputs_json(
+            ledger.account_info(&from, &program_id),
+            &from,
+            &program_id,
+            &op,
+            &string_field(transaction, "transactionInputs")?,
+            amount_field(transaction, "value")?,
+            nonce,
+        );
+        // Pass the transaction as sent, so programs see its signature.
+        inputs["transaction"] = transaction.clone();
+
+        let output = run_program(binary, &serde_json::to_string(&inputs)?)?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        if !output.status.success() {
+            match ProgramError::from_json(&stdout) {
+                Some(program_error) => bail!("program rejected the call: {program_error}"),
+                None => bail!(failure_reason(&output)),
+            }
+        }
+        let outputs: Value = serde_json::from_str(&stdout)
+            .map_err(|e| anyhow::anyhow!("program output is not valid JSON: {e:?}"))?;
+        self.state.ledger.apply_value(&outputs)?;
+        self.save()?;
+        hash_hex(transaction)
+    }
+
+    /// Transfers the transaction's `value` of the `programId` token to `to`.
+    /// Returns the transaction hash.
+    pub fn send(&mut self, transaction: &Value) -> anyhow::Result<String> {
+        let from = self.authorize(transaction)?;
+        self.state.ledger.transfer(
+            &from,
+            &string_field(transaction, "to")?,
+            &string_field(transaction, "programId")?,
+            amount_field(transaction, "value")?,
+        )?;
+        self.save()?;
+        hash_hex(transaction)
+    }
+
+    /// The serialized `lasr_types::Account` at `address`, as a JSON string
+    /// like the nodes return it.
+    pub fn get_account(&self, address: &str) -> anyhow::Result<String> {
+        if self.state.ledger.account(address).is_none() {
+            bail!("account {address} does not exist");
+        }
+        let mut account = self.state.ledger.account_info(address, address);
+        if self.state.ledger.program(address).is_none() {
+            account["accountType"] = Value::String("user".to_string());
+        }
+        Ok(serde_json::to_string(&account)?)
+    }
+
+    /// Credits `address` with `faucet_amount` of ETH and VERSE.
+    pub fn faucet(&mut self, address: &str) -> anyhow::Result<()> {
+        for token in [ETH_PROGRAM_ADDRESS, VERSE_PROGRAM_ADDRESS] {
+            self.state.ledger.credit(address, token, faucet_amount());
+        }
+        self.save()
+    }
+}
+
+fn string_field(transaction: &Value, field: &str) -> anyhow::Result<String> {
+    match transaction.get(field) {
+        Some(Value::String(value)) => Ok(value.clone()),
+        Some(other) => bail!("transaction field '{field}' is not a string: {other}"),
+        None => bail!("transaction is missing '{field}'"),
+    }
+}
+
+fn amount_field(transaction: &Value, field: &str) -> anyhow::Result<U256> {
+    match transaction.get(field) {
+        Some(Value::String(value)) => parse_u256(value),
+        Some(other) => bail!("transaction field '{field}' is not a string: {other}"),
+        None => Ok(U256::zero()),
+    }
+}
+
+fn hash_hex(transaction: &Value) -> anyhow::Result<String> {
+    Ok(format!("0x{}", hex::encode(transaction_hash(transaction)?)))
+}
+
+fn rpc_error(e: anyhow::Error) -> ErrorObjectOwned {
+    ErrorObjectOwned::owned(CALL_EXECUTION_FAILED_CODE, format!("{e:#}"), None::<()>)
+}
+
+/// Registers the `lasr_rpc` methods, plus the faucet, for `node`.
+pub fn rpc_module(node: LocalNode) -> anyhow::Result<RpcModule<Mutex<LocalNode>>> {
+    type Handler = fn(&mut LocalNode, &Value) -> anyhow::Result<String>;
+    let mut module = RpcModule::new(Mutex::new(node));
+
+    let transaction_methods: [(&'static str, Handler); 3] = [
+        ("lasr_call", LocalNode::call),
+        ("lasr_send", LocalNode::send),
+        ("lasr_registerProgram", LocalNode::register_program),
+    ];
+    for (method, handler) in transaction_methods {
+        module
+            .register_blocking_method(method, move |params, node: Arc<Mutex<LocalNode>>| {
+                let transaction: Value = params.one()?;
+                let mut node = node
+                    .lock()
+                    .map_err(|_| rpc_error(anyhow::anyhow!("node state is poisoned")))?;
+                handler(&mut node, &transaction).map_err(rpc_error)
+            })
+            .map_err(|e| anyhow::anyhow!("failed to register {method}: {e:?}"))?;
+    }
+    module
+        .register_method("lasr_getAccount", |params, node| {
+            let address: String = params.one()?;
+            let node = node
+                .lock()
+                .map_err(|_| rpc_error(anyhow::anyhow!("node state is poisoned")))?;
+            node.get_account(&address).map_err(rpc_error)
+        })
+        .map_err(|e| anyhow::anyhow!("failed to register lasr_getAccount: {e:?}"))?;
+    module
+        .register_method(FAUCET_METHOD, |params, node| {
+            let address: String = params.one()?;
+            let mut node = node
+                .lock()
+                .map_err(|_| rpc_error(anyhow::anyhow!("node state is poisoned")))?;
+            node.faucet(&address).map_err(rpc_error)?;
+            Ok::<_, ErrorObjectOwned>(format!("{:#x}", faucet_amount()))
+        })
+        .map_err(|e| anyhow::anyhow!("failed to register {FAUCET_METHOD}: {e:?}"))?;
+    Ok(module)
+}
+
+/// Serves `node` on `addr` until the process is stopped.
+pub async fn serve(node: LocalNode, addr: SocketAddr) -> anyhow::Result<()> {
+    let server = Server::builder()
+        .build(addr)
+        .await
+        .map_err(|e| anyhow::anyhow!("failed to bind local node to {addr}: {e:?}"))?;
+    let handle = server.start(rpc_module(node)?);
+    handle.stopped().await;
+    Ok(())
+}
+
+#[test]
+fn test_local_node() -> Result<(), anyhow::Error> {
+    use crate::lasrctl::signing::{parse_secret_key, sign_transaction};
+
+    let alice_key =
+        parse_secret_key("0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318")?;
+    let alice = "0x2c7536e3605d9c16a7a3d7b1898e529396a65c23";
+    let bob = "0x482830d7655fb8465a43844fc1530a7713781b49";
+    let mut node = LocalNode::in_memory();
+    assert!(node.get_account(alice).is_err());
+
+    node.faucet(alice)?;
+    let signed = |mut transaction: Value| -> anyhow::Result<Value> {
+        sign_transaction(&mut transaction, &alice_key)?;
+        Ok(transaction)
+    };
+    let send = serde_json::json!({
+        "transactionType": { "send": "0x0" },
+        "from": alice,
+        "to": bob,
+        "programId": VERSE_PROGRAM_ADDRESS,
+        "op": "",
+        "transactionInputs": "",
+        "value": "0x10",
+        "nonce": "0x0",
+    });
+    let hash = node.send(&signed(send.clone())?)?;
+    assert_eq!(hash.len(), 66);
+    assert_eq!(
+        node.state.ledger.balance_of(bob, VERSE_PROGRAM_ADDRESS),
+        U256::from(16)
+    );
+
+    let bob_account: Value = serde_json::from_str(&node.get_account(bob)?)?;
+    assert_eq!(bob_account["accountType"], "user");
+    let alice_account: Value = serde_json::from_str(&node.get_account(alice)?)?;
+    assert_eq!(alice_account["nonce"], format!("0x{:064x}", 1));
+
+    // Replaying the send, or skipping ahead, is rejected.
+    let err = node.send(&signed(send.clone())?).unwrap_err();
+    assert!(format!("{err:#}").contains("stale"));
+    let mut ahead = send.clone();
+    ahead["nonce"] = Value::String("0x2".to_string());
+    assert!(node.send(&signed(ahead)?).is_err());
+
+    // A transaction from bob that alice signed is rejected.
+    let mut forged = signed(send.clone())?;
+    forged["from"] = Value::String(bob.to_string());
+    assert!(node.send(&forged).is_err());
+
+    let mut overdraw = send.clone();
+    overdraw["nonce"] = Value::String("0x1".to_string());
+    overdraw["value"] = Value::String(format!("{:#x}", faucet_amount()));
+    assert!(node.send(&signed(overdraw)?).is_err());
+
+    let mut register = send;
+    register["nonce"] = Value::String("0x1".to_string());
+    register["transactionInputs"] = Value::String(r#"{"binaryPath":"Cargo.toml"}"#.to_string());
+    let program = node.register_program(&signed(register.clone())?)?;
+    assert!(node.state.programs.contains_key(&program));
+    register["nonce"] = Value::String("0x2".to_string());
+    assert_ne!(node.register_program(&signed(register)?)?, program);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_rpc_deploy_call_send() -> Result<(), anyhow::Error> {
+    use jsonrpsee::{core::client::ClientT, http_client::HttpClientBuilder, rpc_params};
+
+    use crate::lasrctl::{
+        rpc::{get_account, SigningClient},
+        signing::{parse_secret_key, sign_transaction},
+    };
+
+    // The deployed program is the test_data crate's binary, built here so the
+    // test does not rely on it having been built by hand.
+    let test_data = Path::new(env!("CARGO_MANIFEST_DIR")).join("test_data");
+    let status = std::process::Command::new(env!("CARGO"))
+        .arg("build")
+        .arg("--manifest-path")
+        .arg(test_data.join("Cargo.toml"))
+        .status()?;
+    anyhow::ensure!(status.success(), "failed to build test_data: {status}");
+
+    let server = Server::builder().build("127.0.0.1:0").await?;
+    let url = format!("http://{}", server.local_addr()?);
+    let handle = server.start(rpc_module(LocalNode::in_memory())?);
+
+    let secret_key =
+        parse_secret_key("0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318")?;
+    let alice = SigningClient::new(&url, secret_key);
+    let bob = "0x482830d7655fb8465a43844fc1530a7713781b49";
+    let rpc = HttpClientBuilder::default().build(&url)?;
+    let _: String = rpc
+        .request(FAUCET_METHOD, rpc_params![alice.address()])
+        .await?;
+
+    // Deploy: register the program binary, then create its token.
+    let binary = test_data.join("target/debug/test_data");
+    let program = alice
+        .register_program(&serde_json::json!({ "binaryPath": binary }))
+        .await?;
+    alice
+        .call(
+            &program,
+            "create",
+            r#"{"name":"HelloToken","symbol":"HLLO","totalSupply":"1000","initializedSupply":"500"}"#,
+            U256::zero(),
+        )
+        .await?;
+
+    alice.call(&program, "mint", "{}", U256::from(100)).await?;
+    alice.send(&program, bob, U256::from(40)).await?;
+
+    let balance = |account: &Value| account["programs"][&program]["balance"].clone();
+    let alice_account = get_account(&url, alice.address()).await?;
+    assert_eq!(balance(&alice_account), format!("0x{:064x}", 60));
+    assert_eq!(alice_account["nonce"], format!("0x{:064x}", 4));
+    assert_eq!(
+        balance(&get_account(&url, bob).await?),
+        format!("0x{:064x}", 40)
+    );
+
+    // Replaying the first transaction's nonce is rejected.
+    let mut stale = serde_json::json!({
+        "transactionType": { "send": "0x0" },
+        "to": bob,
+        "programId": program,
+        "op": "",
+        "transactionInputs": "",
+        "value": "0x1",
+        "nonce": "0x0",
+    });
+    sign_transaction(&mut stale, &secret_key)?;
+    let replay: Result<String, _> = rpc.request("lasr_send", rpc_params![stale]).await;
+    assert!(replay.is_err());
+
+    handle
+        .stop()
+        .map_err(|e| anyhow::anyhow!("failed to stop the node: {e:?}"))?;
+    Ok(())
+}
@@ -0,0 +1,362 @@
+//! Resource usage of program runs, and the execution limits they are held to.
+//!
+//! Nodes reject programs that run too long, use too much memory, or produce
+//! oversized outputs, and refuse to register oversized binaries. Networks
+//! configure those limits themselves, so `lasr test` only holds runs to the
+//! limits it is given, surfacing such failures locally.
+use std::{
+    fmt,
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    process::{Child, Command, ExitStatus, Output, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::bail;
+use serde_json::Value;
+
+/// Resource usage of a single program run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunProfile {
+    pub wall_time: Duration,
+    /// Peak resident set size of the program process, where the platform reports it.
+    pub peak_rss_kib: Option<u64>,
+    pub output_bytes: u64,
+    /// The number of instructions in the outputs, if they are JSON.
+    pub instruction_count: Option<u64>,
+    /// Whether the program was killed for running past its runtime limit.
+    pub timed_out: bool,
+}
+
+impl RunProfile {
+    pub fn new(wall_time: Duration, peak_rss_kib: Option<u64>, output: &Output) -> Self {
+        let instruction_count = serde_json::from_slice::<Value>(&output.stdout)
+            .ok()
+            .and_then(|outputs| {
+                outputs
+                    .get("instructions")
+                    .and_then(Value::as_array)
+                    .map(|instructions| instructions.len() as u64)
+            });
+        Self {
+            wall_time,
+            peak_rss_kib,
+            output_bytes: output.stdout.len() as u64,
+            instruction_count,
+            timed_out: false,
+        }
+    }
+}
+
+impl fmt::Display for RunProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.1}ms", self.wall_time.as_secs_f64() * 1000.0)?;
+        if let Some(peak_rss_kib) = self.peak_rss_kib {
+            write!(f, ", peak RSS {}", human_bytes(peak_rss_kib * 1024))?;
+        }
+        write!(f, ", output {}", human_bytes(self.output_bytes))?;
+        if let Some(instruction_count) = self.instruction_count {
+            write!(f, ", {instruction_count} instructions")?;
+        }
+        Ok(())
+    }
+}
+
+/// Thresholds a program run or binary must stay within. Limits that are
+/// `None` are not enforced.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ExecutionLimits {
+    pub max_runtime: Option<Duration>,
+    pub max_memory_kib: Option<u64>,
+    pub max_output_bytes: Option<u64>,
+    pub max_instructions: Option<u64>,
+    pub max_binary_bytes: Option<u64>,
+}
+
+impl ExecutionLimits {
+    /// Describes every limit `profile` exceeds.
+    pub fn check_run(&self, profile: &RunProfile) -> Vec<String> {
+        let mut violations = Vec::new();
+        if let Some(max_runtime) = self.max_runtime {
+            if profile.timed_out {
+                violations.push(format!(
+                    "was killed after running past the {}ms runtime limit",
+                    max_runtime.as_millis()
+                ));
+            } else if profile.wall_time > max_runtime {
+                violations.push(format!(
+                    "ran for {:.1}ms, over the {}ms runtime limit",
+                    profile.wall_time.as_secs_f64() * 1000.0,
+                    max_runtime.as_millis()
+                ));
+            }
+        }
+        if let (Some(max_memory_kib), Some(peak_rss_kib)) =
+            (self.max_memory_kib, profile.peak_rss_kib)
+        {
+            if peak_rss_kib > max_memory_kib {
+                violations.push(format!(
+                    "used {} of memory, over the {} limit",
+                    human_bytes(peak_rss_kib * 1024),
+                    human_bytes(max_memory_kib * 1024)
+                ));
+            }
+        }
+        if let Some(max_output_bytes) = self.max_output_bytes {
+            if profile.output_bytes > max_output_bytes {
+                violations.push(format!(
+                    "produced {} of output, over the {} limit",
+                    human_bytes(profile.output_bytes),
+                    human_bytes(max_output_bytes)
+                ));
+            }
+        }
+        if let (Some(max_instructions), Some(instruction_count)) =
+            (self.max_instructions, profile.instruction_count)
+        {
+            if instruction_count > max_instructions {
+                violations.push(format!(
+                    "produced {instruction_count} instructions, over the limit of {max_instructions}"
+                ));
+            }
+        }
+        violations
+    }
+
+    /// Describes the binary at `build` exceeding the size limit, if it does.
+    pub fn check_binary(&self, binary_bytes: u64) -> Option<String> {
+        let max_binary_bytes = self.max_binary_bytes?;
+        (binary_bytes > max_binary_bytes).then(|| {
+            format!(
+                "binary is {}, over the {} size limit",
+                human_bytes(binary_bytes),
+                human_bytes(max_binary_bytes)
+            )
+        })
+    }
+}
+
+/// Formats a byte count with a binary unit, e.g. `1.5 KiB`.
+pub fn human_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
+
+/// Feeds `input` to the program binary at `build` via stdin, returning its
+/// output along with the run's resource usage. A program still running after
+/// `timeout` is killed, like `fuzz::run_binary` does.
+pub fn run_profiled(
+    build: &Path,
+    input: &str,
+    timeout: Option<Duration>,
+) -> anyhow::Result<(Output, RunProfile)> {
+    run_command_profiled(Command::new(build), input, timeout)
+}
+
+/// Like [`run_profiled`], but runs `command`, which may carry its own
+/// environment and working directory.
+pub fn run_command_profiled(
+    mut command: Command,
+    input: &str,
+    timeout: Option<Duration>,
+) -> anyhow::Result<(Output, RunProfile)> {
+    let build = PathBuf::from(command.get_program());
+    let started = Instant::now();
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| anyhow::anyhow!("failed to spawn child process for {:?}: {e:?}", build))?;
+    let Some(mut stdin) = child.stdin.take() else {
+        bail!("failed to acquire stdin for child process {:?}", build);
+    };
+    // Written in the background, so a program that hangs without reading its
+    // input is still killed on time.
+    let input = input.to_string();
+    let writer = thread::spawn(move || stdin.write_all(input.as_bytes()));
+    let stdout = child.stdout.take().map(read_in_background);
+    let stderr = child.stderr.take().map(read_in_background);
+
+    let (status, peak_rss_kib, timed_out) = wait_with_usage(&mut child, timeout)
+        .map_err(|e| anyhow::anyhow!("failed to run program at path: {:?}: {e:?}", build))?;
+    let wall_time = started.elapsed();
+    match writer.join() {
+        Ok(Err(e)) if !timed_out => {
+            bail!("failed to write json inputs to stdin: {e:?}")
+        }
+        Err(_) => bail!("failed to write json inputs to stdin"),
+        _ => {}
+    }
+    let collect = |reader: Option<thread::JoinHandle<Vec<u8>>>| {
+        reader
+            .and_then(|reader| reader.join().ok())
+            .unwrap_or_default()
+    };
+    let output = Output {
+        status,
+        stdout: collect(stdout),
+        stderr: collect(stderr),
+    };
+    let profile = RunProfile {
+        timed_out,
+        ..RunProfile::new(wall_time, peak_rss_kib, &output)
+    };
+    Ok((output, profile))
+}
+
+/// Reads `reader` to the end on another thread, so that a program filling
+/// one pipe can't block on the other.
+pub fn read_in_background(mut reader: impl Read + Send + 'static) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut bytes = Vec::new();
+        let _ = reader.read_to_end(&mut bytes);
+        bytes
+    })
+}
+
+/// Waits for `child` to exit, killing it once `timeout` passes. Returns its
+/// status, its peak RSS in KiB, and whether it was killed.
+#[cfg(unix)]
+fn wait_with_usage(
+    child: &mut Child,
+    timeout: Option<Duration>,
+) -> io::Result<(ExitStatus, Option<u64>, bool)> {
+    use std::os::unix::process::ExitStatusExt;
+
+    let pid = child.id() as libc::pid_t;
+    let started = Instant::now();
+    let mut flags = if timeout.is_some() { libc::WNOHANG } else { 0 };
+    let mut timed_out = false;
+    let mut status = 0;
+    // SAFETY: `rusage` is plain old data, for which all zeroes is valid.
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+    loop {
+        // SAFETY: `status` and `usage` are valid for writes, and `pid` is our
+        // unreaped child, so `wait4` reaps only it.
+        match unsafe { libc::wait4(pid, &mut status, flags, &mut usage) } {
+            reaped if reaped == pid => break,
+            // Still running, which `wait4` only reports with `WNOHANG`.
+            0 => {
+                if timeout.is_some_and(|timeout| started.elapsed() > timeout) {
+                    let _ = child.kill();
+                    timed_out = true;
+                    flags = 0;
+                } else {
+                    thread::sleep(Duration::from_millis(1));
+                }
+            }
+            _ => {
+                let error = io::Error::last_os_error();
+                if error.kind() != io::ErrorKind::Interrupted {
+                    return Err(error);
+                }
+            }
+        }
+    }
+    // Linux reports `ru_maxrss` in KiB, macOS in bytes.
+    let peak_rss = usage.ru_maxrss as u64;
+    let peak_rss_kib = if cfg!(target_os = "macos") {
+        peak_rss / 1024
+    } else {
+        peak_rss
+    };
+    Ok((ExitStatus::from_raw(status), Some(peak_rss_kib), timed_out))
+}
+
+#[cfg(not(unix))]
+fn wait_with_usage(
+    child: &mut Child,
+    timeout: Option<Duration>,
+) -> io::Result<(ExitStatus, Option<u64>, bool)> {
+    let Some(timeout) = timeout else {
+        return Ok((child.wait()?, None, false));
+    };
+    let started = Instant::now();
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok((status, None, false));
+        }
+        if started.elapsed() > timeout {
+            let _ = child.kill();
+            return Ok((child.wait()?, None, true));
+        }
+        thread::sleep(Duration::from_millis(1));
+    }
+}
+
+#[test]
+fn test_execution_limits() {
+    let limits = ExecutionLimits {
+        max_runtime: Some(Duration::from_millis(100)),
+        max_memory_kib: Some(1024),
+        max_output_bytes: Some(2048),
+        max_instructions: Some(2),
+        max_binary_bytes: Some(4096),
+    };
+    let mut profile = RunProfile {
+        wall_time: Duration::from_millis(12),
+        peak_rss_kib: Some(512),
+        output_bytes: 1536,
+        instruction_count: Some(2),
+        timed_out: false,
+    };
+    assert!(limits.check_run(&profile).is_empty());
+    assert_eq!(
+        profile.to_string(),
+        "12.0ms, peak RSS 512.0 KiB, output 1.5 KiB, 2 instructions"
+    );
+
+    profile.wall_time = Duration::from_millis(250);
+    profile.peak_rss_kib = Some(4096);
+    profile.instruction_count = Some(3);
+    assert_eq!(
+        limits.check_run(&profile),
+        vec![
+            "ran for 250.0ms, over the 100ms runtime limit",
+            "used 4.0 MiB of memory, over the 1.0 MiB limit",
+            "produced 3 instructions, over the limit of 2",
+        ]
+    );
+    assert_eq!(limits.check_binary(4096), None);
+    assert_eq!(
+        limits.check_binary(8192).as_deref(),
+        Some("binary is 8.0 KiB, over the 4.0 KiB size limit")
+    );
+
+    // Only the limits that were given are enforced.
+    assert!(ExecutionLimits::default().check_run(&profile).is_empty());
+    assert_eq!(ExecutionLimits::default().check_binary(8192), None);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_run_killed_at_max_runtime() -> Result<(), anyhow::Error> {
+    let mut command = Command::new("sleep");
+    command.arg("10");
+    let (output, profile) = run_command_profiled(command, "{}", Some(Duration::from_millis(100)))?;
+    assert!(profile.timed_out);
+    assert!(!output.status.success());
+    assert!(profile.wall_time < Duration::from_secs(5));
+
+    let limits = ExecutionLimits {
+        max_runtime: Some(Duration::from_millis(100)),
+        ..ExecutionLimits::default()
+    };
+    assert_eq!(
+        limits.check_run(&profile),
+        vec!["was killed after running past the 100ms runtime limit"]
+    );
+    Ok(())
+}
@@ -0,0 +1,178 @@
+//! Thin JSON-RPC helpers for querying LASR nodes and sending them signed
+//! transactions.
+use std::path::Path;
+
+use jsonrpsee::{core::client::ClientT, http_client::HttpClientBuilder, rpc_params};
+use lasr_types::U256;
+use secp256k1::SecretKey;
+use serde_json::{json, Value};
+
+use crate::{
+    lasrctl::{
+        builders::program::parse_u256,
+        signing::{address_from_secret_key, sign_transaction},
+        wallet::{read_keypair_file, WalletDir},
+    },
+    scripts::consts::{LASR_RPC_URL_LOCAL, LASR_RPC_URL_STABLE, LASR_RPC_URL_UNSTABLE},
+};
+
+/// Maps a network handle ("stable", "unstable"/"test" or "local") to its RPC
+/// URL. Anything else is treated as a URL.
+pub fn rpc_url(network: &str) -> String {
+    match network {
+        "stable" => LASR_RPC_URL_STABLE.to_string(),
+        "unstable" | "test" => LASR_RPC_URL_UNSTABLE.to_string(),
+        "local" => LASR_RPC_URL_LOCAL.to_string(),
+        url => url.to_string(),
+    }
+}
+
+/// Fetches the serialized `lasr_types::Account` at `address` via `lasr_getAccount`.
+pub async fn get_account(network: &str, address: &str) -> anyhow::Result<Value> {
+    let url = rpc_url(network);
+    let client = HttpClientBuilder::default()
+        .build(&url)
+        .map_err(|e| anyhow::anyhow!("failed to build RPC client for {url}: {e:?}"))?;
+    let account: Value = client
+        .request("lasr_getAccount", rpc_params![address])
+        .await
+        .map_err(|e| anyhow::anyhow!("failed to fetch account {address} from {url}: {e:?}"))?;
+    // Nodes return the account as a JSON encoded string.
+    match account {
+        Value::String(account) => serde_json::from_str(&account)
+            .map_err(|e| anyhow::anyhow!("failed to parse account {address}: {e:?}")),
+        account => Ok(account),
+    }
+}
+
+/// Builds the `accountInfo` for a call from the caller's account (holdings
+/// and nonce) and the program's account (type, owner, metadata and data).
+pub fn compose_account_info(caller: &Value, program: &Value) -> Value {
+    let mut account_info = caller.clone();
+    for field in [
+        "accountType",
+        "programNamespace",
+        "ownerAddress",
+        "programAccountData",
+        "programAccountMetadata",
+        "programAccountLinkedPrograms",
+    ] {
+        if let Some(value) = program.get(field) {
+            account_info[field] = value.clone();
+        }
+    }
+    account_info
+}
+
+/// Sends transactions to a LASR node, signed with a wallet keypair.
+pub struct SigningClient {
+    network: String,
+    secret_key: SecretKey,
+    address: String,
+}
+
+impl SigningClient {
+    pub fn new(network: &str, secret_key: SecretKey) -> Self {
+        Self {
+            network: network.to_string(),
+            address: address_from_secret_key(&secret_key),
+            secret_key,
+        }
+    }
+
+    /// A client signing with the wallet keypair resolved like `WalletDir::resolve`.
+    pub fn from_wallet(
+        network: &str,
+        wallet: Option<&str>,
+        keypair_path: Option<&Path>,
+    ) -> anyhow::Result<Self> {
+        let secret_key =
+            read_keypair_file(&WalletDir::default().resolve(wallet, keypair_path))?.secret_key()?;
+        Ok(Self::new(network, secret_key))
+    }
+
+    /// The address transactions are sent from.
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+
+    /// The nonce the node expects on the next transaction from `address`.
+    pub async fn nonce(&self) -> anyhow::Result<U256> {
+        let account = get_account(&self.network, &self.address)
+            .await
+            .map_err(|e| anyhow::anyhow!("{e:#}, is {} funded?", self.address))?;
+        match account.get("nonce").and_then(Value::as_str) {
+            Some(nonce) => parse_u256(nonce),
+            None => Ok(U256::zero()),
+        }
+    }
+
+    /// Registers a program with `lasr_registerProgram`, returning the node's
+    /// response: the program's address.
+    pub async fn register_program(&self, transaction_inputs: &Value) -> anyhow::Result<String> {
+        let nonce = self.nonce().await?;
+        let transaction = json!({
+            "transactionType": { "registerProgram": quantity(nonce) },
+            "to": self.address,
+            "programId": self.address,
+            "op": "",
+            "transactionInputs": transaction_inputs.to_string(),
+            "value": quantity(U256::zero()),
+            "nonce": quantity(nonce),
+        });
+        self.submit("lasr_registerProgram", transaction).await
+    }
+
+    /// Calls `op` on `program_id` with `lasr_call`, returning the transaction hash.
+    pub async fn call(
+        &self,
+        program_id: &str,
+        op: &str,
+        transaction_inputs: &str,
+        value: U256,
+    ) -> anyhow::Result<String> {
+        let nonce = self.nonce().await?;
+        let transaction = json!({
+            "transactionType": { "call": quantity(nonce) },
+            "to": program_id,
+            "programId": program_id,
+            "op": op,
+            "transactionInputs": transaction_inputs,
+            "value": quantity(value),
+            "nonce": quantity(nonce),
+        });
+        self.submit("lasr_call", transaction).await
+    }
+
+    /// Sends `value` of `program_id`'s token to `to` with `lasr_send`,
+    /// returning the transaction hash.
+    pub async fn send(&self, program_id: &str, to: &str, value: U256) -> anyhow::Result<String> {
+        let nonce = self.nonce().await?;
+        let transaction = json!({
+            "transactionType": { "send": quantity(nonce) },
+            "to": to,
+            "programId": program_id,
+            "op": "",
+            "transactionInputs": "",
+            "value": quantity(value),
+            "nonce": quantity(nonce),
+        });
+        self.submit("lasr_send", transaction).await
+    }
+
+    async fn submit(&self, method: &str, mut transaction: Value) -> anyhow::Result<String> {
+        sign_transaction(&mut transaction, &self.secret_key)?;
+        let url = rpc_url(&self.network);
+        let client = HttpClientBuilder::default()
+            .build(&url)
+            .map_err(|e| anyhow::anyhow!("failed to build RPC client for {url}: {e:?}"))?;
+        client
+            .request(method, rpc_params![transaction])
+            .await
+            .map_err(|e| anyhow::anyhow!("{method} was rejected by {url}: {e:?}"))
+    }
+}
+
+fn quantity(value: U256) -> String {
+    format!("0x{value:064x}")
+}
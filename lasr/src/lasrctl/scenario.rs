@@ -0,0 +1,438 @@
+//! Multi-step scenarios for testing stateful programs.
+//!
+//! A scenario is a JSON file listing a sequence of calls, each with its
+//! sender, value and `transactionInputs`, plus the balances, program metadata
+//! and program data expected afterwards (or the error the call should fail
+//! with). Steps run against a `Ledger`, so every call sees the state left
+//! behind by the previous ones instead of a hand-written `accountInfo`.
+//!
+//! ```json
+//! {
+//!   "programId": "0x57234c52617e7ca8edc5577ebe3eb38d53a77607",
+//!   "accounts": { "alice": "0x100444c7d04a842d19bc3ee63cb7b96682ff3f43" },
+//!   "genesis": [{ "account": "alice", "token": "0x0000000000000000000000000000000000000000", "amount": "1000" }],
+//!   "steps": [
+//!     { "op": "mint", "from": "alice", "value": "10", "transactionInputs": {},
+//!       "expect": { "balances": [{ "account": "alice", "token": "this", "amount": "10" }] } },
+//!     { "op": "burn", "from": "alice", "transactionInputs": { "amount": "20" }, "expectError": "rejected" }
+//!   ]
+//! }
+//! ```
+use std::{collections::BTreeMap, fmt, fs, path::Path};
+
+use anyhow::bail;
+use lasr_types::Inputs;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::lasrctl::{
+    builders::{error::ProgramError, program::parse_u256},
+    ledger::{normalize, Ledger},
+    validate::OutputsValidator,
+};
+
+/// A sequence of calls against a single program.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Scenario {
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Address of the program under test, which `this` refers to.
+    pub program_id: String,
+    /// Named accounts that steps and assertions can refer to instead of addresses.
+    #[serde(default)]
+    pub accounts: BTreeMap<String, String>,
+    /// Balances credited to accounts before the first step runs.
+    #[serde(default)]
+    pub genesis: Vec<BalanceEntry>,
+    pub steps: Vec<Step>,
+}
+
+/// A balance of `token` held by `account`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BalanceEntry {
+    pub account: String,
+    pub token: String,
+    pub amount: String,
+}
+
+/// A single call in a scenario.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Step {
+    #[serde(default)]
+    pub name: Option<String>,
+    pub op: String,
+    pub from: String,
+    /// Value sent with the call, as a decimal or `0x` prefixed hex string.
+    #[serde(default)]
+    pub value: Option<String>,
+    /// Either a JSON object, which is stringified, or an already stringified payload.
+    #[serde(default)]
+    pub transaction_inputs: Value,
+    /// If set, the call must fail with an error whose kind or message contains this text.
+    #[serde(default)]
+    pub expect_error: Option<String>,
+    #[serde(default)]
+    pub expect: Expectations,
+}
+
+/// State asserted after a step has been applied.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Expectations {
+    #[serde(default)]
+    pub balances: Vec<BalanceEntry>,
+    /// Expected entries of the program's metadata.
+    #[serde(default)]
+    pub metadata: BTreeMap<String, String>,
+    /// Expected entries of the program's data.
+    #[serde(default)]
+    pub data: BTreeMap<String, String>,
+}
+
+/// The result of running one step.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StepReport {
+    pub name: String,
+    pub failures: Vec<String>,
+}
+
+impl StepReport {
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+impl fmt::Display for StepReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.passed() {
+            return write!(f, "step {} ... ok", self.name);
+        }
+        write!(f, "step {} ... FAILED", self.name)?;
+        for failure in &self.failures {
+            write!(f, "\n    {failure}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Scenario {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("failed to read scenario {path:?}: {e:?}"))?;
+        serde_json::from_str(&contents)
+            .map_err(|e| anyhow::anyhow!("failed to parse scenario {path:?}: {e}"))
+    }
+
+    /// Resolves an account name, `this`, or a literal address to an address.
+    pub fn resolve(&self, account: &str) -> String {
+        match self.accounts.get(account) {
+            Some(address) => normalize(address),
+            None if account == "this" => normalize(&self.program_id),
+            None => normalize(account),
+        }
+    }
+
+    /// Credits the genesis balances to `ledger`.
+    pub fn seed(&self, ledger: &mut Ledger) -> anyhow::Result<()> {
+        for entry in &self.genesis {
+            ledger.credit(
+                &self.resolve(&entry.account),
+                &self.resolve(&entry.token),
+                parse_u256(&entry.amount)?,
+            );
+        }
+        Ok(())
+    }
+
+    /// Runs every step in order against `ledger`. `call` executes the program
+    /// and returns its serialized outputs, or the error it rejected the call with.
+    ///
+    /// A step that does not behave as expected is reported, and later steps
+    /// still run against whatever state the failed step left behind.
+    pub fn run<F>(&self, ledger: &mut Ledger, mut call: F) -> anyhow::Result<Vec<StepReport>>
+    where
+        F: FnMut(&Inputs) -> anyhow::Result<Result<Value, ProgramError>>,
+    {
+        self.steps
+            .iter()
+            .enumerate()
+            .map(|(index, step)| {
+                let name = step
+                    .name
+                    .clone()
+                    .unwrap_or_else(|| format!("{} ({})", index + 1, step.op));
+                let failures = self.run_step(ledger, step, &mut call)?;
+                Ok(StepReport { name, failures })
+            })
+            .collect()
+    }
+
+    fn run_step<F>(
+        &self,
+        ledger: &mut Ledger,
+        step: &Step,
+        call: &mut F,
+    ) -> anyhow::Result<Vec<String>>
+    where
+        F: FnMut(&Inputs) -> anyhow::Result<Result<Value, ProgramError>>,
+    {
+        let transaction_inputs = match &step.transaction_inputs {
+            Value::Null => String::new(),
+            Value::String(transaction_inputs) => transaction_inputs.clone(),
+            transaction_inputs => serde_json::to_string(transaction_inputs)?,
+        };
+        let value = step
+            .value
+            .as_deref()
+            .map(parse_u256)
+            .transpose()?
+            .unwrap_or_default();
+        let inputs = ledger.call_inputs(
+            &self.resolve(&step.from),
+            &self.program_id,
+            &step.op,
+            &transaction_inputs,
+            value,
+        )?;
+
+        // Outputs the ledger refuses to apply fail the call just like a
+        // rejection by the program would.
+        let mut failures = Vec::new();
+        let result = match call(&inputs)? {
+            Ok(outputs) => {
+                let issues =
+                    OutputsValidator::new(&serde_json::to_value(&inputs)?).validate(&outputs);
+                failures.extend(issues.iter().map(ToString::to_string));
+                ledger.apply_value(&outputs).map_err(|e| {
+                    ProgramError::rejected(format!("ledger rejected the outputs: {e:#}"))
+                })
+            }
+            Err(err) => Err(err),
+        };
+        match (result, &step.expect_error) {
+            (Ok(()), None) => {}
+            (Ok(()), Some(expected)) => {
+                failures.push(format!(
+                    "expected the call to fail with '{expected}', but it succeeded"
+                ));
+            }
+            (Err(err), None) => failures.push(format!("call failed: {err}")),
+            (Err(err), Some(expected)) => {
+                let expected = expected.to_lowercase();
+                if !err.to_string().to_lowercase().contains(&expected) {
+                    failures.push(format!(
+                        "expected an error matching '{expected}', got: {err}"
+                    ));
+                }
+            }
+        }
+
+        failures.extend(self.check_expectations(ledger, &step.expect));
+        Ok(failures)
+    }
+
+    fn check_expectations(&self, ledger: &Ledger, expect: &Expectations) -> Vec<String> {
+        let mut failures = Vec::new();
+        for entry in &expect.balances {
+            let expected = match parse_u256(&entry.amount) {
+                Ok(expected) => expected,
+                Err(e) => {
+                    failures.push(format!("{e:#}"));
+                    continue;
+                }
+            };
+            let actual =
+                ledger.balance_of(&self.resolve(&entry.account), &self.resolve(&entry.token));
+            if actual != expected {
+                failures.push(format!(
+                    "balance of {} in {}: expected {expected}, got {actual}",
+                    entry.account, entry.token
+                ));
+            }
+        }
+
+        let program = ledger
+            .program(&self.program_id)
+            .cloned()
+            .unwrap_or_default();
+        for (field, expected, actual) in [
+            ("metadata", &expect.metadata, &program.metadata),
+            ("data", &expect.data, &program.data),
+        ] {
+            for (key, expected) in expected {
+                match actual.get(key) {
+                    Some(actual) if actual == expected => {}
+                    Some(actual) => failures.push(format!(
+                        "program {field} '{key}': expected {expected:?}, got {actual:?}"
+                    )),
+                    None => failures.push(format!(
+                        "program {field} '{key}': expected {expected:?}, but it is not set"
+                    )),
+                }
+            }
+        }
+        failures
+    }
+}
+
+/// A scenario run from a fresh ledger.
+#[derive(Debug)]
+pub struct ScenarioRun {
+    /// The state the scenario left behind.
+    pub ledger: Ledger,
+    pub reports: Vec<StepReport>,
+}
+
+impl ScenarioRun {
+    /// Every failure of every failed step, prefixed with the step's name.
+    pub fn failures(&self) -> Vec<String> {
+        self.reports
+            .iter()
+            .flat_map(|report| {
+                report
+                    .failures
+                    .iter()
+                    .map(move |failure| format!("step {}: {failure}", report.name))
+            })
+            .collect()
+    }
+
+    /// The ledger the scenario left behind, failing if any step failed.
+    pub fn into_ledger(self) -> anyhow::Result<Ledger> {
+        let failed: Vec<&StepReport> = self
+            .reports
+            .iter()
+            .filter(|report| !report.passed())
+            .collect();
+        if !failed.is_empty() {
+            bail!(
+                "{} of {} steps failed:\n{}",
+                failed.len(),
+                self.reports.len(),
+                failed
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            );
+        }
+        Ok(self.ledger)
+    }
+}
+
+/// Seeds a fresh ledger from `scenario` and runs each of its steps through
+/// `call`, reporting on every step.
+pub fn run_scenario<F>(scenario: &Scenario, call: F) -> anyhow::Result<ScenarioRun>
+where
+    F: FnMut(&Inputs) -> anyhow::Result<Result<Value, ProgramError>>,
+{
+    let mut ledger = Ledger::new();
+    scenario.seed(&mut ledger)?;
+    let reports = scenario.run(&mut ledger, call)?;
+    Ok(ScenarioRun { ledger, reports })
+}
+
+#[test]
+fn test_non_fungible_scenario() -> Result<(), anyhow::Error> {
+    use crate::lasrctl::builders::non_fungible::NonFungibleProgram;
+
+    let scenario: Scenario = serde_json::from_value(serde_json::json!({
+        "programId": "0x57234c52617e7ca8edc5577ebe3eb38d53a77607",
+        "accounts": { "alice": "0x100444c7D04A842D19bc3eE63cB7b96682FF3f43" },
+        "steps": [
+            { "op": "create", "from": "alice", "transactionInputs": {
+                "name": "HelloToken", "symbol": "HLLO", "totalSupply": "5",
+                "initializedSupply": "5", "imgUrl": "https://example.com/hello.png", "price": "0"
+            } },
+            { "op": "mint", "from": "alice", "transactionInputs": { "quantity": "2" },
+              "expect": {
+                "balances": [{ "account": "alice", "token": "this", "amount": "2" }],
+                "data": { "currentSupply": "2" }
+              } },
+            { "op": "burn", "from": "alice", "transactionInputs": { "tokenIds": ["0"] },
+              "expect": {
+                "balances": [{ "account": "alice", "token": "this", "amount": "1" }],
+                "data": { "currentSupply": "1", "nextTokenId": "2" }
+              } },
+            { "op": "burn", "from": "alice", "transactionInputs": { "tokenIds": ["4"] },
+              "expectError": "does not hold token ID 4" },
+            { "op": "mint", "from": "alice", "transactionInputs": { "quantity": "1" },
+              "expect": { "data": { "currentSupply": "2", "nextTokenId": "3" } } }
+        ]
+    }))?;
+
+    let program = NonFungibleProgram::new();
+    let ledger = run_scenario(&scenario, |inputs| {
+        Ok(program
+            .execute(inputs)
+            .map_err(ProgramError::from)
+            .and_then(|outputs| {
+                serde_json::to_value(outputs).map_err(|e| ProgramError::internal(e.to_string()))
+            }))
+    })?
+    .into_ledger()?;
+    assert_eq!(
+        ledger.token_ids_of(&scenario.resolve("alice"), &scenario.resolve("this")),
+        vec![lasr_types::U256::one(), lasr_types::U256::from(2)]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_example_scenarios() -> Result<(), anyhow::Error> {
+    use crate::lasrctl::builders::{
+        faucet::FaucetProgram, non_fungible::NonFungibleProgram, program::Program,
+    };
+
+    let examples = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/examples");
+    let mut ran = 0;
+    for example in fs::read_dir(&examples)? {
+        let example = example?.path();
+        let Ok(scenarios) = fs::read_dir(example.join("scenarios")) else {
+            continue;
+        };
+        let name = example
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or_default();
+        // Each example's scenarios run against the in-process build of the
+        // program its template compiles to.
+        let program = match name {
+            "fungible" => Program::new(),
+            "non_fungible" => NonFungibleProgram::new(),
+            "faucet" => FaucetProgram::new(),
+            other => bail!("no in-process program to run the {other} example's scenarios"),
+        };
+        for path in scenarios {
+            let path = path?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                continue;
+            }
+            let scenario = Scenario::load(&path)?;
+            let run = run_scenario(&scenario, |inputs| {
+                Ok(program
+                    .execute(inputs)
+                    .map_err(ProgramError::from)
+                    .and_then(|outputs| {
+                        serde_json::to_value(outputs)
+                            .map_err(|e| ProgramError::internal(e.to_string()))
+                    }))
+            })?;
+            let failures = run.failures();
+            assert!(
+                failures.is_empty(),
+                "{}:\n{}",
+                path.display(),
+                failures.join("\n")
+            );
+            ran += 1;
+        }
+    }
+    assert!(ran > 0, "no scenarios found under {}", examples.display());
+
+    Ok(())
+}
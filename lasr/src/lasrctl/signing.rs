@@ -0,0 +1,167 @@
+//! Hashing and signing of LASR transactions.
+//!
+//! Transactions are hashed and their signers recovered by
+//! `lasr_types::Transaction`, exactly as a node does. Signatures are
+//! recoverable secp256k1 signatures over that hash, stored in the
+//! transaction's `r`, `s` and `v` fields, where `v` is the recovery ID.
+use std::{fs, path::Path};
+
+use anyhow::bail;
+use lasr_types::{Inputs, Transaction};
+use secp256k1::{ecdsa::RecoverableSignature, Message, PublicKey, Secp256k1, SecretKey};
+use serde_json::{json, Value};
+use sha3::{Digest, Keccak256};
+
+/// A zeroed 32 byte word, standing in for the `r` and `s` of an unsigned transaction.
+const ZERO_WORD: &str = "0x0000000000000000000000000000000000000000000000000000000000000000";
+
+/// Parses a hex encoded secp256k1 secret key, with or without a `0x` prefix.
+pub fn parse_secret_key(secret_key: &str) -> anyhow::Result<SecretKey> {
+    let secret_key = secret_key.trim();
+    let bytes = hex::decode(secret_key.strip_prefix("0x").unwrap_or(secret_key))
+        .map_err(|e| anyhow::anyhow!("secret key is not valid hex: {e:?}"))?;
+    SecretKey::from_slice(&bytes).map_err(|e| anyhow::anyhow!("invalid secret key: {e:?}"))
+}
+
+/// Reads the secret key from a wallet keypair file written by `lasr init`.
+pub fn load_secret_key(keypair_path: &Path) -> anyhow::Result<SecretKey> {
+    let contents = fs::read_to_string(keypair_path)
+        .map_err(|e| anyhow::anyhow!("failed to read keypair file {keypair_path:?}: {e:?}"))?;
+    let keypair: Value = serde_json::from_str(&contents)
+        .map_err(|e| anyhow::anyhow!("failed to parse keypair file {keypair_path:?}: {e:?}"))?;
+    let secret_key = ["/secret_key", "/secretKey", "/keypair/secret_key"]
+        .iter()
+        .find_map(|pointer| keypair.pointer(pointer).and_then(Value::as_str))
+        .ok_or_else(|| anyhow::anyhow!("keypair file {keypair_path:?} has no secret key"))?;
+    parse_secret_key(secret_key)
+}
+
+/// The `0x` prefixed address of `public_key`: the last 20 bytes of the
+/// keccak256 hash of its uncompressed encoding.
+pub fn address_from_public_key(public_key: &PublicKey) -> String {
+    let hash = Keccak256::digest(&public_key.serialize_uncompressed()[1..]);
+    format!("0x{}", hex::encode(&hash[12..]))
+}
+
+pub fn address_from_secret_key(secret_key: &SecretKey) -> String {
+    address_from_public_key(&PublicKey::from_secret_key(
+        &Secp256k1::signing_only(),
+        secret_key,
+    ))
+}
+
+/// Parses a serialized transaction into a `lasr_types::Transaction`.
+fn parse_transaction(transaction: &Value) -> anyhow::Result<Transaction> {
+    serde_json::from_value(transaction.clone())
+        .map_err(|e| anyhow::anyhow!("invalid transaction: {e:?}"))
+}
+
+/// Computes the hash a serialized `lasr_types::Transaction` is signed over,
+/// as the protocol does: `Transaction::hash` of its unsigned fields.
+pub fn transaction_hash(transaction: &Value) -> anyhow::Result<[u8; 32]> {
+    let hash = parse_transaction(transaction)?.hash();
+    <[u8; 32]>::try_from(hash.as_slice())
+        .map_err(|e| anyhow::anyhow!("transaction hash is not 32 bytes: {e:?}"))
+}
+
+/// Signs a serialized `lasr_types::Transaction` in place, setting its `from`
+/// to the signer and its `r`, `s` and `v` to the signature.
+pub fn sign_transaction(transaction: &mut Value, secret_key: &SecretKey) -> anyhow::Result<()> {
+    transaction["from"] = Value::String(address_from_secret_key(secret_key));
+    // Unsigned transactions may omit the signature, which isn't hashed.
+    for (field, placeholder) in [
+        ("v", json!(0)),
+        ("r", json!(ZERO_WORD)),
+        ("s", json!(ZERO_WORD)),
+    ] {
+        if transaction.get(field).is_none() {
+            transaction[field] = placeholder;
+        }
+    }
+    let message = Message::from_digest_slice(&transaction_hash(transaction)?)
+        .map_err(|e| anyhow::anyhow!("failed to build message from transaction hash: {e:?}"))?;
+    let signature: RecoverableSignature =
+        Secp256k1::signing_only().sign_ecdsa_recoverable(&message, secret_key);
+    let (recovery_id, compact) = signature.serialize_compact();
+
+    transaction["r"] = Value::String(format!("0x{}", hex::encode(&compact[..32])));
+    transaction["s"] = Value::String(format!("0x{}", hex::encode(&compact[32..])));
+    transaction["v"] = json!(recovery_id.to_i32());
+    Ok(())
+}
+
+/// Recovers the address that signed a serialized `lasr_types::Transaction`
+/// from its `r`, `s` and `v` fields, with `Transaction::recover`.
+pub fn recover_signer(transaction: &Value) -> anyhow::Result<String> {
+    let signer = parse_transaction(transaction)?
+        .recover()
+        .map_err(|e| anyhow::anyhow!("failed to recover the signer: {e:?}"))?;
+    Ok(signer.to_full_string().to_lowercase())
+}
+
+/// Checks that a serialized `lasr_types::Transaction` was signed by its
+/// `from` address, returning the signer.
+pub fn verify_transaction(transaction: &Value) -> anyhow::Result<String> {
+    let signer = recover_signer(transaction)?;
+    let from = transaction
+        .get("from")
+        .and_then(Value::as_str)
+        .ok_or_else(|| anyhow::anyhow!("transaction is missing 'from'"))?;
+    if signer != from.to_lowercase() {
+        bail!("transaction from {from} was signed by {signer}");
+    }
+    Ok(signer)
+}
+
+/// Checks that the transaction a program was called with was signed by its
+/// sender, e.g. to assert in a program's tests that fixtures carry real
+/// signatures.
+pub fn verify_inputs(inputs: &Inputs) -> anyhow::Result<String> {
+    verify_transaction(&serde_json::to_value(&inputs.transaction)?)
+}
+
+#[test]
+fn test_sign_transaction() -> Result<(), anyhow::Error> {
+    let secret_key =
+        parse_secret_key("0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318")?;
+    let signer = "0x2c7536e3605d9c16a7a3d7b1898e529396a65c23";
+    assert_eq!(address_from_secret_key(&secret_key), signer);
+
+    let inputs: Value = serde_json::from_str(include_str!(
+        "../examples/fungible/example-program-inputs/fungible-create.json"
+    ))?;
+    let mut transaction = inputs["transaction"].clone();
+    // The fixture's placeholder signature doesn't recover to its `from`.
+    assert!(verify_transaction(&transaction).is_err());
+
+    sign_transaction(&mut transaction, &secret_key)?;
+    assert_eq!(transaction["from"], signer);
+    assert_eq!(transaction["r"].as_str().map(str::len), Some(66));
+    assert_eq!(transaction["s"].as_str().map(str::len), Some(66));
+    assert_eq!(verify_transaction(&transaction)?, signer);
+
+    // A node deserializing the transaction recovers the same signer from the
+    // same hash.
+    let protocol: Transaction = serde_json::from_value(transaction.clone())?;
+    assert_eq!(transaction_hash(&transaction)?.to_vec(), protocol.hash());
+    assert_eq!(
+        protocol
+            .recover()
+            .map_err(|e| anyhow::anyhow!("{e:?}"))?
+            .to_full_string()
+            .to_lowercase(),
+        signer
+    );
+
+    // The signature isn't part of the hash, but every signed field is.
+    let hash = transaction_hash(&transaction)?;
+    let mut resigned = transaction.clone();
+    resigned["v"] = json!(1 - transaction["v"].as_i64().unwrap_or_default());
+    assert_eq!(transaction_hash(&resigned)?, hash);
+    assert!(verify_transaction(&resigned).is_err());
+    transaction["value"] =
+        json!("0x0000000000000000000000000000000000000000000000000000000000000002");
+    assert!(verify_transaction(&transaction).is_err());
+
+    Ok(())
+}
@@ -0,0 +1,432 @@
+//! Project templates for `lasr init`.
+//!
+//! A template is either one of the programs bundled with this crate, a local
+//! directory, or a git repository. Directory and git templates describe their
+//! contents in a `lasr-template.json` manifest:
+//!
+//! ```json
+//! {
+//!     "name": "my-token",
+//!     "description": "Our fungible token starter",
+//!     "files": [{ "source": "program.rs", "destination": "src/main.rs" }],
+//!     "fixtures": ["fixtures/create.json"]
+//! }
+//! ```
+//!
+//! `files` are copied into the project, to `destination` if given, and
+//! `fixtures` into its `example-program-inputs` directory. `{{name}}`,
+//! `{{crate_name}}` and `{{symbol}}` are substituted in the contents and
+//! destinations of both. Templates installed under the templates directory
+//! (`$LASR_TEMPLATES_DIR`, or `~/.lasr/templates`) can be selected by name.
+use std::{
+    collections::BTreeMap,
+    env, fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use anyhow::bail;
+use serde::{Deserialize, Serialize};
+
+/// The file describing a directory or git template.
+pub const TEMPLATE_MANIFEST_FILENAME: &str = "lasr-template.json";
+/// Overrides the directory installed templates are looked up in.
+pub const TEMPLATES_DIR_ENV: &str = "LASR_TEMPLATES_DIR";
+/// The directory in a new project that template fixtures are written to.
+pub const FIXTURES_DIR: &str = "example-program-inputs";
+
+struct BuiltinTemplate {
+    name: &'static str,
+    description: &'static str,
+    program: &'static str,
+    fixtures: &'static [(&'static str, &'static str)],
+}
+
+const BUILTIN_TEMPLATES: &[BuiltinTemplate] = &[
+    BuiltinTemplate {
+        name: "blank",
+        description: "A minimal template to start from scratch",
+        program: include_str!("../examples/blank/example_program.rs"),
+        fixtures: &[(
+            "blank-create.json",
+            include_str!("../examples/blank/example-program-inputs/blank-create.json"),
+        )],
+    },
+    BuiltinTemplate {
+        name: "fungible",
+        description: "A template for creating fungible tokens",
+        program: include_str!("../examples/fungible/example_program.rs"),
+        fixtures: &[(
+            "fungible-create.json",
+            include_str!("../examples/fungible/example-program-inputs/fungible-create.json"),
+        )],
+    },
+    BuiltinTemplate {
+        name: "non_fungible",
+        description: "A template for creating non-fungible tokens",
+        program: include_str!("../examples/non_fungible/example_program.rs"),
+        fixtures: &[(
+            "non-fungible-create.json",
+            include_str!(
+                "../examples/non_fungible/example-program-inputs/non-fungible-create.json"
+            ),
+        )],
+    },
+    BuiltinTemplate {
+        name: "faucet",
+        description: "A template for creating a faucet, allowing users to request test tokens",
+        program: include_str!("../examples/faucet/example_program.rs"),
+        fixtures: &[(
+            "faucet-create.json",
+            include_str!("../examples/faucet/example-program-inputs/faucet-create.json"),
+        )],
+    },
+    BuiltinTemplate {
+        name: "hello_lasr",
+        description: "The default program strategies, with no custom methods",
+        program: include_str!("../examples/hello_lasr/example_program.rs"),
+        fixtures: &[(
+            "lasr-hello-create.json",
+            include_str!("../examples/hello_lasr/example-program-inputs/lasr-hello-create.json"),
+        )],
+    },
+];
+
+/// The contents of `lasr-template.json`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TemplateManifest {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub files: Vec<ManifestFile>,
+    #[serde(default)]
+    pub fixtures: Vec<PathBuf>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestFile {
+    /// Path of the file, relative to the template directory.
+    pub source: PathBuf,
+    /// Path to write the file to, relative to the project. Defaults to `source`.
+    #[serde(default)]
+    pub destination: Option<PathBuf>,
+}
+
+/// Where a template comes from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemplateSource {
+    Builtin(&'static str),
+    Path(PathBuf),
+    Git(String),
+}
+
+impl TemplateSource {
+    /// Resolves `template` as a git url, a built-in or installed template
+    /// name, or a path to a template directory, in that order.
+    pub fn parse(template: &str) -> anyhow::Result<Self> {
+        if is_git_url(template) {
+            return Ok(Self::Git(template.to_string()));
+        }
+        let name = template.replace('-', "_");
+        if let Some(builtin) = BUILTIN_TEMPLATES.iter().find(|b| b.name == name) {
+            return Ok(Self::Builtin(builtin.name));
+        }
+        let installed = templates_dir().join(template);
+        if installed.join(TEMPLATE_MANIFEST_FILENAME).is_file() {
+            return Ok(Self::Path(installed));
+        }
+        let path = PathBuf::from(template);
+        if path.join(TEMPLATE_MANIFEST_FILENAME).is_file() {
+            return Ok(Self::Path(path));
+        }
+        bail!(
+            "unknown template '{template}', choose one of: {}, or give a template directory or git url",
+            list_templates()?
+                .iter()
+                .map(|t| t.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+}
+
+fn is_git_url(template: &str) -> bool {
+    ["https://", "http://", "ssh://", "git://", "git@"]
+        .iter()
+        .any(|prefix| template.starts_with(prefix))
+        || template.ends_with(".git")
+}
+
+/// The directory installed templates are looked up in.
+pub fn templates_dir() -> PathBuf {
+    if let Ok(dir) = env::var(TEMPLATES_DIR_ENV) {
+        return PathBuf::from(dir);
+    }
+    env::var("HOME")
+        .map(PathBuf::from)
+        .unwrap_or_default()
+        .join(".lasr")
+        .join("templates")
+}
+
+/// A template's name and description, for `lasr templates list`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateSummary {
+    pub name: String,
+    pub description: String,
+    pub source: TemplateSource,
+}
+
+/// Lists the built-in templates, followed by the installed ones.
+pub fn list_templates() -> anyhow::Result<Vec<TemplateSummary>> {
+    let mut templates: Vec<TemplateSummary> = BUILTIN_TEMPLATES
+        .iter()
+        .map(|builtin| TemplateSummary {
+            name: builtin.name.to_string(),
+            description: builtin.description.to_string(),
+            source: TemplateSource::Builtin(builtin.name),
+        })
+        .collect();
+
+    let dir = templates_dir();
+    if dir.is_dir() {
+        let mut installed = Vec::new();
+        for entry in fs::read_dir(&dir)
+            .map_err(|e| anyhow::anyhow!("failed to read templates directory {dir:?}: {e:?}"))?
+        {
+            let path = entry?.path();
+            if !path.join(TEMPLATE_MANIFEST_FILENAME).is_file() {
+                continue;
+            }
+            let manifest = read_manifest(&path)?;
+            installed.push(TemplateSummary {
+                name: path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or(manifest.name),
+                description: manifest.description,
+                source: TemplateSource::Path(path),
+            });
+        }
+        installed.sort_by(|a, b| a.name.cmp(&b.name));
+        templates.extend(installed);
+    }
+    Ok(templates)
+}
+
+fn read_manifest(template_dir: &Path) -> anyhow::Result<TemplateManifest> {
+    let manifest_path = template_dir.join(TEMPLATE_MANIFEST_FILENAME);
+    let contents = fs::read_to_string(&manifest_path)
+        .map_err(|e| anyhow::anyhow!("failed to read {manifest_path:?}: {e:?}"))?;
+    serde_json::from_str(&contents)
+        .map_err(|e| anyhow::anyhow!("failed to parse {manifest_path:?}: {e:?}"))
+}
+
+/// The values substituted for `{{name}}`, `{{crate_name}}` and `{{symbol}}`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateVariables(BTreeMap<&'static str, String>);
+
+impl TemplateVariables {
+    /// `symbol` defaults to the first four alphanumeric characters of `name`,
+    /// uppercased.
+    pub fn new(name: &str, symbol: Option<&str>) -> Self {
+        let symbol = symbol.map(str::to_string).unwrap_or_else(|| {
+            name.chars()
+                .filter(char::is_ascii_alphanumeric)
+                .take(4)
+                .collect::<String>()
+                .to_uppercase()
+        });
+        Self(BTreeMap::from([
+            ("name", name.to_string()),
+            ("crate_name", name.replace('-', "_")),
+            ("symbol", symbol),
+        ]))
+    }
+
+    pub fn substitute(&self, contents: &str) -> String {
+        self.0
+            .iter()
+            .fold(contents.to_string(), |contents, (variable, value)| {
+                contents.replace(&format!("{{{{{variable}}}}}"), value)
+            })
+    }
+}
+
+/// A file to write into a new project.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateFile {
+    /// Path relative to the project directory.
+    pub path: PathBuf,
+    pub contents: String,
+}
+
+/// A template's files, with its variables substituted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    pub name: String,
+    pub files: Vec<TemplateFile>,
+}
+
+impl Template {
+    pub fn load(source: &TemplateSource, variables: &TemplateVariables) -> anyhow::Result<Self> {
+        match source {
+            TemplateSource::Builtin(name) => {
+                let builtin = BUILTIN_TEMPLATES
+                    .iter()
+                    .find(|b| b.name == *name)
+                    .ok_or_else(|| anyhow::anyhow!("no built-in template named '{name}'"))?;
+                let mut files = vec![TemplateFile {
+                    path: PathBuf::from("src").join("main.rs"),
+                    contents: variables.substitute(builtin.program),
+                }];
+                files.extend(
+                    builtin
+                        .fixtures
+                        .iter()
+                        .map(|(file_name, contents)| TemplateFile {
+                            path: Path::new(FIXTURES_DIR).join(file_name),
+                            contents: variables.substitute(contents),
+                        }),
+                );
+                Ok(Self {
+                    name: builtin.name.to_string(),
+                    files,
+                })
+            }
+            TemplateSource::Path(dir) => Self::load_dir(dir, variables),
+            TemplateSource::Git(url) => {
+                let checkout = env::temp_dir().join(format!(
+                    "lasr-template-{}-{}",
+                    std::process::id(),
+                    std::time::SystemTime::now()
+                        .duration_since(std::time::UNIX_EPOCH)?
+                        .as_nanos()
+                ));
+                let output = Command::new("git")
+                    .args(["clone", "--depth", "1", url])
+                    .arg(&checkout)
+                    .output()
+                    .map_err(|e| anyhow::anyhow!("failed to run git clone: {e:?}"))?;
+                let template = if output.status.success() {
+                    Self::load_dir(&checkout, variables)
+                } else {
+                    Err(anyhow::anyhow!(
+                        "failed to clone template {url}: {}",
+                        String::from_utf8_lossy(&output.stderr).trim()
+                    ))
+                };
+                if checkout.exists() {
+                    fs::remove_dir_all(&checkout)
+                        .map_err(|e| anyhow::anyhow!("failed to remove {checkout:?}: {e:?}"))?;
+                }
+                template
+            }
+        }
+    }
+
+    fn load_dir(dir: &Path, variables: &TemplateVariables) -> anyhow::Result<Self> {
+        let manifest = read_manifest(dir)?;
+        let read = |source: &Path| -> anyhow::Result<String> {
+            if source.is_absolute() || source.components().any(|c| c.as_os_str() == "..") {
+                bail!("template file {source:?} must be relative to the template directory");
+            }
+            let path = dir.join(source);
+            fs::read_to_string(&path)
+                .map(|contents| variables.substitute(&contents))
+                .map_err(|e| anyhow::anyhow!("failed to read template file {path:?}: {e:?}"))
+        };
+        let destination = |path: &Path| -> anyhow::Result<PathBuf> {
+            let path = PathBuf::from(variables.substitute(&path.to_string_lossy()));
+            if path.is_absolute() || path.components().any(|c| c.as_os_str() == "..") {
+                bail!("template destination {path:?} must stay inside the project");
+            }
+            Ok(path)
+        };
+
+        let mut files = Vec::new();
+        for file in &manifest.files {
+            files.push(TemplateFile {
+                path: destination(file.destination.as_ref().unwrap_or(&file.source))?,
+                contents: read(&file.source)?,
+            });
+        }
+        for fixture in &manifest.fixtures {
+            let file_name = fixture
+                .file_name()
+                .ok_or_else(|| anyhow::anyhow!("fixture {fixture:?} has no file name"))?;
+            files.push(TemplateFile {
+                path: Path::new(FIXTURES_DIR).join(destination(Path::new(file_name))?),
+                contents: read(fixture)?,
+            });
+        }
+        Ok(Self {
+            name: manifest.name,
+            files,
+        })
+    }
+
+    /// Writes the template's files into `project_dir`, overwriting existing files.
+    pub fn write(&self, project_dir: &Path) -> anyhow::Result<()> {
+        for file in &self.files {
+            let path = project_dir.join(&file.path);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&path, &file.contents)
+                .map_err(|e| anyhow::anyhow!("failed to write {path:?}: {e:?}"))?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_directory_template() -> Result<(), anyhow::Error> {
+    let dir = env::temp_dir().join(format!("lasr-template-test-{}", std::process::id()));
+    fs::create_dir_all(dir.join("fixtures"))?;
+    fs::write(
+        dir.join(TEMPLATE_MANIFEST_FILENAME),
+        r#"{
+            "name": "starter",
+            "files": [{ "source": "program.rs", "destination": "src/{{crate_name}}.rs" }],
+            "fixtures": ["fixtures/create.json"]
+        }"#,
+    )?;
+    fs::write(dir.join("program.rs"), "// {{name}} ({{symbol}})")?;
+    fs::write(
+        dir.join("fixtures/create.json"),
+        r#"{"symbol":"{{symbol}}"}"#,
+    )?;
+
+    let source = TemplateSource::parse(&dir.to_string_lossy())?;
+    let template = Template::load(&source, &TemplateVariables::new("my-token", None))?;
+    fs::remove_dir_all(&dir)?;
+
+    assert_eq!(template.name, "starter");
+    assert_eq!(
+        template.files,
+        vec![
+            TemplateFile {
+                path: PathBuf::from("src/my_token.rs"),
+                contents: "// my-token (MYTO)".to_string(),
+            },
+            TemplateFile {
+                path: Path::new(FIXTURES_DIR).join("create.json"),
+                contents: r#"{"symbol":"MYTO"}"#.to_string(),
+            },
+        ]
+    );
+
+    assert_eq!(
+        TemplateSource::parse("non-fungible")?,
+        TemplateSource::Builtin("non_fungible")
+    );
+    assert!(matches!(
+        TemplateSource::parse("https://github.com/acme/lasr-starter")?,
+        TemplateSource::Git(_)
+    ));
+    assert!(TemplateSource::parse("no-such-template").is_err());
+
+    Ok(())
+}
@@ -0,0 +1,359 @@
+//! In-process validation of the `lasr_types::Outputs` a program prints, so
+//! `lasr test` does not depend on an external `lasr_cli` binary.
+//!
+//! Outputs are first deserialized into `lasr_types::Outputs`, then checked
+//! semantically against the inputs the program was called with. Every issue
+//! is reported with the JSON path of the offending value.
+use std::fmt;
+
+use lasr_types::{Inputs, Outputs, U256};
+use serde_json::Value;
+
+use crate::lasrctl::builders::program::parse_u256;
+
+/// A semantic problem found in a program's outputs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationIssue {
+    /// JSON path of the offending value, e.g. `$.instructions[1].transfer.from`.
+    pub path: String,
+    pub message: String,
+}
+
+impl ValidationIssue {
+    fn new(path: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+/// Deserializes `outputs_json` into `Outputs`, failing with the line and
+/// column of the first malformed value.
+pub fn parse_outputs(outputs_json: &str) -> anyhow::Result<Outputs> {
+    serde_json::from_str(outputs_json).map_err(|e| {
+        anyhow::anyhow!(
+            "program outputs are not valid lasr_types::Outputs at line {} column {}: {e}",
+            e.line(),
+            e.column()
+        )
+    })
+}
+
+/// Parses and validates a program's outputs against the inputs it was called
+/// with, returning every semantic issue found.
+pub fn validate_outputs(
+    outputs_json: &str,
+    inputs: &Inputs,
+) -> anyhow::Result<Vec<ValidationIssue>> {
+    let outputs = parse_outputs(outputs_json)?;
+    let outputs = serde_json::to_value(&outputs)?;
+    let inputs = serde_json::to_value(inputs)?;
+    Ok(OutputsValidator::new(&inputs).validate(&outputs))
+}
+
+/// Checks serialized `Outputs` against serialized `Inputs`.
+pub struct OutputsValidator {
+    inputs: Value,
+    caller: Option<String>,
+    known_accounts: Vec<String>,
+}
+
+impl OutputsValidator {
+    pub fn new(inputs: &Value) -> Self {
+        let address = |pointer: &str| {
+            inputs
+                .pointer(pointer)
+                .and_then(Value::as_str)
+                .map(str::to_lowercase)
+        };
+        let caller = address("/transaction/from");
+        let known_accounts = [
+            caller.clone(),
+            address("/transaction/programId"),
+            address("/accountInfo/ownerAddress"),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+
+        Self {
+            inputs: inputs.clone(),
+            caller,
+            known_accounts,
+        }
+    }
+
+    pub fn validate(&self, outputs: &Value) -> Vec<ValidationIssue> {
+        let mut issues = Vec::new();
+
+        if outputs.get("computeInputs") != Some(&self.inputs) {
+            issues.push(ValidationIssue::new(
+                "$.computeInputs",
+                "does not echo the inputs the program was called with",
+            ));
+        }
+
+        let instructions = outputs
+            .get("instructions")
+            .and_then(Value::as_array)
+            .cloned()
+            .unwrap_or_default();
+        if instructions.is_empty() {
+            issues.push(ValidationIssue::new(
+                "$.instructions",
+                "program produced no instructions",
+            ));
+        }
+
+        for (index, instruction) in instructions.iter().enumerate() {
+            let Some((kind, body)) = instruction.as_object().and_then(|obj| obj.iter().next())
+            else {
+                continue;
+            };
+            let path = format!("$.instructions[{index}].{kind}");
+            match kind.as_str() {
+                "create" => self.validate_create(&path, body, &mut issues),
+                "transfer" => self.validate_transfer(&path, body, &mut issues),
+                "burn" => self.validate_burn(&path, body, &mut issues),
+                "update" => self.validate_update(&path, body, &mut issues),
+                _ => {}
+            }
+        }
+
+        issues
+    }
+
+    fn validate_create(&self, path: &str, create: &Value, issues: &mut Vec<ValidationIssue>) {
+        let total = parse_amount(path, create, "totalSupply", issues);
+        let initialized = parse_amount(path, create, "initializedSupply", issues);
+        if let (Some(total), Some(initialized)) = (total, initialized) {
+            if initialized > total {
+                issues.push(ValidationIssue::new(
+                    format!("{path}.initializedSupply"),
+                    format!("initializedSupply {initialized} exceeds totalSupply {total}"),
+                ));
+            }
+        }
+
+        let distributions = create
+            .get("distribution")
+            .and_then(Value::as_array)
+            .cloned()
+            .unwrap_or_default();
+        let mut distributed = U256::zero();
+        for (index, distribution) in distributions.iter().enumerate() {
+            let path = format!("{path}.distribution[{index}]");
+            let Some(amount) = parse_amount(&path, distribution, "amount", issues) else {
+                continue;
+            };
+            let token_ids = token_ids(distribution);
+            if !token_ids.is_empty() && U256::from(token_ids.len() as u64) != amount {
+                issues.push(ValidationIssue::new(
+                    format!("{path}.tokenIds"),
+                    format!(
+                        "distributes {} token IDs but an amount of {amount}",
+                        token_ids.len()
+                    ),
+                ));
+            }
+            distributed = distributed.saturating_add(amount);
+        }
+        if let Some(initialized) = initialized {
+            if distributed > initialized {
+                issues.push(ValidationIssue::new(
+                    format!("{path}.distribution"),
+                    format!("distributes {distributed}, more than initializedSupply {initialized}"),
+                ));
+            }
+        }
+    }
+
+    fn validate_transfer(&self, path: &str, transfer: &Value, issues: &mut Vec<ValidationIssue>) {
+        let amount = parse_amount(path, transfer, "amount", issues).unwrap_or_default();
+        if amount.is_zero() && token_ids(transfer).is_empty() {
+            issues.push(ValidationIssue::new(
+                format!("{path}.amount"),
+                "transfers neither an amount nor any token IDs",
+            ));
+        }
+        self.validate_debited_account(path, transfer, issues);
+        if transfer.get("from").is_some() && transfer.get("from") == transfer.get("to") {
+            issues.push(ValidationIssue::new(
+                format!("{path}.to"),
+                "transfers to the account it debits",
+            ));
+        }
+    }
+
+    fn validate_burn(&self, path: &str, burn: &Value, issues: &mut Vec<ValidationIssue>) {
+        let amount = parse_amount(path, burn, "amount", issues).unwrap_or_default();
+        if amount.is_zero() && token_ids(burn).is_empty() {
+            issues.push(ValidationIssue::new(
+                format!("{path}.amount"),
+                "burns neither an amount nor any token IDs",
+            ));
+        }
+        let caller = burn
+            .get("caller")
+            .and_then(account)
+            .map(|caller| caller.to_lowercase());
+        if caller.is_some() && caller != self.caller {
+            issues.push(ValidationIssue::new(
+                format!("{path}.caller"),
+                "is not the transaction's sender",
+            ));
+        }
+        self.validate_debited_account(path, burn, issues);
+    }
+
+    fn validate_update(&self, path: &str, update: &Value, issues: &mut Vec<ValidationIssue>) {
+        let updates = update
+            .get("updates")
+            .and_then(Value::as_array)
+            .cloned()
+            .unwrap_or_default();
+        if updates.is_empty() {
+            issues.push(ValidationIssue::new(
+                format!("{path}.updates"),
+                "contains no updates",
+            ));
+        }
+        for (index, update) in updates.iter().enumerate() {
+            let Some((kind, body)) = update.as_object().and_then(|obj| obj.iter().next()) else {
+                continue;
+            };
+            let is_empty = body
+                .get("updates")
+                .and_then(Value::as_array)
+                .filter(|field_updates| !field_updates.is_empty())
+                .is_none();
+            if is_empty {
+                issues.push(ValidationIssue::new(
+                    format!("{path}.updates[{index}].{kind}.updates"),
+                    "contains no field updates",
+                ));
+            }
+        }
+    }
+
+    /// Accounts may only be debited if they belong to the caller or the program.
+    fn validate_debited_account(
+        &self,
+        path: &str,
+        instruction: &Value,
+        issues: &mut Vec<ValidationIssue>,
+    ) {
+        let Some(from) = instruction.get("from") else {
+            return;
+        };
+        match account(from) {
+            Some(address) if !self.known_accounts.contains(&address.to_lowercase()) => {
+                issues.push(ValidationIssue::new(
+                    format!("{path}.from"),
+                    format!("debits {address}, which is neither the caller nor the program"),
+                ));
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The address an `AddressOrNamespace` refers to, or `None` for `this` and
+/// namespaces.
+fn account(value: &Value) -> Option<&str> {
+    match value {
+        Value::String(address) if address != "this" => Some(address),
+        Value::Object(obj) => obj.get("address").and_then(Value::as_str),
+        _ => None,
+    }
+}
+
+fn token_ids(instruction: &Value) -> Vec<Value> {
+    instruction
+        .get("tokenIds")
+        .or_else(|| instruction.get("ids"))
+        .and_then(Value::as_array)
+        .cloned()
+        .unwrap_or_default()
+}
+
+fn parse_amount(
+    path: &str,
+    instruction: &Value,
+    field: &str,
+    issues: &mut Vec<ValidationIssue>,
+) -> Option<U256> {
+    match instruction.get(field) {
+        None | Some(Value::Null) => None,
+        Some(Value::String(amount)) => match parse_u256(amount) {
+            Ok(amount) => Some(amount),
+            Err(e) => {
+                issues.push(ValidationIssue::new(
+                    format!("{path}.{field}"),
+                    format!("{e:#}"),
+                ));
+                None
+            }
+        },
+        Some(other) => {
+            issues.push(ValidationIssue::new(
+                format!("{path}.{field}"),
+                format!("expected a hex encoded U256, got {other}"),
+            ));
+            None
+        }
+    }
+}
+
+#[test]
+fn test_validate_outputs() {
+    let inputs = serde_json::json!({
+        "transaction": {
+            "from": "0x100444c7D04A842D19bc3eE63cB7b96682FF3f43",
+            "programId": "0x57234c52617e7ca8edc5577ebe3eb38d53a77607"
+        },
+        "accountInfo": { "ownerAddress": "0x482830d7655fb8465a43844fc1530a7713781b49" }
+    });
+    let outputs = serde_json::json!({
+        "computeInputs": inputs.clone(),
+        "instructions": [
+            { "create": {
+                "totalSupply": "0x10",
+                "initializedSupply": "0x20",
+                "distribution": []
+            } },
+            { "transfer": {
+                "token": "0x57234c52617e7ca8edc5577ebe3eb38d53a77607",
+                "from": { "address": "0x0000000000000000000000000000000000000001" },
+                "to": "this",
+                "amount": "0x0",
+                "ids": []
+            } },
+            { "transfer": {
+                "token": "0x57234c52617e7ca8edc5577ebe3eb38d53a77607",
+                "from": { "address": "0x100444c7d04a842d19bc3ee63cb7b96682ff3f43" },
+                "to": "this",
+                "amount": "0x1",
+                "ids": []
+            } }
+        ]
+    });
+
+    let issues = OutputsValidator::new(&inputs).validate(&outputs);
+    let paths: Vec<&str> = issues.iter().map(|issue| issue.path.as_str()).collect();
+    assert_eq!(
+        paths,
+        vec![
+            "$.instructions[0].create.initializedSupply",
+            "$.instructions[1].transfer.amount",
+            "$.instructions[1].transfer.from",
+        ]
+    );
+}
@@ -0,0 +1,425 @@
+//! Named keypairs stored under `.lasr/wallet`.
+//!
+//! Each keypair lives in `.lasr/wallet/<name>.json`. The default keypair is
+//! named in `.lasr/wallet/default`, falling back to `keypair`, so that
+//! `.lasr/wallet/keypair.json` is used by every command unless told otherwise.
+//!
+//! A keypair file is either a passphrase encrypted `Keystore` or, for
+//! keypairs created before keystores were introduced, plaintext JSON. The
+//! latter can be migrated with `lasr wallet encrypt`.
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::bail;
+use bip32::XPrv;
+use bip39::Mnemonic;
+use secp256k1::{PublicKey, Secp256k1, SecretKey};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    lasrctl::{
+        keystore::{read_passphrase, Keystore, ScryptParams},
+        signing::{address_from_public_key, load_secret_key, parse_secret_key},
+    },
+    scripts::consts::{KEYPAIR_FILENAME, WALLET_PATH},
+};
+
+/// The name of the keypair used when no default has been set.
+pub const DEFAULT_KEYPAIR_NAME: &str = "keypair";
+/// The file in the wallet directory holding the name of the default keypair.
+pub const DEFAULT_POINTER_FILENAME: &str = "default";
+/// The BIP-44 derivation path used for keypairs derived from a mnemonic.
+pub const DERIVATION_PATH: &str = "m/44'/60'/0'/0/0";
+
+/// The contents of a keypair file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeypairFile {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mnemonic: Option<String>,
+    pub secret_key: String,
+    pub public_key: String,
+    pub address: String,
+}
+
+impl KeypairFile {
+    pub fn from_secret_key(secret_key: &SecretKey, mnemonic: Option<String>) -> Self {
+        let public_key = PublicKey::from_secret_key(&Secp256k1::signing_only(), secret_key);
+        Self {
+            mnemonic,
+            secret_key: format!("0x{}", hex::encode(secret_key.secret_bytes())),
+            public_key: format!("0x{}", hex::encode(public_key.serialize())),
+            address: address_from_public_key(&public_key),
+        }
+    }
+
+    /// Generates a keypair from a fresh 12 word mnemonic.
+    pub fn generate() -> anyhow::Result<Self> {
+        let mnemonic = Mnemonic::generate(12)
+            .map_err(|e| anyhow::anyhow!("failed to generate mnemonic: {e:?}"))?;
+        Self::from_mnemonic(&mnemonic.to_string())
+    }
+
+    /// Derives the keypair at `DERIVATION_PATH` from a BIP-39 mnemonic.
+    pub fn from_mnemonic(phrase: &str) -> anyhow::Result<Self> {
+        let mnemonic = Mnemonic::parse_normalized(phrase.trim())
+            .map_err(|e| anyhow::anyhow!("invalid mnemonic: {e:?}"))?;
+        let path = DERIVATION_PATH
+            .parse()
+            .map_err(|e| anyhow::anyhow!("invalid derivation path: {e:?}"))?;
+        let xprv = XPrv::derive_from_path(mnemonic.to_seed(""), &path)
+            .map_err(|e| anyhow::anyhow!("failed to derive key from mnemonic: {e:?}"))?;
+        let secret_key = SecretKey::from_slice(&xprv.to_bytes())
+            .map_err(|e| anyhow::anyhow!("derived an invalid secret key: {e:?}"))?;
+        Ok(Self::from_secret_key(
+            &secret_key,
+            Some(mnemonic.to_string()),
+        ))
+    }
+
+    pub fn from_secret_key_hex(secret_key: &str) -> anyhow::Result<Self> {
+        Ok(Self::from_secret_key(&parse_secret_key(secret_key)?, None))
+    }
+
+    pub fn secret_key(&self) -> anyhow::Result<SecretKey> {
+        parse_secret_key(&self.secret_key)
+    }
+
+    /// Encrypts the secret key, and the mnemonic if there is one, into a keystore.
+    pub fn encrypt(&self, passphrase: &str) -> anyhow::Result<Keystore> {
+        Keystore::encrypt(
+            &self.secret_key()?.secret_bytes(),
+            self.mnemonic.as_deref(),
+            &self.address,
+            passphrase,
+            ScryptParams::default(),
+        )
+    }
+}
+
+/// A keypair file as stored on disk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StoredKeypair {
+    Plaintext(KeypairFile),
+    Encrypted(Keystore),
+}
+
+impl StoredKeypair {
+    pub fn address(&self) -> String {
+        match self {
+            StoredKeypair::Plaintext(keypair) => keypair.address.clone(),
+            StoredKeypair::Encrypted(keystore) => format!("0x{}", keystore.address),
+        }
+    }
+
+    pub fn is_encrypted(&self) -> bool {
+        matches!(self, StoredKeypair::Encrypted(_))
+    }
+
+    /// Returns the keypair, decrypting it with a passphrase read from
+    /// `LASR_KEYSTORE_PASSPHRASE` or prompted for if it is encrypted.
+    pub fn unlock(self) -> anyhow::Result<KeypairFile> {
+        match self {
+            StoredKeypair::Plaintext(keypair) => Ok(keypair),
+            StoredKeypair::Encrypted(keystore) => {
+                let passphrase =
+                    read_passphrase(&format!("Passphrase for 0x{}", keystore.address))?;
+                unlock_keystore(&keystore, &passphrase)
+            }
+        }
+    }
+}
+
+pub fn unlock_keystore(keystore: &Keystore, passphrase: &str) -> anyhow::Result<KeypairFile> {
+    let decrypted = keystore.decrypt(passphrase)?;
+    let secret_key = SecretKey::from_slice(&decrypted.secret_key)
+        .map_err(|e| anyhow::anyhow!("keystore holds an invalid secret key: {e:?}"))?;
+    Ok(KeypairFile::from_secret_key(
+        &secret_key,
+        decrypted.mnemonic,
+    ))
+}
+
+/// The keypairs in a project's wallet directory.
+#[derive(Debug, Clone)]
+pub struct WalletDir {
+    path: PathBuf,
+}
+
+impl Default for WalletDir {
+    fn default() -> Self {
+        Self::new(WALLET_PATH)
+    }
+}
+
+impl WalletDir {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// The wallet directory of the project at `project_dir`.
+    pub fn in_project(project_dir: &Path) -> Self {
+        Self::new(project_dir.join(WALLET_PATH))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn keypair_path(&self, name: &str) -> PathBuf {
+        if name == DEFAULT_KEYPAIR_NAME {
+            return self.path.join(KEYPAIR_FILENAME);
+        }
+        self.path.join(format!("{name}.json"))
+    }
+
+    /// The name of the default keypair.
+    pub fn default_name(&self) -> String {
+        fs::read_to_string(self.path.join(DEFAULT_POINTER_FILENAME))
+            .ok()
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| DEFAULT_KEYPAIR_NAME.to_string())
+    }
+
+    pub fn set_default(&self, name: &str) -> anyhow::Result<()> {
+        if !self.keypair_path(name).exists() {
+            bail!("no keypair named '{name}' in {:?}", self.path);
+        }
+        fs::write(
+            self.path.join(DEFAULT_POINTER_FILENAME),
+            format!("{name}\n"),
+        )
+        .map_err(|e| anyhow::anyhow!("failed to set default keypair: {e:?}"))
+    }
+
+    /// Resolves the keypair file for a command: an explicit path wins, then a
+    /// named keypair, then the default keypair.
+    pub fn resolve(&self, name: Option<&str>, keypair_path: Option<&Path>) -> PathBuf {
+        match (keypair_path, name) {
+            (Some(keypair_path), _) => keypair_path.to_path_buf(),
+            (None, Some(name)) => self.keypair_path(name),
+            (None, None) => self.keypair_path(&self.default_name()),
+        }
+    }
+
+    /// The names of every keypair in the wallet, sorted.
+    pub fn list(&self) -> anyhow::Result<Vec<String>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        let mut names: Vec<String> = fs::read_dir(&self.path)
+            .map_err(|e| anyhow::anyhow!("failed to read wallet directory {:?}: {e:?}", self.path))?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .filter_map(|path| {
+                path.file_stem()
+                    .map(|stem| stem.to_string_lossy().into_owned())
+            })
+            .collect();
+        names.sort();
+        Ok(names)
+    }
+
+    /// Loads and, if necessary, decrypts the keypair `name`.
+    pub fn load(&self, name: &str) -> anyhow::Result<KeypairFile> {
+        read_keypair_file(&self.keypair_path(name))
+    }
+
+    pub fn load_stored(&self, name: &str) -> anyhow::Result<StoredKeypair> {
+        read_stored_keypair(&self.keypair_path(name))
+    }
+
+    /// Saves `keypair` as `name`, encrypted under `passphrase` if one is given,
+    /// refusing to overwrite an existing keypair.
+    pub fn save(
+        &self,
+        name: &str,
+        keypair: &KeypairFile,
+        passphrase: Option<&str>,
+    ) -> anyhow::Result<PathBuf> {
+        let keypair_path = self.keypair_path(name);
+        if keypair_path.exists() {
+            bail!("a keypair named '{name}' already exists at {keypair_path:?}");
+        }
+        write_keypair_file(&keypair_path, keypair, passphrase)?;
+        Ok(keypair_path)
+    }
+
+    /// Encrypts the plaintext keypair `name` in place. Returns `false` if it
+    /// was already encrypted.
+    pub fn encrypt(&self, name: &str, passphrase: &str) -> anyhow::Result<bool> {
+        match self.load_stored(name)? {
+            StoredKeypair::Encrypted(_) => Ok(false),
+            StoredKeypair::Plaintext(keypair) => {
+                write_keypair_file(&self.keypair_path(name), &keypair, Some(passphrase))?;
+                Ok(true)
+            }
+        }
+    }
+
+    /// Replaces the keypair `name` with a freshly generated one, keeping the
+    /// old keypair as `<name>.<unix time>.json.bak`. The new keypair is
+    /// encrypted under `passphrase` if one is given.
+    pub fn rotate(
+        &self,
+        name: &str,
+        passphrase: Option<&str>,
+    ) -> anyhow::Result<(PathBuf, KeypairFile)> {
+        let keypair_path = self.keypair_path(name);
+        if !keypair_path.exists() {
+            bail!("no keypair named '{name}' in {:?}", self.path);
+        }
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default();
+        let backup_path = self.path.join(format!("{name}.{now}.json.bak"));
+        fs::rename(&keypair_path, &backup_path)
+            .map_err(|e| anyhow::anyhow!("failed to back up {keypair_path:?}: {e:?}"))?;
+        // Keypairs written by older versions may be world readable.
+        restrict_permissions(&backup_path, PRIVATE_FILE_MODE)?;
+
+        let keypair = KeypairFile::generate()?;
+        write_keypair_file(&keypair_path, &keypair, passphrase)?;
+        Ok((backup_path, keypair))
+    }
+}
+
+/// Reads and, if necessary, decrypts the keypair file at `keypair_path`.
+pub fn read_keypair_file(keypair_path: &Path) -> anyhow::Result<KeypairFile> {
+    read_stored_keypair(keypair_path)?.unlock()
+}
+
+pub fn read_stored_keypair(keypair_path: &Path) -> anyhow::Result<StoredKeypair> {
+    let contents = fs::read_to_string(keypair_path)
+        .map_err(|e| anyhow::anyhow!("failed to read keypair file {keypair_path:?}: {e:?}"))?;
+    let json: serde_json::Value = serde_json::from_str(&contents)
+        .map_err(|e| anyhow::anyhow!("failed to parse keypair file {keypair_path:?}: {e:?}"))?;
+    if json.get("crypto").is_some() {
+        return serde_json::from_value(json)
+            .map(StoredKeypair::Encrypted)
+            .map_err(|e| anyhow::anyhow!("failed to parse keystore {keypair_path:?}: {e:?}"));
+    }
+    match serde_json::from_value(json) {
+        Ok(keypair) => Ok(StoredKeypair::Plaintext(keypair)),
+        // Keypair files written by older versions of `lasr init` hold a
+        // `lasr_wallet::WalletInfo`, which only needs its secret key carried over.
+        Err(_) => Ok(StoredKeypair::Plaintext(KeypairFile::from_secret_key(
+            &load_secret_key(keypair_path)?,
+            None,
+        ))),
+    }
+}
+
+/// Writes `keypair` to `keypair_path`, as a keystore if a passphrase is given.
+pub fn write_keypair_file(
+    keypair_path: &Path,
+    keypair: &KeypairFile,
+    passphrase: Option<&str>,
+) -> anyhow::Result<()> {
+    if let Some(parent) = keypair_path.parent() {
+        create_private_dir(parent)?;
+    }
+    let contents = match passphrase {
+        Some(passphrase) => serde_json::to_string_pretty(&keypair.encrypt(passphrase)?)?,
+        None => serde_json::to_string_pretty(keypair)?,
+    };
+    write_private_file(keypair_path, format!("{contents}\n").as_bytes())
+        .map_err(|e| anyhow::anyhow!("failed to write keypair file {keypair_path:?}: {e:?}"))
+}
+
+/// Permissions of the wallet directory: only its owner may list or enter it.
+const PRIVATE_DIR_MODE: u32 = 0o700;
+
+/// Permissions of keypair files: only their owner may read or write them.
+const PRIVATE_FILE_MODE: u32 = 0o600;
+
+/// Creates `dir` and its parents, restricting `dir` itself to its owner.
+fn create_private_dir(dir: &Path) -> anyhow::Result<()> {
+    fs::create_dir_all(dir)
+        .map_err(|e| anyhow::anyhow!("failed to create wallet directory {dir:?}: {e:?}"))?;
+    restrict_permissions(dir, PRIVATE_DIR_MODE)
+}
+
+/// Writes `contents` to `path`, creating the file readable by its owner only.
+/// An existing file is truncated and has its permissions tightened as well.
+fn write_private_file(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, PRIVATE_FILE_MODE);
+    let mut file = options.open(path)?;
+    #[cfg(unix)]
+    file.set_permissions(std::os::unix::fs::PermissionsExt::from_mode(
+        PRIVATE_FILE_MODE,
+    ))?;
+    file.write_all(contents)
+}
+
+#[cfg(unix)]
+fn restrict_permissions(path: &Path, mode: u32) -> anyhow::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    fs::set_permissions(path, fs::Permissions::from_mode(mode))
+        .map_err(|e| anyhow::anyhow!("failed to restrict permissions of {path:?}: {e:?}"))
+}
+
+#[cfg(not(unix))]
+fn restrict_permissions(_path: &Path, _mode: u32) -> anyhow::Result<()> {
+    Ok(())
+}
+
+#[test]
+fn test_keypair_from_mnemonic() -> Result<(), anyhow::Error> {
+    // The well known test mnemonic, whose first account is widely published.
+    let keypair =
+        KeypairFile::from_mnemonic("test test test test test test test test test test test junk")?;
+    assert_eq!(
+        keypair.address,
+        "0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266"
+    );
+    assert_eq!(
+        KeypairFile::from_secret_key_hex(&keypair.secret_key)?.address,
+        keypair.address
+    );
+
+    let wallet = WalletDir::new("/tmp/.lasr/wallet");
+    assert_eq!(
+        wallet.keypair_path(DEFAULT_KEYPAIR_NAME),
+        PathBuf::from("/tmp/.lasr/wallet/keypair.json")
+    );
+    assert_eq!(
+        wallet.resolve(Some("deployer"), None),
+        PathBuf::from("/tmp/.lasr/wallet/deployer.json")
+    );
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn test_keypair_file_permissions() -> Result<(), anyhow::Error> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mode = |path: &Path| -> anyhow::Result<u32> {
+        Ok(fs::metadata(path)?.permissions().mode() & 0o777)
+    };
+    let dir = std::env::temp_dir().join(format!("lasr-wallet-permissions-{}", std::process::id()));
+    let wallet = WalletDir::new(dir.join("wallet"));
+    let keypair_path = wallet.save(DEFAULT_KEYPAIR_NAME, &KeypairFile::generate()?, None)?;
+    assert_eq!(mode(wallet.path())?, PRIVATE_DIR_MODE);
+    assert_eq!(mode(&keypair_path)?, PRIVATE_FILE_MODE);
+
+    // A keypair left world readable by an older version is tightened on rotate.
+    fs::set_permissions(&keypair_path, fs::Permissions::from_mode(0o644))?;
+    let (backup_path, _) = wallet.rotate(DEFAULT_KEYPAIR_NAME, None)?;
+    assert_eq!(mode(&backup_path)?, PRIVATE_FILE_MODE);
+    assert_eq!(mode(&keypair_path)?, PRIVATE_FILE_MODE);
+
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+}
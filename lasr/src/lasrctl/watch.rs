@@ -0,0 +1,107 @@
+//! Change detection for `lasr dev`, by polling file modification times.
+//!
+//! Polling keeps the watcher dependency free and behaves the same on every
+//! platform; the watched trees (a program's `src/` and its fixtures) are small
+//! enough that walking them a few times a second is cheap.
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+/// Directories never descended into while watching.
+const IGNORED_DIRS: &[&str] = &["target", "node_modules"];
+
+/// The modification times of every file under a set of watched paths.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    files: BTreeMap<PathBuf, SystemTime>,
+}
+
+impl Snapshot {
+    /// Records every file under `paths`. Missing paths are skipped, so a
+    /// directory that appears later is picked up by the next snapshot.
+    pub fn take(paths: &[PathBuf]) -> Self {
+        let mut snapshot = Self::default();
+        for path in paths {
+            snapshot.record(path);
+        }
+        snapshot
+    }
+
+    fn record(&mut self, path: &Path) {
+        let Ok(metadata) = fs::metadata(path) else {
+            return;
+        };
+        if metadata.is_file() {
+            if let Ok(modified) = metadata.modified() {
+                self.files.insert(path.to_path_buf(), modified);
+            }
+            return;
+        }
+        let Ok(entries) = fs::read_dir(path) else {
+            return;
+        };
+        for entry in entries.filter_map(Result::ok) {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            let is_dir = entry.file_type().is_ok_and(|file_type| file_type.is_dir());
+            if is_dir && (name.starts_with('.') || IGNORED_DIRS.contains(&name.as_ref())) {
+                continue;
+            }
+            self.record(&entry.path());
+        }
+    }
+
+    /// Every recorded file, in path order.
+    pub fn paths(&self) -> impl Iterator<Item = &Path> {
+        self.files.keys().map(PathBuf::as_path)
+    }
+
+    /// The files added, modified or removed since `previous`, in path order.
+    pub fn changes_since(&self, previous: &Snapshot) -> Vec<PathBuf> {
+        let mut changes: Vec<PathBuf> = self
+            .files
+            .iter()
+            .filter(|(path, modified)| previous.files.get(*path) != Some(modified))
+            .map(|(path, _)| path.clone())
+            .chain(
+                previous
+                    .files
+                    .keys()
+                    .filter(|path| !self.files.contains_key(*path))
+                    .cloned(),
+            )
+            .collect();
+        changes.sort();
+        changes
+    }
+}
+
+#[test]
+fn test_snapshot_changes() -> Result<(), anyhow::Error> {
+    let dir = std::env::temp_dir().join(format!("lasr-watch-test-{}", std::process::id()));
+    let src = dir.join("src");
+    fs::create_dir_all(src.join("nested"))?;
+    fs::create_dir_all(dir.join("target"))?;
+    fs::write(src.join("main.rs"), "fn main() {}")?;
+    fs::write(src.join("nested").join("lib.rs"), "")?;
+    fs::write(dir.join("target").join("ignored"), "")?;
+
+    let watched = vec![dir.clone(), dir.join("missing.json")];
+    let before = Snapshot::take(&watched);
+    assert_eq!(before.files.len(), 2);
+    assert!(Snapshot::take(&watched).changes_since(&before).is_empty());
+
+    fs::remove_file(src.join("nested").join("lib.rs"))?;
+    fs::write(dir.join("missing.json"), "{}")?;
+    let after = Snapshot::take(&watched);
+    assert_eq!(
+        after.changes_since(&before),
+        vec![dir.join("missing.json"), src.join("nested").join("lib.rs")]
+    );
+
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+}
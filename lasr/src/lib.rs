@@ -1,3 +1,6 @@
+// Lets code generated by `lasr-derive` refer to `::lasr_rust` inside this crate too.
+extern crate self as lasr_rust;
+
 pub mod examples;
 pub mod lasrctl;
 pub mod scripts;
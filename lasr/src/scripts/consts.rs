@@ -11,3 +11,5 @@ pub const FAUCET_URL: &str = "https://playground.versatus.io/";
 pub const WALLET_PATH: &str = ".lasr/wallet";
 pub const KEYPAIR_FILENAME: &str = "keypair.json";
 pub const PROGRAM_OUTPUT_FILENAME: &str = "temp.json";
+pub const LASR_RPC_URL_LOCAL: &str = "http://127.0.0.1:9292";
+pub const LASR_RUST_GIT_URL: &str = "https://github.com/versatus/versatus-rust";
@@ -10,16 +10,17 @@ pub struct BlankProgram {
 }
 
 impl BlankProgram {
-    pub fn new(_inputs: Inputs) -> Program<Inputs> {
-        Program::new()
+    /// Creates a program with the default method strategies, plus `hello`.
+    pub fn new() -> Program<Inputs> {
+        let mut program = Program::new();
+        program.register_method("hello", BlankProgram::hello);
+        program
     }
 
-    pub fn hello(inputs: Inputs) -> Result<String, anyhow::Error> {
-        let transaction = &inputs.transaction;
-        let txn_inputs: CreateTransactionInputs =
-            serde_json::from_str(&transaction.inputs().as_str())
-                .map_err(|e| anyhow::anyhow!("failed to deserialize txInputs: {e}"))?;
-
+    pub fn hello(
+        inputs: &Inputs,
+        txn_inputs: CreateTransactionInputs,
+    ) -> Result<Outputs, anyhow::Error> {
         let name = serde_json::to_string(&format!("{:?} World", txn_inputs.name))
             .map_err(|e| anyhow::anyhow!("failed to serialize txn metadata: {e:?}"))?;
         let current_time = std::time::SystemTime::now();
@@ -43,18 +44,16 @@ impl BlankProgram {
             .build();
 
         let outputs: Outputs = OutputsBuilder::new()
-            .inputs(inputs)
+            .inputs(inputs.clone())
             .add_instruction(lasr_types::Instruction::Update(update_instruction))
             .build()
             .map_err(|e| anyhow::anyhow!("failed to build computeOutputs: {e:?}"))?;
 
-        let obj = serde_json::to_string_pretty(&outputs).unwrap();
-
-        Ok(obj)
+        Ok(outputs)
     }
 
     pub fn start(inputs: Inputs) -> Result<String, anyhow::Error> {
-        let blank = Program::new();
+        let blank = BlankProgram::new();
         let outputs = blank
             .execute_method(&inputs)
             .map_err(|e| anyhow::anyhow!("failed to update program: {e:?}"))?;
@@ -65,7 +64,7 @@ impl BlankProgram {
 
 #[allow(dead_code)]
 fn main() -> anyhow::Result<()> {
-    if let Err(e) = Program::run() {
+    if let Err(e) = BlankProgram::new().run_stdin() {
         anyhow::bail!("program failed: {e:?}")
     }
     Ok(())